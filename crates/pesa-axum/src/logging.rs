@@ -0,0 +1,78 @@
+//! Picks the global logger: `env_logger`, or the tracing subscriber when traces are exported.
+#[cfg(feature = "otlp")]
+use opentelemetry_sdk::trace::SdkTracerProvider;
+
+use crate::CliArgs;
+
+pub struct Logging {
+    #[cfg(feature = "otlp")]
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Logging {
+    /// Installs the global logger, before anything else logs.
+    ///
+    /// The OTLP subscriber forwards `log` records itself and can't be installed once
+    /// `env_logger` owns the logger, so `env_logger` is only the fallback.
+    pub fn init(cli_args: &CliArgs) -> Self {
+        #[cfg(feature = "otlp")]
+        if let Some(endpoint) = cli_args.otlp_endpoint.as_deref() {
+            return match crate::telemetry::init(endpoint) {
+                Ok(provider) => Self {
+                    tracer_provider: Some(provider),
+                },
+                Err(e) => {
+                    // a half-installed subscriber may already hold the logger
+                    let _ = env_logger::try_init();
+                    log::error!("Traces won't be exported: {:#}", e);
+                    Self {
+                        tracer_provider: None,
+                    }
+                }
+            };
+        }
+        #[cfg(not(feature = "otlp"))]
+        let _ = cli_args;
+
+        env_logger::init();
+        Self {
+            #[cfg(feature = "otlp")]
+            tracer_provider: None,
+        }
+    }
+
+    /// Flushes the spans still waiting to be exported.
+    pub fn shutdown(self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.tracer_provider
+            && let Err(e) = provider.shutdown()
+        {
+            log::error!("Failed to flush the remaining traces: {}", e);
+        }
+    }
+
+    /// `std::process::exit` skips destructors, so the spans are flushed first.
+    pub fn exit(self, code: i32) -> ! {
+        self.shutdown();
+        std::process::exit(code)
+    }
+}
+
+#[cfg(all(test, feature = "otlp"))]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn exporting_traces_keeps_the_provider() {
+        let cli_args =
+            CliArgs::parse_from(["pesa-axum", "--otlp-endpoint", "http://127.0.0.1:4318"]);
+
+        let logging = Logging::init(&cli_args);
+
+        assert!(logging.tracer_provider.is_some());
+        // `log` records reach the subscriber instead of panicking on a second logger
+        log::info!("logged through the tracing subscriber");
+        assert!(log::log_enabled!(log::Level::Info));
+    }
+}
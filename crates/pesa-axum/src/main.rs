@@ -1,4 +1,7 @@
-use pesa_core::self_test::context::TestMode;
+use pesa_core::self_test::{
+    context::TestMode,
+    ui::{ContractTestOptions, SelfTestOptions},
+};
 use pesa_core::server::api::stkpush::ui::UserResponse;
 use serde::Deserialize;
 use std::path::PathBuf;
@@ -11,11 +14,11 @@ use axum::extract::{
 };
 use axum::routing::{get, post};
 use axum::{
-    http::Request,
+    http::{Request, header},
     middleware::{self, Next},
-    response::Response,
+    response::{IntoResponse, Response},
 };
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use futures_util::SinkExt;
 use futures_util::stream::StreamExt;
 use pesa_core::{
@@ -27,22 +30,33 @@ use pesa_core::{
     api_logs::{UpdateApiLogRequest, ui::ApiLogFilter},
     business::{CreateBusiness, UpdateBusiness},
     business_operators::ui::CreateOperatorPayload,
+    inbox::{CannedResponse, ui::InboxFilter},
+    logs::{RetentionPolicy, ui::LogSearch},
     projects::{CreateProject, UpdateProject},
+    recording::replay::ReplayOptions,
     settings::models::AppSettings,
-    transaction_costs::ui::TransactionCostData,
+    transaction_costs::ui::{TariffExport, TariffImport, TransactionCostData},
     transactions::{
-        TransactionNote, TransactionType,
+        ReversalOptions, TransactionNote, TransactionType,
         ui::{LipaArgs, TransactionFilter},
     },
     transactions_log::ui::HistoryFilter,
 };
 use pesa_lua::ScriptManager;
-use pesa_macros::generate_axum_rpc_handler;
+use pesa_macros::{assert_commands_match, generate_axum_rpc_handler};
 use tokio::sync::{Mutex, broadcast};
 use tower_http::cors::CorsLayer;
 use tower_http::services::{ServeDir, ServeFile};
 
 use log::{error, info};
+use logging::Logging;
+
+mod logging;
+mod prune_logs;
+mod replay;
+mod self_test;
+#[cfg(feature = "otlp")]
+mod telemetry;
 
 const TAURI_APP_ID: &str = "net.omenta.pesaplayground";
 
@@ -62,6 +76,15 @@ impl AppEventManager for AxumEventManager {
     }
 }
 
+/// Drops the events of the subcommands, which have no GUI or scripts listening.
+struct NoEvents;
+
+impl AppEventManager for NoEvents {
+    fn emit_all(&self, _event: &str, _payload: serde_json::Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
 // AxumAppState will hold the core context and the Axum-specific event manager
 #[derive(Clone)]
 pub struct AxumAppState {
@@ -108,6 +131,14 @@ async fn handle_socket(socket: WebSocket, event_manager: Arc<AxumEventManager>)
     }
 }
 
+/// Every sandbox's series and the ledger's, in the Prometheus text format.
+async fn metrics_handler(State(state): State<AxumAppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, pesa_core::metrics::CONTENT_TYPE)],
+        state.core_context.metrics.render(None),
+    )
+}
+
 // The original macro-generated handler is renamed to `rpc_handler_inner`
 generate_axum_rpc_handler! {
     rpc_handler_inner,
@@ -168,7 +199,9 @@ generate_axum_rpc_handler! {
     get_transaction_stats() => pesa_core::transactions::ui::get_transaction_stats,
     get_transaction_history(filter: HistoryFilter) => pesa_core::transactions_log::ui::get_transaction_history,
     transfer(source: Option<u32>, destination: u32, amount: i64, txn_type: TransactionType, notes: Option<TransactionNote>) => pesa_core::transactions::ui::transfer,
-    reverse(id: String) => pesa_core::transactions::ui::reverse,
+    reverse(id: String, options: Option<ReversalOptions>) => pesa_core::transactions::ui::reverse,
+    complete_reversal(id: String) => pesa_core::transactions::ui::complete_reversal,
+    cancel_reversal(id: String) => pesa_core::transactions::ui::cancel_reversal,
     lipa(args: LipaArgs) => pesa_core::transactions::ui::lipa,
 
     get_transaction_log(transaction_id: u32) => pesa_core::transactions_log::ui::get_transaction_log,
@@ -177,6 +210,9 @@ generate_axum_rpc_handler! {
     list_accounts_full_transaction_logs(accounts: Vec<u32>, limit: Option<u64>, offset: Option<u64>) => pesa_core::transactions_log::ui::list_accounts_full_transaction_logs,
     count_transaction_logs(accounts: Vec<u32>) => pesa_core::transactions_log::ui::count_transaction_logs,
 
+    audit_ledger() => pesa_core::journal::ui::audit_ledger,
+    get_journal_entries(transaction_id: String) => pesa_core::journal::ui::get_journal_entries,
+
     get_api_log(log_id: String) => pesa_core::api_logs::ui::get_api_log,
     update_api_log(log_id: String, request: UpdateApiLogRequest) => pesa_core::api_logs::ui::update_api_log,
     delete_api_log(log_id: String) => pesa_core::api_logs::ui::delete_api_log,
@@ -184,12 +220,41 @@ generate_axum_rpc_handler! {
     count_api_logs(project_id: Option<i64>, method: Option<String>, path: Option<String>, status_code: Option<i32>) => pesa_core::api_logs::ui::count_api_logs,
     get_project_api_logs(project_id: u32, filter: ApiLogFilter) => pesa_core::api_logs::ui::get_project_api_logs,
     get_api_logs_by_method(project_id: u32, method: String, limit: Option<u64>) => pesa_core::api_logs::ui::get_api_logs_by_method,
+    export_api_logs_har(filter: ApiLogFilter) => pesa_core::api_logs::ui::export_api_logs_har,
+    export_api_log_har(log_id: String) => pesa_core::api_logs::ui::export_api_log_har,
+    export_api_log_curl(log_id: String) => pesa_core::api_logs::ui::export_api_log_curl,
+
+    get_retention_policy(project_id: u32) => pesa_core::logs::ui::get_retention_policy,
+    set_retention_policy(policy: RetentionPolicy) => pesa_core::logs::ui::set_retention_policy,
+    clear_retention_policy(project_id: u32) => pesa_core::logs::ui::clear_retention_policy,
+    prune_logs(project_id: Option<u32>) => pesa_core::logs::ui::prune_logs,
+    search_logs(search: LogSearch) => pesa_core::logs::ui::search_logs,
+    get_request_timeline(id: String) => pesa_core::timeline::ui::get_request_timeline,
+
+    list_inboxes(project_id: u32) => pesa_core::inbox::ui::list_inboxes,
+    list_inbox_requests(filter: InboxFilter) => pesa_core::inbox::ui::list_inbox_requests,
+    clear_inbox(project_id: u32, inbox: Option<String>) => pesa_core::inbox::ui::clear_inbox,
+    get_inbox_response(project_id: u32, inbox: String) => pesa_core::inbox::ui::get_inbox_response,
+    set_inbox_response(project_id: u32, inbox: String, response: CannedResponse) => pesa_core::inbox::ui::set_inbox_response,
+    get_inbox_url(project_id: u32, inbox: String) => pesa_core::inbox::ui::get_inbox_url,
+
+    start_recording(project_id: u32, name: Option<String>) => pesa_core::recording::ui::start_recording,
+    stop_recording(project_id: u32) => pesa_core::recording::ui::stop_recording,
+    recording_status(project_id: u32) => pesa_core::recording::ui::recording_status,
+    list_sessions() => pesa_core::recording::ui::list_sessions,
+    get_session(name: String) => pesa_core::recording::ui::get_session,
+    delete_session(name: String) => pesa_core::recording::ui::delete_session,
+    replay_session(options: ReplayOptions) => pesa_core::recording::ui::replay_session,
 
     create_transaction_cost(data: TransactionCostData) => pesa_core::transaction_costs::ui::create_transaction_cost,
     list_transaction_costs() => pesa_core::transaction_costs::ui::list_transaction_costs,
     update_transaction_cost(id: i32, data: TransactionCostData) => pesa_core::transaction_costs::ui::update_transaction_cost,
     delete_transaction_cost(id: i32) => pesa_core::transaction_costs::ui::delete_transaction_cost,
-    calculate_transaction_fee(txn_type: TransactionType, amount: i64) => pesa_core::transaction_costs::ui::calculate_transaction_fee,
+    calculate_transaction_fee(txn_type: TransactionType, amount: i64, business_id: Option<u32>) => pesa_core::transaction_costs::ui::calculate_transaction_fee,
+    list_tariff_versions() => pesa_core::transaction_costs::ui::list_tariff_versions,
+    import_tariff_schedule(input: TariffImport) => pesa_core::transaction_costs::ui::import_tariff_schedule,
+    export_tariff_schedule(input: TariffExport) => pesa_core::transaction_costs::ui::export_tariff_schedule,
+    preview_tariff_schedule(input: TariffImport) => pesa_core::transaction_costs::ui::preview_tariff_schedule,
 
     resolve_stk_prompt(checkout_id: String, result: UserResponse) => pesa_core::server::api::stkpush::ui::resolve_stk_prompt,
     #[no_context]
@@ -210,9 +275,15 @@ generate_axum_rpc_handler! {
     get_mmf_account_by_business_id(business_id: u32) => pesa_core::accounts::mmf_accounts::ui::get_mmf_account_by_business_id,
     revenue_settlement(business_id: u32) => pesa_core::business::ui::revenue_settlement,
 
-    run_self_tests(mode: TestMode) => pesa_core::self_test::ui::run_self_tests
+    run_self_tests(mode: TestMode) => pesa_core::self_test::ui::run_self_tests,
+    run_selected_self_tests(options: SelfTestOptions) => pesa_core::self_test::ui::run_selected_self_tests,
+    list_self_tests() => pesa_core::self_test::ui::list_self_tests,
+    run_contract_tests(options: ContractTestOptions) => pesa_core::self_test::ui::run_contract_tests,
+    list_contract_tests() => pesa_core::self_test::ui::list_contract_tests
 }
 
+assert_commands_match!(RPC_COMMANDS, pesa_lua::manager::LUA_COMMANDS);
+
 pub async fn rpc_handler(
     State(state): State<AxumAppState>,
     axum::Json(payload): axum::Json<RpcRequest>,
@@ -328,6 +399,54 @@ pub async fn rpc_handler(
                 }
             }
         }
+        "scripts_run" => {
+            #[derive(Deserialize)]
+            struct Args {
+                name: String,
+            }
+            let call_result: Result<serde_json::Value, anyhow::Error> = async {
+                let args: Args = serde_json::from_value(params_val)?;
+                // scripts may run for a while, don't hold the lock meanwhile
+                let manager = state.script_manager.lock().await.clone();
+                manager.run_script(&args.name).await?;
+                Ok(serde_json::Value::Null)
+            }
+            .await;
+            match call_result {
+                Ok(data) => {
+                    serde_json::json!({"jsonrpc": "2.0", "result": data, "id": payload.id})
+                }
+                Err(e) => {
+                    status_code = axum::http::StatusCode::INTERNAL_SERVER_ERROR;
+                    serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": format!("Invalid params: {:?}", e)}, "id": payload.id})
+                }
+            }
+        }
+        "scripts_unload" => {
+            #[derive(Deserialize)]
+            struct Args {
+                name: String,
+            }
+            let call_result: Result<serde_json::Value, anyhow::Error> = async {
+                let args: Args = serde_json::from_value(params_val)?;
+                let manager = state.script_manager.lock().await;
+                Ok(serde_json::to_value(manager.unload_script(&args.name))?)
+            }
+            .await;
+            match call_result {
+                Ok(data) => {
+                    serde_json::json!({"jsonrpc": "2.0", "result": data, "id": payload.id})
+                }
+                Err(e) => {
+                    status_code = axum::http::StatusCode::INTERNAL_SERVER_ERROR;
+                    serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": format!("Invalid params: {:?}", e)}, "id": payload.id})
+                }
+            }
+        }
+        "scripts_loaded" => {
+            let manager = state.script_manager.lock().await;
+            serde_json::json!({"jsonrpc": "2.0", "result": manager.loaded_scripts(), "id": payload.id})
+        }
         _ => {
             let (s, r) = rpc_handler_inner(State(state), axum::Json(payload)).await;
             status_code = s;
@@ -352,6 +471,24 @@ struct CliArgs {
     /// Path to the SvelteKit build output (webroot)
     #[arg(short, long, default_value = ".")]
     webroot: PathBuf,
+
+    /// Export traces to the OpenTelemetry collector at this URL, e.g. http://localhost:4318
+    #[cfg(feature = "otlp")]
+    #[arg(long, value_name = "URL")]
+    otlp_endpoint: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the built-in self-test suite and exit, with status 1 when a step fails
+    SelfTest(self_test::SelfTestArgs),
+    /// Replay a recorded session and exit, with status 1 when the sandbox diverges from it
+    Replay(replay::ReplayArgs),
+    /// Prune the logs past the projects' retention policies and exit
+    PruneLogs(prune_logs::PruneLogsArgs),
 }
 
 async fn log_requests(mut req: Request<axum::body::Body>, next: Next) -> Response {
@@ -404,9 +541,8 @@ async fn main() {
     unsafe {
         std::env::set_var("RUST_LOG", "info,sqlx=warn");
     }
-    env_logger::init();
-
     let cli_args = CliArgs::parse();
+    let logging = Logging::init(&cli_args);
 
     let data_dir = if let Some(mut dir) = dirs::data_dir() {
         dir.push(TAURI_APP_ID);
@@ -423,6 +559,42 @@ async fn main() {
         panic!("Failed to create data directory: {}", e);
     }
 
+    match cli_args.command {
+        Some(Command::SelfTest(args)) => {
+            let code = match self_test::run(args, data_dir).await {
+                Ok(true) => 0,
+                Ok(false) => 1,
+                Err(e) => {
+                    error!("Self-test could not run: {:#}", e);
+                    2
+                }
+            };
+            logging.exit(code);
+        }
+        Some(Command::Replay(args)) => {
+            let code = match replay::run(args, data_dir).await {
+                Ok(true) => 0,
+                Ok(false) => 1,
+                Err(e) => {
+                    error!("Replay could not run: {:#}", e);
+                    2
+                }
+            };
+            logging.exit(code);
+        }
+        Some(Command::PruneLogs(args)) => {
+            let code = match prune_logs::run(args, data_dir).await {
+                Ok(()) => 0,
+                Err(e) => {
+                    error!("Pruning logs failed: {:#}", e);
+                    1
+                }
+            };
+            logging.exit(code);
+        }
+        None => {}
+    }
+
     let db_path = data_dir.join("database.sqlite");
 
     let db = pesa_core::db::Database::new(&db_path)
@@ -449,11 +621,23 @@ async fn main() {
         event_manager: axum_event_manager.clone(),
         running: Arc::new(pesa_core::dashmap::DashMap::new()),
         app_root: data_dir.clone(),
+        interceptors: Default::default(),
+        callback_hooks: Default::default(),
+        recordings: Default::default(),
+        metrics: Default::default(),
     };
 
     let script_manager = ScriptManager::new(core_context.clone(), &data_dir)
         .expect("Failed to initialize script manager");
 
+    if let Err(e) = script_manager.lock().await.autorun().await {
+        error!("Failed to run autorun scripts: {:?}", e);
+    }
+    tokio::spawn(pesa_lua::watch::watch(
+        script_manager.clone(),
+        pesa_lua::watch::WATCH_INTERVAL,
+    ));
+
     let script_manager_clone = script_manager.clone();
     let mut script_event_receiver = event_sender.subscribe();
 
@@ -465,7 +649,7 @@ async fn main() {
                         event_payload["event"].as_str(),
                         event_payload["payload"].as_object(),
                     ) {
-                        let sm = script_manager_clone.lock().await;
+                        let sm = script_manager_clone.lock().await.clone();
                         sm.emit_event(event_name, serde_json::Value::Object(payload.clone()))
                             .await;
                     }
@@ -487,6 +671,7 @@ async fn main() {
     let app = Router::new()
         .route("/rpc", post(rpc_handler))
         .route("/ws", get(ws_handler))
+        .route("/metrics", get(metrics_handler))
         .with_state(app_state)
         .fallback_service(
             ServeDir::new(cli_args.webroot.clone())
@@ -498,5 +683,11 @@ async fn main() {
     let addr = format!("{}:{}", cli_args.address, cli_args.port);
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     info!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .unwrap();
+    logging.shutdown();
 }
@@ -0,0 +1,40 @@
+//! `pesa-axum prune-logs`: applies the projects' log retention policies once, e.g. from cron.
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use pesa_core::{AppContext, logs::ui::prune_logs};
+
+#[derive(clap::Args, Debug)]
+pub struct PruneLogsArgs {
+    /// Prune only this project's logs
+    #[arg(long, value_name = "ID")]
+    project: Option<u32>,
+}
+
+pub async fn run(args: PruneLogsArgs, app_root: PathBuf) -> anyhow::Result<()> {
+    let db = pesa_core::db::Database::new(&app_root.join("database.sqlite"))
+        .await
+        .context("Failed to open database")?;
+    db.init().await?;
+    let settings = pesa_core::settings::SettingsManager::new(app_root.join("settings.json"))
+        .await
+        .context("Failed to load settings")?;
+    let ctx = AppContext {
+        db: db.conn.clone(),
+        settings,
+        event_manager: Arc::new(crate::NoEvents),
+        running: Default::default(),
+        app_root,
+        interceptors: Default::default(),
+        callback_hooks: Default::default(),
+        recordings: Default::default(),
+        metrics: Default::default(),
+    };
+
+    let report = prune_logs(&ctx, args.project).await?;
+    println!(
+        "Pruned {} API logs and {} callback logs",
+        report.api_logs, report.callback_logs
+    );
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! `pesa-axum replay`: replays a recorded session without the GUI, for CI. Exits with status 1
+//! when the sandbox now answers differently than it did when the session was recorded.
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use pesa_core::{
+    AppContext,
+    recording::{
+        replay::{ReplayOptions, ReplayReport},
+        ui::replay_session,
+    },
+};
+
+#[derive(clap::Args, Debug)]
+pub struct ReplayArgs {
+    /// Name of the recorded session
+    session: String,
+
+    /// Send the requests back to back instead of with their recorded spacing
+    #[arg(long)]
+    ignore_timing: bool,
+
+    /// Milliseconds to wait for callbacks after the last request
+    #[arg(long, value_name = "MS")]
+    settle_ms: Option<u64>,
+
+    /// Write a JSON report to this file
+    #[arg(long, value_name = "FILE")]
+    json: Option<PathBuf>,
+}
+
+/// Replays the session, writes the requested report and returns whether nothing diverged.
+pub async fn run(args: ReplayArgs, app_root: PathBuf) -> anyhow::Result<bool> {
+    let db = pesa_core::db::Database::new(&app_root.join("database.sqlite"))
+        .await
+        .context("Failed to open database")?;
+    let settings = pesa_core::settings::SettingsManager::new(app_root.join("settings.json"))
+        .await
+        .context("Failed to load settings")?;
+    let ctx = AppContext {
+        db: db.conn.clone(),
+        settings,
+        event_manager: Arc::new(crate::NoEvents),
+        running: Default::default(),
+        app_root,
+        interceptors: Default::default(),
+        callback_hooks: Default::default(),
+        recordings: Default::default(),
+        metrics: Default::default(),
+    };
+
+    println!("Replaying session {}", args.session);
+    let report = replay_session(
+        &ctx,
+        ReplayOptions {
+            session: args.session,
+            ignore_timing: args.ignore_timing,
+            settle_ms: args.settle_ms,
+        },
+    )
+    .await?;
+
+    print_report(&report);
+    if let Some(path) = args.json {
+        tokio::fs::write(&path, serde_json::to_string_pretty(&report)?)
+            .await
+            .with_context(|| format!("Failed to write JSON report to {}", path.display()))?;
+    }
+
+    Ok(report.passed())
+}
+
+fn print_report(report: &ReplayReport) {
+    for divergence in &report.divergences {
+        println!(
+            "  DIFF {:?} #{} {}",
+            divergence.kind, divergence.index, divergence.target
+        );
+        println!("       expected: {}", divergence.expected);
+        println!("       actual:   {}", divergence.actual);
+    }
+    if report.unanswered_prompts > 0 {
+        println!(
+            "  NOTE {} STK prompts had no recorded decision and were answered as timed out",
+            report.unanswered_prompts
+        );
+    }
+    println!(
+        "\n{} requests, {} callbacks, {} divergences",
+        report.requests,
+        report.callbacks,
+        report.divergences.len()
+    );
+}
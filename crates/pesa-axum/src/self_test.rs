@@ -0,0 +1,222 @@
+//! `pesa-axum self-test`: the built-in self-test suite without the GUI, for CI. With
+//! `--target` it runs the contract tests against that backend instead.
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use log::info;
+use pesa_core::{
+    AppEventManager,
+    self_test::{
+        context::TestMode,
+        contract::{ContractTarget, ContractTests},
+        emitter::{SELF_TEST_PLAN, SELF_TEST_PROGRESS_LOG, SELF_TEST_STEP_UPDATE, TestStatus},
+        report::TestReport,
+        runner::{TestRunner, TestSelection, TestSuite},
+        tests::SelfTests,
+    },
+};
+use serde_json::Value;
+
+#[derive(clap::Args, Debug)]
+pub struct SelfTestArgs {
+    /// Run against a copy of the app database (`clone`) or an empty one (`fresh`)
+    #[arg(long, default_value = "fresh", value_parser = parse_mode)]
+    mode: TestMode,
+
+    /// Write a JUnit XML report to this file
+    #[arg(long, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// Write a JSON report to this file
+    #[arg(long, value_name = "FILE")]
+    json: Option<PathBuf>,
+
+    /// Seconds a step may run before it fails as timed out
+    #[arg(long, default_value_t = 60)]
+    test_timeout: u64,
+
+    /// Seconds a step waits for a callback
+    #[arg(long, default_value_t = 30)]
+    callback_timeout: u64,
+
+    /// Run only this step and the steps it depends on (repeatable)
+    #[arg(long = "test", value_name = "NAME")]
+    tests: Vec<String>,
+
+    /// Run only the steps with this tag and the steps they depend on (repeatable)
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    /// Keep running the steps that don't depend on a failed one
+    #[arg(long)]
+    continue_on_failure: bool,
+
+    /// List the steps with their tags and dependencies, without running them
+    #[arg(long)]
+    list: bool,
+
+    /// Run the contract tests against the backend at this URL instead of the self-tests
+    #[arg(long, value_name = "URL")]
+    target: Option<String>,
+
+    /// Path of the target's STK push callback endpoint
+    #[arg(long, value_name = "PATH")]
+    stk_callback_path: Option<String>,
+
+    /// Path of the target's B2C result endpoint
+    #[arg(long, value_name = "PATH")]
+    b2c_result_path: Option<String>,
+
+    /// Path of the target's C2B validation endpoint
+    #[arg(long, value_name = "PATH")]
+    c2b_validation_path: Option<String>,
+
+    /// Path of the target's C2B confirmation endpoint
+    #[arg(long, value_name = "PATH")]
+    c2b_confirmation_path: Option<String>,
+}
+
+impl SelfTestArgs {
+    fn contract_target(&self) -> Option<ContractTarget> {
+        let mut target = ContractTarget::new(self.target.clone()?);
+        let paths = [
+            (&self.stk_callback_path, &mut target.stk_callback_path),
+            (&self.b2c_result_path, &mut target.b2c_result_path),
+            (&self.c2b_validation_path, &mut target.c2b_validation_path),
+            (
+                &self.c2b_confirmation_path,
+                &mut target.c2b_confirmation_path,
+            ),
+        ];
+        for (arg, path) in paths {
+            if let Some(arg) = arg {
+                *path = arg.clone();
+            }
+        }
+        Some(target)
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<TestMode, String> {
+    match mode {
+        "fresh" => Ok(TestMode::Fresh),
+        "clone" => Ok(TestMode::Clone),
+        other => Err(format!("unknown mode '{}', expected fresh or clone", other)),
+    }
+}
+
+/// Prints the progress events of the runner instead of sending them to a GUI.
+struct ConsoleEventManager;
+
+impl AppEventManager for ConsoleEventManager {
+    fn emit_all(&self, event: &str, payload: Value) -> anyhow::Result<()> {
+        match event {
+            SELF_TEST_PLAN => {
+                let steps = payload["steps"].as_array().map_or(0, |s| s.len());
+                println!("Running {} self-test steps", steps);
+            }
+            SELF_TEST_STEP_UPDATE => {
+                let name = payload["name"].as_str().unwrap_or_default();
+                let message = payload["message"].as_str().unwrap_or_default();
+                match payload["status"].as_str().unwrap_or_default() {
+                    "running" => println!("  RUN  {}", name),
+                    "passed" => println!("  PASS {}", name),
+                    status => {
+                        println!("  FAIL {} ({})", name, status);
+                        for line in message.lines() {
+                            println!("       {}", line);
+                        }
+                    }
+                }
+            }
+            SELF_TEST_PROGRESS_LOG => {
+                info!("{}", payload["message"].as_str().unwrap_or_default());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Runs the suite, writes the requested reports and returns whether every step passed.
+pub async fn run(args: SelfTestArgs, app_root: PathBuf) -> anyhow::Result<bool> {
+    let target = args.contract_target();
+    if args.list {
+        match target {
+            Some(_) => print_tests::<ContractTests>(),
+            None => print_tests::<SelfTests>(),
+        }
+        return Ok(true);
+    }
+
+    let selection = TestSelection {
+        names: args.tests,
+        tags: args.tags,
+    };
+    let runner = TestRunner::new(
+        Duration::from_secs(args.test_timeout),
+        Duration::from_secs(args.callback_timeout),
+    )
+    .with_selection(selection.clone())
+    .continue_on_failure(args.continue_on_failure);
+    let events = Arc::new(ConsoleEventManager);
+    let report = match target {
+        Some(target) => {
+            target.validate()?;
+            selection.plan::<ContractTests>()?;
+            runner
+                .with_target(target)
+                .run_suite::<ContractTests>(TestMode::Fresh, events, app_root)
+                .await?
+        }
+        None => {
+            selection.plan::<SelfTests>()?;
+            runner
+                .run_suite::<SelfTests>(args.mode, events, app_root)
+                .await?
+        }
+    };
+
+    print_summary(&report);
+    if let Some(path) = args.junit {
+        tokio::fs::write(&path, report.to_junit_xml())
+            .await
+            .with_context(|| format!("Failed to write JUnit report to {}", path.display()))?;
+    }
+    if let Some(path) = args.json {
+        tokio::fs::write(&path, serde_json::to_string_pretty(&report)?)
+            .await
+            .with_context(|| format!("Failed to write JSON report to {}", path.display()))?;
+    }
+
+    Ok(report.passed())
+}
+
+fn print_tests<T: TestSuite>() {
+    for test in T::all() {
+        println!("{:<16} {}", test.name(), test.description());
+        if !test.tags().is_empty() {
+            println!("{:<16} tags: {}", "", test.tags().join(", "));
+        }
+        if !test.dependencies().is_empty() {
+            let dependencies: Vec<_> = test.dependencies().iter().map(|d| d.name()).collect();
+            println!("{:<16} depends on: {}", "", dependencies.join(", "));
+        }
+    }
+}
+
+fn print_summary(report: &TestReport) {
+    let total = report.cases().count();
+    let failed = report.failures();
+    let skipped = report
+        .cases()
+        .filter(|c| c.status == TestStatus::Pending)
+        .count();
+    println!(
+        "\n{} passed, {} failed, {} not run, {} total",
+        total - failed - skipped,
+        failed,
+        skipped,
+        total
+    );
+}
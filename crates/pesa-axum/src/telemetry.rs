@@ -0,0 +1,41 @@
+//! Exports the sandboxes' spans to an OpenTelemetry collector over OTLP/HTTP.
+use anyhow::Context;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+
+const SERVICE_NAME: &str = "pesa-playground";
+
+/// Installs the exporter. `endpoint` is the collector's base URL, e.g. `http://localhost:4318`.
+///
+/// The returned provider flushes the remaining spans when shut down.
+pub fn init(endpoint: &str) -> anyhow::Result<SdkTracerProvider> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let endpoint = if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}/v1/traces")
+    };
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .context("Failed to build the OTLP exporter")?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+        .build();
+
+    // a subscriber stops `tracing` from falling back to `log`, so the events are printed here.
+    // `RUST_LOG` picks what is printed and exported alike
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)))
+        .with(tracing_subscriber::fmt::layer())
+        .try_init()
+        .context("Failed to install the tracing subscriber")?;
+
+    Ok(provider)
+}
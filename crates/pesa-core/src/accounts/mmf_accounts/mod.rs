@@ -3,7 +3,7 @@ use anyhow::Context;
 use chrono::{DateTime, Utc};
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
-    FromQueryResult, QueryFilter, QuerySelect, RelationTrait, SelectColumns,
+    FromQueryResult, QueryFilter, QuerySelect, RelationTrait, SelectColumns, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 
@@ -47,7 +47,7 @@ impl MmfAccount {
     /// Creates a new mmf account. We expect db to be within a transaction.
     pub async fn create<C>(db: &C, business_id: u32, initial_balance: i64) -> anyhow::Result<Self>
     where
-        C: ConnectionTrait,
+        C: ConnectionTrait + TransactionTrait,
     {
         let account = Account::create_account(db, AccountType::Mmf, initial_balance)
             .await
@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use db::Column;
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
-    QueryFilter,
+    QueryFilter, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString};
@@ -17,6 +17,9 @@ pub mod ui;
 pub mod user_profiles;
 pub mod utility_accounts;
 
+/// The system account issues float into the simulator and collects transaction fees.
+pub const SYSTEM_ACCOUNT_ID: u32 = 0;
+
 #[derive(EnumString, Display, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
@@ -53,12 +56,12 @@ impl Account {
     where
         C: ConnectionTrait,
     {
-        if id == 0 {
+        if id == SYSTEM_ACCOUNT_ID {
             // system account
             let account = db::Entity::find_by_id(id).one(conn).await?;
             if account.is_none() {
                 let model = db::ActiveModel {
-                    id: Set(0),
+                    id: Set(SYSTEM_ACCOUNT_ID),
                     balance: Set(0),
                     account_type: Set(AccountType::System.to_string()),
                     created_at: Set(Utc::now()),
@@ -86,7 +89,7 @@ impl Account {
         initial_balance: i64,
     ) -> anyhow::Result<Self>
     where
-        C: ConnectionTrait,
+        C: ConnectionTrait + TransactionTrait,
     {
         let create = db::ActiveModel {
             account_type: Set(account_type.to_string()),
@@ -4,7 +4,8 @@ use fake::{Fake, faker::name::en::Name};
 use rand::{Rng, seq::SliceRandom};
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
-    FromQueryResult, QueryFilter, QuerySelect, RelationTrait, SelectColumns, prelude::DateTimeUtc,
+    FromQueryResult, QueryFilter, QuerySelect, RelationTrait, SelectColumns, TransactionTrait,
+    prelude::DateTimeUtc,
 };
 use serde::{Deserialize, Serialize};
 
@@ -53,7 +54,7 @@ impl User {
         balance: i64,
     ) -> anyhow::Result<User>
     where
-        C: ConnectionTrait,
+        C: ConnectionTrait + TransactionTrait,
     {
         let random_registration = Self::random_registration_date();
         let imsi = Self::generate_test_imsi();
@@ -75,7 +76,7 @@ impl User {
     }
     pub async fn create<C>(self, conn: &C) -> anyhow::Result<User>
     where
-        C: ConnectionTrait,
+        C: ConnectionTrait + TransactionTrait,
     {
         let account = Account::create_account(conn, AccountType::User, self.balance)
             .await
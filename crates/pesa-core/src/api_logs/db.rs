@@ -8,12 +8,17 @@ pub struct Model {
     pub project_id: u32,
     pub method: String,
     pub path: String,
+    /// The raw query string, `path` being the route the request matched.
+    pub query: Option<String>,
     pub status_code: u16,
     pub request_body: Option<String>,
     pub response_body: Option<String>,
     pub created_at: DateTimeUtc,
     pub error_desc: Option<String>,
     pub duration: u32,
+    /// The conversation the request started, shared with its callbacks and transactions.
+    #[sea_orm(indexed)]
+    pub conversation_id: Option<String>,
 }
 
 #[derive(Clone, Debug, EnumIter)]
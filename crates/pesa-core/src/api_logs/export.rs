@@ -0,0 +1,311 @@
+//! API logs as HAR 1.2 archives and `curl` commands, for bug reports and replaying requests.
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::ApiLog;
+
+/// Headers a replayed request should not carry over, curl works them out itself.
+const SKIPPED_CURL_HEADERS: [&str; 3] = ["host", "content-length", "connection"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: String,
+    /// Total time of the request in milliseconds.
+    pub time: u32,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: Value,
+    pub timings: HarTimings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<Value>,
+    pub headers: Vec<HarHeader>,
+    pub query_string: Vec<HarHeader>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<Value>,
+    pub headers: Vec<HarHeader>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HarHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarTimings {
+    pub send: i64,
+    pub wait: i64,
+    pub receive: i64,
+}
+
+/// One side of a logged exchange, as stored in `request_body` and `response_body`.
+#[derive(Debug, Default)]
+struct Message {
+    headers: BTreeMap<String, String>,
+    body: Option<String>,
+}
+
+impl Message {
+    fn parse(stored: Option<&str>) -> Self {
+        let Some(stored) = stored else {
+            return Self::default();
+        };
+        let Ok(value) = serde_json::from_str::<Value>(stored) else {
+            // not written by the logging middleware, keep it as the body
+            return Self {
+                headers: BTreeMap::new(),
+                body: Some(stored.to_string()),
+            };
+        };
+
+        let headers = value["headers"]
+            .as_object()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        // outgoing callbacks are logged with the JSON payload rather than its text
+        let body = match &value["body"] {
+            Value::Null => None,
+            Value::String(body) => Some(body.clone()),
+            json => Some(json.to_string()),
+        };
+
+        Self { headers, body }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn mime_type(&self) -> String {
+        self.header("content-type")
+            .unwrap_or("application/json")
+            .to_string()
+    }
+
+    fn har_headers(&self) -> Vec<HarHeader> {
+        self.headers
+            .iter()
+            .map(|(name, value)| HarHeader {
+                name: name.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    fn body_size(&self) -> i64 {
+        self.body.as_ref().map_or(0, |body| body.len() as i64)
+    }
+}
+
+/// The full URL of a logged request. Sandbox routes are logged as paths, so they are resolved
+/// against the request's `Host` header, then `base_url`. Outgoing callbacks are logged with
+/// their full URL already. The query string is logged apart from the route and put back here.
+fn request_url(log: &ApiLog, request: &Message, base_url: &str) -> String {
+    let url = if log.path.starts_with("http://") || log.path.starts_with("https://") {
+        log.path.clone()
+    } else {
+        match request.header("host") {
+            Some(host) => format!("http://{}{}", host, log.path),
+            None => format!("{}{}", base_url.trim_end_matches('/'), log.path),
+        }
+    };
+
+    match log.query.as_deref() {
+        Some(query) if !query.is_empty() => format!("{url}?{query}"),
+        _ => url,
+    }
+}
+
+/// The decoded parameters in the query string of `url`.
+fn query_string(url: &str) -> Vec<HarHeader> {
+    reqwest::Url::parse(url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| HarHeader {
+                    name: name.into_owned(),
+                    value: value.into_owned(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl Har {
+    pub fn new(entries: Vec<HarEntry>) -> Self {
+        Self {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator {
+                    name: "Pesa Playground".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                entries,
+            },
+        }
+    }
+
+    /// Renders `logs` as one archive, `base_url` resolving paths logged without a host.
+    pub fn from_logs(logs: &[ApiLog], base_url: &str) -> Self {
+        Self::new(
+            logs.iter()
+                .map(|log| HarEntry::from_log(log, base_url))
+                .collect(),
+        )
+    }
+}
+
+impl HarEntry {
+    pub fn from_log(log: &ApiLog, base_url: &str) -> Self {
+        let request = Message::parse(log.request_body.as_deref());
+        let response = Message::parse(log.response_body.as_deref());
+        let status_text = axum::http::StatusCode::from_u16(log.status_code)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or_default()
+            .to_string();
+
+        let url = request_url(log, &request, base_url);
+
+        Self {
+            started_date_time: log.created_at.to_rfc3339(),
+            time: log.duration,
+            request: HarRequest {
+                method: log.method.clone(),
+                query_string: query_string(&url),
+                url,
+                http_version: "HTTP/1.1".to_string(),
+                cookies: Vec::new(),
+                headers: request.har_headers(),
+                post_data: request.body.clone().map(|text| HarPostData {
+                    mime_type: request.mime_type(),
+                    text,
+                }),
+                headers_size: -1,
+                body_size: request.body_size(),
+            },
+            response: HarResponse {
+                status: log.status_code,
+                status_text,
+                http_version: "HTTP/1.1".to_string(),
+                cookies: Vec::new(),
+                headers: response.har_headers(),
+                content: HarContent {
+                    size: response.body_size(),
+                    mime_type: response.mime_type(),
+                    text: response.body.clone(),
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: response.body_size(),
+            },
+            cache: Value::Object(Default::default()),
+            timings: HarTimings {
+                send: 0,
+                wait: log.duration as i64,
+                receive: 0,
+            },
+            comment: log.error_desc.clone(),
+        }
+    }
+}
+
+/// Quotes `value` for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Renders a logged request as a `curl` command that sends it again.
+pub fn to_curl(log: &ApiLog, base_url: &str) -> String {
+    let request = Message::parse(log.request_body.as_deref());
+    let mut parts = vec![format!(
+        "curl -X {} {}",
+        log.method,
+        shell_quote(&request_url(log, &request, base_url))
+    )];
+
+    for (name, value) in &request.headers {
+        if SKIPPED_CURL_HEADERS
+            .iter()
+            .any(|skipped| name.eq_ignore_ascii_case(skipped))
+        {
+            continue;
+        }
+        parts.push(format!("-H {}", shell_quote(&format!("{name}: {value}"))));
+    }
+
+    if let Some(body) = &request.body {
+        parts.push(format!("--data-raw {}", shell_quote(body)));
+    }
+
+    parts.join(" \\\n  ")
+}
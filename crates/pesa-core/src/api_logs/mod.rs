@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::server::log::generate_request_id;
 
 pub mod db;
+pub mod export;
 pub mod ui;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -13,12 +14,14 @@ pub struct ApiLog {
     pub project_id: u32,
     pub method: String,
     pub path: String,
+    pub query: Option<String>,
     pub status_code: u16,
     pub request_body: Option<String>,
     pub response_body: Option<String>,
     pub created_at: DateTimeUtc,
     pub error_desc: Option<String>,
     pub duration: u32,
+    pub conversation_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,12 +60,14 @@ impl From<db::Model> for ApiLog {
             project_id: value.project_id,
             method: value.method,
             path: value.path,
+            query: value.query,
             status_code: value.status_code,
             request_body: value.request_body,
             response_body: value.response_body,
             created_at: value.created_at,
             error_desc: value.error_desc,
             duration: value.duration,
+            conversation_id: value.conversation_id,
         }
     }
 }
@@ -73,12 +78,14 @@ pub struct ApiLogBuilder {
     project_id: Option<u32>,
     method: Option<String>,
     path: Option<String>,
+    query: Option<String>,
     status_code: Option<u16>,
     request_body: Option<String>,
     response_body: Option<String>,
     created_at: Option<DateTimeUtc>,
     error_desc: Option<String>,
     duration: Option<u32>,
+    conversation_id: Option<String>,
 }
 
 impl ApiLogBuilder {
@@ -106,6 +113,11 @@ impl ApiLogBuilder {
         self
     }
 
+    pub fn query<S: Into<String>>(mut self, query: S) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+
     pub fn status_code(mut self, status_code: u16) -> Self {
         self.status_code = Some(status_code);
         self
@@ -136,6 +148,11 @@ impl ApiLogBuilder {
         self
     }
 
+    pub fn conversation_id<S: Into<String>>(mut self, conversation_id: S) -> Self {
+        self.conversation_id = Some(conversation_id.into());
+        self
+    }
+
     pub async fn save<C: ConnectionTrait>(self, conn: &C) -> anyhow::Result<ApiLog> {
         let api = ApiLog {
             id: self.id.unwrap_or(generate_request_id()),
@@ -146,6 +163,7 @@ impl ApiLogBuilder {
                 .method
                 .ok_or(ApiLogBuilderError::MissingField("method"))?,
             path: self.path.ok_or(ApiLogBuilderError::MissingField("path"))?,
+            query: self.query,
             status_code: self
                 .status_code
                 .ok_or(ApiLogBuilderError::MissingField("status_code"))?,
@@ -156,6 +174,7 @@ impl ApiLogBuilder {
             duration: self
                 .duration
                 .ok_or(ApiLogBuilderError::MissingField("duration"))?,
+            conversation_id: self.conversation_id,
         };
 
         let create_api = db::ActiveModel {
@@ -163,12 +182,14 @@ impl ApiLogBuilder {
             project_id: Set(api.project_id),
             method: Set(api.method.clone()),
             path: Set(api.path.clone()),
+            query: Set(api.query.clone()),
             status_code: Set(api.status_code),
             request_body: Set(api.request_body.clone()),
             response_body: Set(api.response_body.clone()),
             created_at: Set(api.created_at),
             error_desc: Set(api.error_desc.clone()),
             duration: Set(api.duration),
+            conversation_id: Set(api.conversation_id.clone()),
         };
 
         create_api.insert(conn).await?;
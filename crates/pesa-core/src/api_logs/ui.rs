@@ -6,8 +6,9 @@ use sea_orm::ActiveValue::Set;
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
 use serde::Deserialize;
 
-use crate::AppContext;
 use crate::api_logs::ApiLog;
+use crate::api_logs::export::{self, Har, HarEntry};
+use crate::{AppContext, logs, sandboxes};
 
 #[derive(Deserialize)]
 pub struct ApiLogFilter {
@@ -15,6 +16,8 @@ pub struct ApiLogFilter {
     method: Option<String>,
     path: Option<String>,
     status_code: Option<u16>,
+    /// Full-text search over the bodies and error, see [`crate::logs::match_query`].
+    search: Option<String>,
     limit: Option<u64>,
     offset: Option<u64>,
 }
@@ -85,7 +88,7 @@ pub async fn list_api_logs(ctx: &AppContext, filter: ApiLogFilter) -> Result<Vec
     }
 
     if let Some(method) = filter.method {
-        q = q.filter(super::db::Column::Path.eq(method));
+        q = q.filter(super::db::Column::Method.eq(method));
     }
 
     if let Some(path) = filter.path {
@@ -96,6 +99,13 @@ pub async fn list_api_logs(ctx: &AppContext, filter: ApiLogFilter) -> Result<Vec
         q = q.filter(super::db::Column::StatusCode.eq(status));
     }
 
+    // a blank search lists every log, one too short to look up is refused as by `search_logs`
+    if let Some(search) = filter.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        q = q.filter(logs::api_logs_matching(&logs::required_match_query(
+            search,
+        )?));
+    }
+
     if let Some(limit) = filter.limit {
         q = q.limit(Some(limit));
     }
@@ -179,3 +189,32 @@ pub async fn get_api_logs_by_method(
 
     Ok(logs.into_iter().map(|log| log.into()).collect())
 }
+
+/// Where paths logged without a `Host` header are resolved against.
+fn base_url(ctx: &AppContext, project_id: u32) -> String {
+    sandboxes::ui::sandbox_url(ctx, project_id).unwrap_or_else(|| "http://localhost".to_string())
+}
+
+pub async fn export_api_logs_har(ctx: &AppContext, filter: ApiLogFilter) -> Result<Har> {
+    let mut logs = list_api_logs(ctx, filter).await?;
+    // HAR entries read oldest first
+    logs.reverse();
+
+    Ok(Har::new(
+        logs.iter()
+            .map(|log| HarEntry::from_log(log, &base_url(ctx, log.project_id)))
+            .collect(),
+    ))
+}
+
+pub async fn export_api_log_har(ctx: &AppContext, log_id: String) -> Result<Option<Har>> {
+    let log = get_api_log(ctx, log_id).await?;
+
+    Ok(log.map(|log| Har::from_logs(std::slice::from_ref(&log), &base_url(ctx, log.project_id))))
+}
+
+pub async fn export_api_log_curl(ctx: &AppContext, log_id: String) -> Result<Option<String>> {
+    let log = get_api_log(ctx, log_id).await?;
+
+    Ok(log.map(|log| export::to_curl(&log, &base_url(ctx, log.project_id))))
+}
@@ -1,13 +1,14 @@
 use anyhow::{Context, Result, anyhow};
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
-    IntoActiveModel, PaginatorTrait, QueryFilter,
+    IntoActiveModel, PaginatorTrait, QueryFilter, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::accounts::paybill_accounts::{CreatePaybillAccount, PaybillAccount};
 use crate::accounts::{mmf_accounts::MmfAccount, utility_accounts::UtilityAccount};
 use crate::business_operators::BusinessOperator;
+use crate::events::DomainEvent;
 use crate::transactions::Ledger;
 pub mod db;
 pub mod ui;
@@ -33,7 +34,7 @@ impl From<&db::Model> for Business {
 impl Business {
     pub async fn create<C>(conn: &C, input: CreateBusiness) -> Result<Business>
     where
-        C: ConnectionTrait,
+        C: ConnectionTrait + TransactionTrait,
     {
         // verify shortcode is valid
         let shortcodes = db::Entity::find()
@@ -189,9 +190,12 @@ impl Business {
             utility_account: utility,
         })
     }
-    pub async fn settle_revenue<C>(conn: &C, business_id: u32) -> Result<()>
+    /// Settles accrued charges and sweeps the utility balance into the MMF account.
+    ///
+    /// Returns the ledger events, to be dispatched once the caller's transaction commits.
+    pub async fn settle_revenue<C>(conn: &C, business_id: u32) -> Result<Vec<DomainEvent>>
     where
-        C: ConnectionTrait,
+        C: ConnectionTrait + TransactionTrait,
     {
         // 1. Fetch the business
         let business_model = db::Entity::find_by_id(business_id)
@@ -207,10 +211,12 @@ impl Business {
                 business_id
             ))?;
 
+        let mut events = Vec::new();
+
         // 3. Settle Charges Paid Account (if negative)
         if business_model.charges_amount < 0 {
             let amount_to_settle = -business_model.charges_amount as i64;
-            Ledger::transfer(
+            let (_, settlement_events) = Ledger::transfer(
                 conn,
                 Some(utility_account.account_id),
                 0,
@@ -219,6 +225,10 @@ impl Business {
                 None,
             )
             .await?;
+            events.extend(settlement_events);
+
+            // the accrued charges are paid, don't collect them again on the next settlement
+            Self::increment_charges_amount(conn, business_id, amount_to_settle).await?;
         }
 
         // 4. Sweep remaining balance from Utility Account to MMF Account
@@ -238,7 +248,7 @@ impl Business {
 
         if updated_utility_account.balance > 0 {
             let amount_to_sweep = updated_utility_account.balance as i64;
-            Ledger::transfer(
+            let (_, sweep_events) = Ledger::transfer(
                 conn,
                 Some(utility_account.account_id),
                 mmf_account.account_id,
@@ -247,8 +257,9 @@ impl Business {
                 None,
             )
             .await?;
+            events.extend(sweep_events);
         }
-        Ok(())
+        Ok(events)
     }
 
     pub async fn get_by_id<C>(conn: &C, id: u32) -> Result<Option<Business>>
@@ -269,6 +280,24 @@ impl Business {
             .await?;
         Ok(business.as_ref().map(|b| b.into()))
     }
+    /// The business owning `account_id`, if it is one of its utility or MMF accounts.
+    pub async fn find_by_account<C>(conn: &C, account_id: u32) -> Result<Option<u32>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if let Some(utility) = UtilityAccount::find_by_id(conn, account_id).await? {
+            return Ok(Some(utility.business_id));
+        }
+
+        Ok(MmfAccount::find_by_id(conn, account_id)
+            .await?
+            .map(|mmf| mmf.business_id))
+    }
+
+    /// Adds `amount` to the charges accrued by the business, negative for fees it owes.
+    ///
+    /// Business paid fees of [`crate::transactions::Ledger::transfer`] are accrued here and
+    /// collected by [`Business::settle_revenue`].
     pub async fn increment_charges_amount<C>(
         conn: &C,
         id: u32,
@@ -2,6 +2,7 @@ use anyhow::Result;
 use sea_orm::TransactionTrait;
 
 use crate::AppContext;
+use crate::events::DomainEventDispatcher;
 
 use crate::business::{Business, BusinessSummary, CreateBusiness, UpdateBusiness};
 
@@ -33,7 +34,9 @@ pub async fn delete_business(ctx: &AppContext, id: u32) -> Result<bool> {
 
 pub async fn revenue_settlement(ctx: &AppContext, business_id: u32) -> Result<()> {
     let txn = ctx.db.begin().await?;
-    Business::settle_revenue(&txn, business_id).await?;
+    let events = Business::settle_revenue(&txn, business_id).await?;
     txn.commit().await?;
+
+    DomainEventDispatcher::dispatch_events(ctx, events)?;
     Ok(())
 }
@@ -32,6 +32,10 @@ pub struct Model {
     pub status: String, // e.g., "Pending", "Delivered", "Failed"
     /// Any error message from a failed dispatch attempt.
     pub error: Option<String>,
+    /// What the before dispatch hooks did to the callback, e.g. "modified" or "dropped".
+    pub hook_decision: Option<String>,
+    /// The JSON payload before the hooks rewrote it.
+    pub original_payload: Option<String>,
     pub created_at: DateTimeUtc,
     pub updated_at: Option<DateTimeUtc>,
 }
@@ -1,8 +1,14 @@
-use reqwest::{Client, header::HeaderMap};
+use bytes::Bytes;
+use reqwest::{
+    Client,
+    header::{CONTENT_TYPE, HeaderMap},
+};
 use serde::Serialize;
 use serde_json::Value;
 use std::{fmt::Debug, time::Duration};
 
+use crate::server::trace_context::TraceContext;
+
 /// Configuration for the dispatch service.
 #[derive(Debug, Clone, Copy)]
 pub struct DispatchConfig {
@@ -29,6 +35,7 @@ pub struct DispatchResponse {
 pub struct CallbackDispatchService {
     client: Client,
     config: DispatchConfig,
+    trace: Option<TraceContext>,
 }
 
 impl CallbackDispatchService {
@@ -42,7 +49,17 @@ impl CallbackDispatchService {
             .timeout(config.timeout)
             .build()
             .expect("Failed to build reqwest client for CallbackDispatchService");
-        Self { client, config }
+        Self {
+            client,
+            config,
+            trace: None,
+        }
+    }
+
+    /// Passes the trace context of the request behind the callbacks on to their receiver.
+    pub fn with_trace_context(mut self, trace: Option<TraceContext>) -> Self {
+        self.trace = trace;
+        self
     }
 
     /// Dispatches a serializable payload to a URL, with retries on failure.
@@ -51,6 +68,20 @@ impl CallbackDispatchService {
         url: &str,
         payload: &T,
     ) -> Result<DispatchResponse, anyhow::Error> {
+        self.dispatch_body(url, serde_json::to_vec(payload)?).await
+    }
+
+    /// Dispatches a body as JSON without validating it, so malformed bodies go out as they are.
+    #[tracing::instrument(name = "callback.dispatch", skip_all, fields(url = %url))]
+    pub async fn dispatch_body(
+        &self,
+        url: &str,
+        body: impl Into<Bytes>,
+    ) -> Result<DispatchResponse, anyhow::Error> {
+        let body = body.into();
+        let trace_headers = TraceContext::outgoing(&tracing::Span::current(), self.trace.as_ref())
+            .map(|trace| trace.headers())
+            .unwrap_or_default();
         let mut last_error: Option<anyhow::Error> = None;
 
         for attempt in 1..=self.config.max_retries {
@@ -61,7 +92,17 @@ impl CallbackDispatchService {
                 self.config.max_retries
             );
 
-            match self.client.post(url).json(payload).send().await {
+            let request = self
+                .client
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            let request = trace_headers
+                .iter()
+                .fold(request, |request, (name, value)| {
+                    request.header(*name, value)
+                });
+            match request.send().await {
                 Ok(response) => {
                     let status = response.status();
                     if status.is_success() {
@@ -0,0 +1,85 @@
+//! Hooks letting a script host rewrite, delay or drop callbacks before they are sent.
+use std::sync::{Arc, RwLock};
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::CreateCallbackParams;
+
+/// What the hooks did to a callback, as stored in `callback_logs`.
+#[derive(
+    Debug, Clone, strum::EnumString, strum::Display, Deserialize, Serialize, PartialEq, Eq,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackDecision {
+    Unchanged,
+    Modified,
+    Delayed,
+    Dropped,
+}
+
+/// The callback a hook wants sent, and how.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookOutcome {
+    #[serde(flatten)]
+    pub callback: CreateCallbackParams,
+    /// Wait this long before sending the callback.
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
+    /// Don't send the callback at all.
+    #[serde(default)]
+    pub drop: bool,
+    /// Send this body verbatim instead of the JSON payload, e.g. truncated JSON.
+    #[serde(default)]
+    pub raw_body: Option<String>,
+}
+
+impl From<CreateCallbackParams> for HookOutcome {
+    fn from(callback: CreateCallbackParams) -> Self {
+        Self {
+            callback,
+            delay_ms: None,
+            drop: false,
+            raw_body: None,
+        }
+    }
+}
+
+impl HookOutcome {
+    /// Classifies the outcome against the callback the hooks were given. A callback that is
+    /// rewritten and delayed is [`CallbackDecision::Modified`], what is sent matters more than
+    /// when.
+    pub fn decision(&self, original: &CreateCallbackParams) -> CallbackDecision {
+        if self.drop {
+            CallbackDecision::Dropped
+        } else if self.raw_body.is_some() || &self.callback != original {
+            CallbackDecision::Modified
+        } else if self.delay_ms.is_some_and(|ms| ms > 0) {
+            CallbackDecision::Delayed
+        } else {
+            CallbackDecision::Unchanged
+        }
+    }
+}
+
+pub trait CallbackHook: Send + Sync {
+    fn before_dispatch(
+        &self,
+        callback: CreateCallbackParams,
+    ) -> BoxFuture<'static, anyhow::Result<HookOutcome>>;
+}
+
+/// The callback hook installed by the script host, shared by every clone of the [`crate::AppContext`].
+#[derive(Clone, Default)]
+pub struct CallbackHooks(Arc<RwLock<Option<Arc<dyn CallbackHook>>>>);
+
+impl CallbackHooks {
+    pub fn set(&self, hook: Arc<dyn CallbackHook>) {
+        *self.0.write().unwrap() = Some(hook);
+    }
+
+    pub fn get(&self) -> Option<Arc<dyn CallbackHook>> {
+        self.0.read().unwrap().clone()
+    }
+}
@@ -6,8 +6,11 @@ use sea_orm::{
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use self::hooks::CallbackDecision;
+
 pub mod db;
 pub mod dispatch;
+pub mod hooks;
 pub mod orchestrator;
 
 #[derive(
@@ -31,6 +34,8 @@ pub enum CallbackStatus {
     Pending,
     Delivered,
     Failed,
+    /// Dropped by a before dispatch hook, never sent.
+    Dropped,
 }
 
 /// A struct representing a single callback log record from the database.
@@ -49,11 +54,16 @@ pub struct CallbackLog {
     pub response_headers: Option<Value>,
     pub status: CallbackStatus,
     pub error: Option<String>,
+    /// What the before dispatch hooks did to the callback, if any ran.
+    pub hook_decision: Option<CallbackDecision>,
+    /// The payload before the hooks rewrote it.
+    pub original_payload: Option<Value>,
     pub created_at: DateTimeUtc,
     pub updated_at: Option<DateTimeUtc>,
 }
 
 /// Parameters for creating a new callback log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CreateCallbackParams {
     pub project_id: u32,
     pub callback_type: CallbackType,
@@ -94,6 +104,10 @@ impl From<db::Model> for CallbackLog {
                 .and_then(|h| serde_json::from_str(&h).ok()),
             status: value.status.parse().unwrap_or_default(),
             error: value.error,
+            hook_decision: value.hook_decision.and_then(|d| d.parse().ok()),
+            original_payload: value
+                .original_payload
+                .and_then(|p| serde_json::from_str(&p).ok()),
             created_at: value.created_at,
             updated_at: value.updated_at,
         }
@@ -107,6 +121,20 @@ impl CallbackLog {
         db: &C,
         params: CreateCallbackParams,
     ) -> Result<Self, DbErr> {
+        Self::create_hooked(db, params, None, None).await
+    }
+
+    /// Creates a callback record along with what the before dispatch hooks did to it.
+    pub async fn create_hooked<C: ConnectionTrait>(
+        db: &C,
+        params: CreateCallbackParams,
+        decision: Option<CallbackDecision>,
+        original_payload: Option<Value>,
+    ) -> Result<Self, DbErr> {
+        let status = match decision {
+            Some(CallbackDecision::Dropped) => CallbackStatus::Dropped,
+            _ => CallbackStatus::Pending,
+        };
         let model = db::ActiveModel {
             project_id: Set(params.project_id),
             conversation_id: Set(params.conversation_id),
@@ -115,7 +143,9 @@ impl CallbackLog {
             callback_url: Set(params.url),
             callback_type: Set(params.callback_type.to_string()),
             payload: Set(serde_json::to_string(&params.payload).unwrap_or_default()),
-            status: Set(CallbackStatus::Pending.to_string()),
+            status: Set(status.to_string()),
+            hook_decision: Set(decision.map(|d| d.to_string())),
+            original_payload: Set(original_payload.map(|p| p.to_string())),
             created_at: Set(Utc::now().to_utc()),
             ..Default::default()
         }
@@ -195,6 +225,8 @@ impl From<CallbackLog> for db::ActiveModel {
                 .map(|h| serde_json::to_string(&h).unwrap_or_default())),
             status: Set(log.status.to_string()),
             error: Set(log.error),
+            hook_decision: Set(log.hook_decision.map(|d| d.to_string())),
+            original_payload: Set(log.original_payload.map(|p| p.to_string())),
             created_at: Set(log.created_at),
             updated_at: Set(log.updated_at),
         }
@@ -1,22 +1,40 @@
 use super::{CallbackLog, CreateCallbackParams, DispatchOutcome};
 use crate::{
     AppContext,
-    callbacks::dispatch::{CallbackDispatchService, DispatchConfig},
+    callbacks::{
+        dispatch::{CallbackDispatchService, DispatchConfig},
+        hooks::{CallbackDecision, HookOutcome},
+    },
+    server::trace_context::TraceContext,
+};
+use serde_json::{Value, json};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
 };
-use serde::Serialize;
-use serde_json::json;
-use std::{collections::HashMap, time::Duration};
 
 pub struct CallbackOrchestrator;
 
 impl CallbackOrchestrator {
-    pub async fn handle_callback<T: Serialize>(
+    /// Runs the hooks, stores and sends a callback. `trace` is the W3C trace context of the
+    /// request that caused it, passed on in the callback's headers.
+    pub async fn handle_callback(
         context: &AppContext,
         params: CreateCallbackParams,
-        payload: T,
+        trace: Option<TraceContext>,
     ) {
-        // Create and save a "Pending" callback record.
-        let saved_log = match CallbackLog::create(&context.db, params).await {
+        // Let the script hooks rewrite, delay or drop the callback.
+        let (outcome, decision, original_payload) = Self::run_hooks(context, params).await;
+
+        // Create and save the callback record, "Pending" unless it was dropped.
+        let saved_log = match CallbackLog::create_hooked(
+            &context.db,
+            outcome.callback,
+            decision.clone(),
+            original_payload,
+        )
+        .await
+        {
             Ok(log) => log,
             Err(e) => {
                 tracing::error!("Failed to insert pending callback into database: {:?}", e);
@@ -24,15 +42,46 @@ impl CallbackOrchestrator {
             }
         };
 
+        if decision == Some(CallbackDecision::Dropped) {
+            tracing::info!("Callback {} dropped by a script hook", saved_log.id);
+            context
+                .recordings
+                .record(saved_log.project_id, (&saved_log).into());
+            return;
+        }
+        if let Some(delay_ms) = outcome.delay_ms {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+
         // Dispatch the callback.
-        let dispatch_service = CallbackDispatchService::new(DispatchConfig {
+        let config = DispatchConfig {
             timeout: Duration::from_secs(30),
             max_retries: 2,
-        });
+        };
+        let dispatch_service = CallbackDispatchService::new(config).with_trace_context(trace);
+        let started = Instant::now();
 
-        let dispatch_result = dispatch_service
-            .dispatch(&saved_log.callback_url, &payload)
-            .await;
+        let dispatch_result = match outcome.raw_body {
+            Some(raw_body) => {
+                dispatch_service
+                    .dispatch_body(&saved_log.callback_url, raw_body)
+                    .await
+            }
+            None => {
+                dispatch_service
+                    .dispatch(&saved_log.callback_url, &saved_log.payload)
+                    .await
+            }
+        };
+        context.metrics.observe_callback(
+            saved_log.project_id,
+            &saved_log.callback_type.to_string(),
+            dispatch_result
+                .as_ref()
+                .map_or(config.max_retries, |res| res.attempts_made),
+            dispatch_result.is_ok(),
+            started.elapsed(),
+        );
 
         // Update the database record with the outcome.
         let outcome = match dispatch_result {
@@ -56,8 +105,38 @@ impl CallbackOrchestrator {
             },
         };
 
-        if let Err(e) = saved_log.update_dispatch_status(&context.db, outcome).await {
-            tracing::error!("Failed to update callback status in database: {:?}", e);
+        match saved_log.update_dispatch_status(&context.db, outcome).await {
+            Ok(log) => context.recordings.record(log.project_id, (&log).into()),
+            Err(e) => tracing::error!("Failed to update callback status in database: {:?}", e),
         }
     }
+
+    /// Runs the before dispatch hook, if one is installed.
+    ///
+    /// A raw body replaces the logged payload, and a failing hook sends the callback unchanged.
+    async fn run_hooks(
+        context: &AppContext,
+        params: CreateCallbackParams,
+    ) -> (HookOutcome, Option<CallbackDecision>, Option<Value>) {
+        let Some(hook) = context.callback_hooks.get() else {
+            return (params.into(), None, None);
+        };
+
+        let mut outcome = match hook.before_dispatch(params.clone()).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::error!("Callback hook failed, sending the callback unchanged: {e:#}");
+                params.clone().into()
+            }
+        };
+        let decision = outcome.decision(&params);
+
+        if let Some(raw_body) = &outcome.raw_body {
+            outcome.callback.payload = Value::String(raw_body.clone());
+        }
+        let original_payload =
+            (outcome.callback.payload != params.payload).then_some(params.payload);
+
+        (outcome, Some(decision), original_payload)
+    }
 }
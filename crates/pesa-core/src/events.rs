@@ -1,6 +1,9 @@
 use serde_json::json;
 
-use crate::{AppContext, transactions_log::FullTransactionLog};
+use crate::{
+    AppContext,
+    transactions_log::{FullTransactionLog, db::Direction},
+};
 
 pub enum DomainEvent {
     TransactionCreated(FullTransactionLog),
@@ -19,6 +22,13 @@ impl DomainEventDispatcher {
     fn dispatch(context: &AppContext, event: DomainEvent) -> anyhow::Result<()> {
         match event {
             DomainEvent::TransactionCreated(log) => {
+                context.recordings.record_ledger(&log);
+                // each side of a transfer is logged, count the transaction once
+                if log.direction == Direction::Inflow {
+                    context
+                        .metrics
+                        .observe_transaction(&log.transaction_type, log.transaction_amount);
+                }
                 context
                     .event_manager
                     .emit_all("new_transaction", json!(log))?;
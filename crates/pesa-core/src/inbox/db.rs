@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "inbox_requests")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub project_id: u32,
+    /// The inbox name, the last segment of `/_inbox/<name>`.
+    pub inbox: String,
+    pub method: String,
+    /// The raw query string, if the request had one.
+    pub query: Option<String>,
+    /// The request headers as a JSON object.
+    pub headers: String,
+    pub body: Option<String>,
+    /// The status of the canned response the request was answered with.
+    pub response_status: u16,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::projects::db::Entity",
+        from = "Column::ProjectId",
+        to = "crate::projects::db::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Project,
+}
+
+impl Related<crate::projects::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Project.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,243 @@
+//! A callback sink inside each sandbox. Pointing `CallBackURL` or `ResultURL` at
+//! `http://<sandbox>/_inbox/<name>` stores every callback without a backend to receive it.
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, prelude::DateTimeUtc, sea_query::OnConflict,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::server::api::c2b::ResultCode;
+
+pub mod db;
+pub mod responses_db;
+pub mod ui;
+
+/// The sandbox route serving every inbox.
+pub const INBOX_ROUTE: &str = "/_inbox/{name}";
+
+/// A request captured by an inbox.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboxRequest {
+    pub id: u32,
+    pub project_id: u32,
+    pub inbox: String,
+    pub method: String,
+    pub query: Option<String>,
+    pub headers: HashMap<String, String>,
+    /// The JSON body, or the raw body as a string when it is not JSON.
+    pub body: Value,
+    pub response_status: u16,
+    pub created_at: DateTimeUtc,
+}
+
+/// A request as received, before it is stored.
+#[derive(Debug, Clone)]
+pub struct NewInboxRequest {
+    pub project_id: u32,
+    pub inbox: String,
+    pub method: String,
+    pub query: Option<String>,
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+    pub response_status: u16,
+}
+
+/// What an inbox answers every request with.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CannedResponse {
+    /// Acknowledge with `ResultCode` 0, which also accepts a C2B validation.
+    #[default]
+    Accept,
+    /// Reject a C2B validation with one of Daraja's `C2B000xx` result codes.
+    Reject {
+        #[serde(default = "default_reject_code")]
+        result_code: ResultCode,
+        #[serde(default)]
+        result_desc: Option<String>,
+    },
+    /// Any status, headers and body.
+    Custom {
+        status: u16,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Value,
+    },
+}
+
+fn default_reject_code() -> ResultCode {
+    ResultCode::C2B00016
+}
+
+impl CannedResponse {
+    pub fn status(&self) -> u16 {
+        match self {
+            CannedResponse::Accept | CannedResponse::Reject { .. } => 200,
+            CannedResponse::Custom { status, .. } => *status,
+        }
+    }
+
+    pub fn headers(&self) -> HashMap<String, String> {
+        match self {
+            CannedResponse::Custom { headers, .. } => headers.clone(),
+            _ => HashMap::new(),
+        }
+    }
+
+    pub fn body(&self) -> Value {
+        match self {
+            CannedResponse::Accept => json!({
+                "ResultCode": ResultCode::Ok,
+                "ResultDesc": "Accepted",
+            }),
+            CannedResponse::Reject {
+                result_code,
+                result_desc,
+            } => json!({
+                "ResultCode": result_code,
+                "ResultDesc": result_desc.as_deref().unwrap_or("Rejected"),
+            }),
+            CannedResponse::Custom { body, .. } => body.clone(),
+        }
+    }
+
+    /// The response configured for `inbox`, [`CannedResponse::Accept`] when there is none.
+    pub async fn for_inbox<C: ConnectionTrait>(
+        db: &C,
+        project_id: u32,
+        inbox: &str,
+    ) -> Result<Self, DbErr> {
+        let model = responses_db::Entity::find()
+            .filter(responses_db::Column::ProjectId.eq(project_id))
+            .filter(responses_db::Column::Inbox.eq(inbox))
+            .one(db)
+            .await?;
+
+        Ok(model
+            .and_then(|model| serde_json::from_str(&model.response).ok())
+            .unwrap_or_default())
+    }
+
+    /// Makes this the response of `inbox`.
+    pub async fn save<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        project_id: u32,
+        inbox: &str,
+    ) -> Result<(), DbErr> {
+        let model = responses_db::ActiveModel {
+            project_id: Set(project_id),
+            inbox: Set(inbox.to_string()),
+            response: Set(serde_json::to_string(self).unwrap_or_default()),
+            updated_at: Set(Utc::now().to_utc()),
+            ..Default::default()
+        };
+
+        responses_db::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([responses_db::Column::ProjectId, responses_db::Column::Inbox])
+                    .update_columns([
+                        responses_db::Column::Response,
+                        responses_db::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl From<db::Model> for InboxRequest {
+    fn from(value: db::Model) -> Self {
+        Self {
+            id: value.id,
+            project_id: value.project_id,
+            inbox: value.inbox,
+            method: value.method,
+            query: value.query,
+            headers: serde_json::from_str(&value.headers).unwrap_or_default(),
+            body: value
+                .body
+                .map(|body| serde_json::from_str(&body).unwrap_or(Value::String(body)))
+                .unwrap_or_default(),
+            response_status: value.response_status,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl InboxRequest {
+    pub async fn record<C: ConnectionTrait>(
+        db: &C,
+        request: NewInboxRequest,
+    ) -> Result<Self, DbErr> {
+        let model = db::ActiveModel {
+            project_id: Set(request.project_id),
+            inbox: Set(request.inbox),
+            method: Set(request.method),
+            query: Set(request.query),
+            headers: Set(serde_json::to_string(&request.headers).unwrap_or_default()),
+            body: Set(request.body),
+            response_status: Set(request.response_status),
+            created_at: Set(Utc::now().to_utc()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(model.into())
+    }
+
+    /// The requests of a project, newest first, optionally of one inbox only.
+    pub async fn list<C: ConnectionTrait>(
+        db: &C,
+        project_id: u32,
+        inbox: Option<&str>,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> Result<Vec<Self>, DbErr> {
+        let mut q = db::Entity::find()
+            .filter(db::Column::ProjectId.eq(project_id))
+            .order_by_desc(db::Column::Id);
+
+        if let Some(inbox) = inbox {
+            q = q.filter(db::Column::Inbox.eq(inbox));
+        }
+
+        q.limit(limit)
+            .offset(offset)
+            .all(db)
+            .await
+            .map(|models| models.into_iter().map(Into::into).collect())
+    }
+
+    /// Deletes the requests of a project, or of one of its inboxes. Returns how many went.
+    pub async fn clear<C: ConnectionTrait>(
+        db: &C,
+        project_id: u32,
+        inbox: Option<&str>,
+    ) -> Result<u64, DbErr> {
+        let mut q = db::Entity::delete_many().filter(db::Column::ProjectId.eq(project_id));
+
+        if let Some(inbox) = inbox {
+            q = q.filter(db::Column::Inbox.eq(inbox));
+        }
+
+        Ok(q.exec(db).await?.rows_affected)
+    }
+}
+
+/// Whether `name` can be used as the last path segment of an inbox URL.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "inbox_responses")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub project_id: u32,
+    pub inbox: String,
+    /// The [`super::CannedResponse`] as JSON.
+    pub response: String,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::projects::db::Entity",
+        from = "Column::ProjectId",
+        to = "crate::projects::db::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Project,
+}
+
+impl Related<crate::projects::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Project.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result, bail};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{CannedResponse, InboxRequest, db, is_valid_name, responses_db};
+use crate::{AppContext, sandboxes};
+
+#[derive(Deserialize)]
+pub struct InboxFilter {
+    project_id: u32,
+    inbox: Option<String>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+/// An inbox that has received requests or has a response configured.
+#[derive(Serialize, Debug, Clone)]
+pub struct InboxSummary {
+    pub name: String,
+    pub requests: u64,
+    pub response: CannedResponse,
+}
+
+pub async fn list_inbox_requests(
+    ctx: &AppContext,
+    filter: InboxFilter,
+) -> Result<Vec<InboxRequest>> {
+    InboxRequest::list(
+        &ctx.db,
+        filter.project_id,
+        filter.inbox.as_deref(),
+        filter.limit,
+        filter.offset,
+    )
+    .await
+    .context("Failed to list inbox requests")
+}
+
+pub async fn list_inboxes(ctx: &AppContext, project_id: u32) -> Result<Vec<InboxSummary>> {
+    let counts: Vec<(String, i64)> = db::Entity::find()
+        .select_only()
+        .column(db::Column::Inbox)
+        .column_as(db::Column::Id.count(), "requests")
+        .filter(db::Column::ProjectId.eq(project_id))
+        .group_by(db::Column::Inbox)
+        .into_tuple()
+        .all(&ctx.db)
+        .await
+        .context("Failed to count inbox requests")?;
+
+    let configured = responses_db::Entity::find()
+        .filter(responses_db::Column::ProjectId.eq(project_id))
+        .order_by_asc(responses_db::Column::Inbox)
+        .all(&ctx.db)
+        .await
+        .context("Failed to get inbox responses")?;
+
+    let mut inboxes: Vec<InboxSummary> = counts
+        .into_iter()
+        .map(|(name, requests)| InboxSummary {
+            name,
+            requests: requests as u64,
+            response: CannedResponse::default(),
+        })
+        .collect();
+
+    for model in configured {
+        let response = serde_json::from_str(&model.response).unwrap_or_default();
+        match inboxes.iter_mut().find(|inbox| inbox.name == model.inbox) {
+            Some(inbox) => inbox.response = response,
+            None => inboxes.push(InboxSummary {
+                name: model.inbox,
+                requests: 0,
+                response,
+            }),
+        }
+    }
+    inboxes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(inboxes)
+}
+
+pub async fn clear_inbox(ctx: &AppContext, project_id: u32, inbox: Option<String>) -> Result<u64> {
+    let cleared = InboxRequest::clear(&ctx.db, project_id, inbox.as_deref())
+        .await
+        .context("Failed to clear inbox")?;
+
+    let _ = ctx.event_manager.emit_all(
+        "inbox_cleared",
+        json!({
+            "project_id": project_id,
+            "inbox": inbox,
+            "cleared": cleared,
+        }),
+    );
+
+    Ok(cleared)
+}
+
+pub async fn get_inbox_response(
+    ctx: &AppContext,
+    project_id: u32,
+    inbox: String,
+) -> Result<CannedResponse> {
+    CannedResponse::for_inbox(&ctx.db, project_id, &inbox)
+        .await
+        .context("Failed to get inbox response")
+}
+
+pub async fn set_inbox_response(
+    ctx: &AppContext,
+    project_id: u32,
+    inbox: String,
+    response: CannedResponse,
+) -> Result<()> {
+    if !is_valid_name(&inbox) {
+        bail!("Invalid inbox name {inbox:?}, use letters, digits, '-', '_' or '.'");
+    }
+
+    response
+        .save(&ctx.db, project_id, &inbox)
+        .await
+        .context("Failed to set inbox response")
+}
+
+/// The URL of `inbox` on the project's sandbox, if the sandbox is running.
+pub async fn get_inbox_url(
+    ctx: &AppContext,
+    project_id: u32,
+    inbox: String,
+) -> Result<Option<String>> {
+    if !is_valid_name(&inbox) {
+        bail!("Invalid inbox name {inbox:?}, use letters, digits, '-', '_' or '.'");
+    }
+
+    Ok(sandboxes::ui::sandbox_url(ctx, project_id).map(|url| format!("{url}/_inbox/{inbox}")))
+}
@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "journal_entries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    /// The transaction this entry was posted for.
+    #[sea_orm(indexed)]
+    pub transaction_id: String,
+    /// The account being debited or credited.
+    #[sea_orm(indexed)]
+    pub account_id: u32,
+    pub side: EntrySide,
+    /// Always positive. The side decides whether it adds to or removes from the balance.
+    pub amount: i64,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "entry_side")]
+pub enum EntrySide {
+    #[sea_orm(string_value = "DEBIT")]
+    Debit,
+    #[sea_orm(string_value = "CREDIT")]
+    Credit,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::transactions::db::Entity",
+        from = "Column::TransactionId",
+        to = "crate::transactions::db::Column::Id"
+    )]
+    Transaction,
+    #[sea_orm(
+        belongs_to = "crate::accounts::db::Entity",
+        from = "Column::AccountId",
+        to = "crate::accounts::db::Column::Id"
+    )]
+    Account,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,270 @@
+//! Double-entry journal backing every balance change made by the [`Ledger`](crate::transactions::Ledger).
+//!
+//! Each ledger operation posts a set of lines where the total debited equals the total credited.
+//! Account balances are derived from these postings, which lets [`audit_ledger`] recompute them
+//! from scratch and report anything that does not add up.
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter, QueryOrder, QuerySelect, Select, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{accounts, transactions::TransactionEngineError};
+
+pub use db::EntrySide;
+
+pub mod db;
+pub mod ui;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: u32,
+    pub transaction_id: String,
+    pub account_id: u32,
+    pub side: EntrySide,
+    pub amount: i64,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+impl From<db::Model> for JournalEntry {
+    fn from(value: db::Model) -> Self {
+        Self {
+            id: value.id,
+            transaction_id: value.transaction_id,
+            account_id: value.account_id,
+            side: value.side,
+            amount: value.amount,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// One leg of a posting, before it is written to the journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalLine {
+    pub account_id: u32,
+    pub side: EntrySide,
+    pub amount: i64,
+}
+
+impl JournalLine {
+    pub fn debit(account_id: u32, amount: i64) -> Self {
+        Self {
+            account_id,
+            side: EntrySide::Debit,
+            amount,
+        }
+    }
+
+    pub fn credit(account_id: u32, amount: i64) -> Self {
+        Self {
+            account_id,
+            side: EntrySide::Credit,
+            amount,
+        }
+    }
+
+    /// The signed effect this line has on the account balance.
+    fn delta(&self) -> i64 {
+        match self.side {
+            EntrySide::Debit => -self.amount,
+            EntrySide::Credit => self.amount,
+        }
+    }
+}
+
+pub struct Journal;
+
+impl Journal {
+    /// Writes `lines` against `transaction_id` and applies them to the account balances.
+    ///
+    /// The lines must balance: the sum of debits has to equal the sum of credits. Zero amount
+    /// lines are skipped. Returns the resulting balance of every account that was touched.
+    pub async fn post<C>(
+        conn: &C,
+        transaction_id: &str,
+        lines: &[JournalLine],
+    ) -> Result<HashMap<u32, i64>, TransactionEngineError>
+    where
+        C: ConnectionTrait,
+    {
+        let (debits, credits) = lines.iter().fold((0, 0), |(d, c), line| match line.side {
+            EntrySide::Debit => (d + line.amount, c),
+            EntrySide::Credit => (d, c + line.amount),
+        });
+
+        if debits != credits || lines.iter().any(|line| line.amount < 0) {
+            return Err(TransactionEngineError::UnbalancedEntry { debits, credits });
+        }
+
+        let mut deltas: BTreeMap<u32, i64> = BTreeMap::new();
+        let now = Utc::now().to_utc();
+
+        for line in lines.iter().filter(|line| line.amount != 0) {
+            db::ActiveModel {
+                transaction_id: Set(transaction_id.to_string()),
+                account_id: Set(line.account_id),
+                side: Set(line.side),
+                amount: Set(line.amount),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(conn)
+            .await?;
+
+            *deltas.entry(line.account_id).or_default() += line.delta();
+        }
+
+        let mut balances = HashMap::new();
+        for (account_id, delta) in deltas {
+            let account = accounts::db::Entity::find_by_id(account_id)
+                .one(conn)
+                .await?
+                .ok_or(TransactionEngineError::AccountNotFound(account_id))?;
+
+            let balance = account.balance + delta;
+            if delta != 0 {
+                accounts::db::ActiveModel {
+                    id: sea_orm::ActiveValue::Unchanged(account_id),
+                    balance: Set(balance),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+            }
+            balances.insert(account_id, balance);
+        }
+
+        Ok(balances)
+    }
+
+    pub async fn entries_for_transaction<C>(
+        conn: &C,
+        transaction_id: &str,
+    ) -> Result<Vec<JournalEntry>, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let entries = db::Entity::find()
+            .filter(db::Column::TransactionId.eq(transaction_id))
+            .order_by_asc(db::Column::Id)
+            .all(conn)
+            .await?;
+
+        Ok(entries.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LedgerDiscrepancy {
+    /// The balance stored on the account differs from the one derived from the journal.
+    BalanceMismatch {
+        account_id: u32,
+        stored_balance: i64,
+        journal_balance: i64,
+    },
+    /// The entries posted for a transaction do not net out to zero.
+    UnbalancedTransaction {
+        transaction_id: String,
+        debits: i64,
+        credits: i64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerAudit {
+    pub balanced: bool,
+    pub accounts_checked: u64,
+    pub transactions_checked: u64,
+    pub discrepancies: Vec<LedgerDiscrepancy>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct AccountTotals {
+    account_id: u32,
+    debits: i64,
+    credits: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct TransactionTotals {
+    transaction_id: String,
+    debits: i64,
+    credits: i64,
+}
+
+/// Sums debits and credits of the journal, grouped by `column`.
+fn totals_by(column: db::Column) -> Select<db::Entity> {
+    db::Entity::find()
+        .select_only()
+        .column(column)
+        .column_as(
+            Expr::cust("COALESCE(SUM(CASE WHEN side = 'DEBIT' THEN amount ELSE 0 END), 0)"),
+            "debits",
+        )
+        .column_as(
+            Expr::cust("COALESCE(SUM(CASE WHEN side = 'CREDIT' THEN amount ELSE 0 END), 0)"),
+            "credits",
+        )
+        .group_by(column)
+}
+
+/// Recomputes every account balance from the journal and compares it with the stored balance.
+///
+/// Also checks that the entries of each transaction balance. An empty discrepancy list means the
+/// ledger adds up.
+pub async fn audit_ledger<C>(conn: &C) -> Result<LedgerAudit, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut discrepancies = Vec::new();
+
+    let account_totals: HashMap<u32, i64> = totals_by(db::Column::AccountId)
+        .into_model::<AccountTotals>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|totals| (totals.account_id, totals.credits - totals.debits))
+        .collect();
+
+    let accounts = accounts::db::Entity::find()
+        .order_by_asc(accounts::db::Column::Id)
+        .all(conn)
+        .await?;
+
+    for account in &accounts {
+        let journal_balance = account_totals.get(&account.id).copied().unwrap_or_default();
+        if journal_balance != account.balance {
+            discrepancies.push(LedgerDiscrepancy::BalanceMismatch {
+                account_id: account.id,
+                stored_balance: account.balance,
+                journal_balance,
+            });
+        }
+    }
+
+    let transaction_totals = totals_by(db::Column::TransactionId)
+        .into_model::<TransactionTotals>()
+        .all(conn)
+        .await?;
+    for totals in &transaction_totals {
+        if totals.debits != totals.credits {
+            discrepancies.push(LedgerDiscrepancy::UnbalancedTransaction {
+                transaction_id: totals.transaction_id.clone(),
+                debits: totals.debits,
+                credits: totals.credits,
+            });
+        }
+    }
+
+    Ok(LedgerAudit {
+        balanced: discrepancies.is_empty(),
+        accounts_checked: accounts.len() as u64,
+        transactions_checked: transaction_totals.len() as u64,
+        discrepancies,
+    })
+}
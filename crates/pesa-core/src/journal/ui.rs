@@ -0,0 +1,19 @@
+use anyhow::{Context, Result};
+
+use super::{Journal, JournalEntry, LedgerAudit};
+use crate::AppContext;
+
+pub async fn audit_ledger(ctx: &AppContext) -> Result<LedgerAudit> {
+    super::audit_ledger(&ctx.db)
+        .await
+        .context("Failed to audit ledger")
+}
+
+pub async fn get_journal_entries(
+    ctx: &AppContext,
+    transaction_id: String,
+) -> Result<Vec<JournalEntry>> {
+    Journal::entries_for_transaction(&ctx.db, &transaction_id)
+        .await
+        .context("Failed to get journal entries")
+}
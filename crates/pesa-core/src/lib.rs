@@ -13,14 +13,20 @@ pub mod business_operators;
 pub mod callbacks;
 pub mod db;
 pub mod events;
+pub mod inbox;
 pub mod info;
+pub mod journal;
+pub mod logs;
+pub mod metrics;
 pub mod migrations;
 pub mod projects;
+pub mod recording;
 pub mod sandboxes;
 pub mod self_test;
 pub mod server;
 pub mod settings;
 pub mod system;
+pub mod timeline;
 pub mod transaction_costs;
 pub mod transactions;
 pub mod transactions_log;
@@ -38,4 +44,8 @@ pub struct AppContext {
     pub event_manager: Arc<dyn AppEventManager + Send + Sync>,
     pub running: Arc<DashMap<u32, RunningSandbox>>,
     pub app_root: PathBuf,
+    pub interceptors: server::intercept::Interceptors,
+    pub callback_hooks: callbacks::hooks::CallbackHooks,
+    pub recordings: recording::Recordings,
+    pub metrics: metrics::Metrics,
 }
@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "log_retention")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub project_id: u32,
+    pub max_age_days: Option<u32>,
+    pub max_count: Option<u32>,
+    pub updated_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::projects::db::Entity",
+        from = "Column::ProjectId",
+        to = "crate::projects::db::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Project,
+}
+
+impl Related<crate::projects::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Project.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,197 @@
+//! Keeping `api_logs` and `callback_logs` in check: retention policies per project, pruning,
+//! and full-text search over the bodies and errors they hold.
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    prelude::DateTimeUtc,
+    sea_query::{Expr, OnConflict, SimpleExpr},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{api_logs, callbacks};
+
+pub mod db;
+pub mod ui;
+
+/// Search terms shorter than this match nothing in a trigram index.
+pub const MIN_SEARCH_TERM: usize = 3;
+
+/// How much of a project's logs to keep. Logs past either limit are pruned.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetentionPolicy {
+    pub project_id: u32,
+    /// Delete logs older than this many days.
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// Keep only this many of the newest API logs, and as many callback logs.
+    #[serde(default)]
+    pub max_count: Option<u32>,
+}
+
+/// How many logs a prune deleted.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct PruneReport {
+    pub api_logs: u64,
+    pub callback_logs: u64,
+}
+
+impl std::ops::AddAssign for PruneReport {
+    fn add_assign(&mut self, other: Self) {
+        self.api_logs += other.api_logs;
+        self.callback_logs += other.callback_logs;
+    }
+}
+
+impl From<db::Model> for RetentionPolicy {
+    fn from(model: db::Model) -> Self {
+        Self {
+            project_id: model.project_id,
+            max_age_days: model.max_age_days,
+            max_count: model.max_count,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub async fn for_project<C: ConnectionTrait>(
+        db: &C,
+        project_id: u32,
+    ) -> Result<Option<Self>, DbErr> {
+        Ok(db::Entity::find_by_id(project_id)
+            .one(db)
+            .await?
+            .map(Into::into))
+    }
+
+    pub async fn all<C: ConnectionTrait>(db: &C) -> Result<Vec<Self>, DbErr> {
+        Ok(db::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    pub async fn save<C: ConnectionTrait>(&self, db: &C) -> Result<(), DbErr> {
+        let model = db::ActiveModel {
+            project_id: Set(self.project_id),
+            max_age_days: Set(self.max_age_days),
+            max_count: Set(self.max_count),
+            updated_at: Set(Utc::now()),
+        };
+        db::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(db::Column::ProjectId)
+                    .update_columns([
+                        db::Column::MaxAgeDays,
+                        db::Column::MaxCount,
+                        db::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete<C: ConnectionTrait>(db: &C, project_id: u32) -> Result<bool, DbErr> {
+        let res = db::Entity::delete_by_id(project_id).exec(db).await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Deletes the project's logs that fall outside the policy.
+    pub async fn prune<C: ConnectionTrait>(&self, db: &C) -> Result<PruneReport, DbErr> {
+        let cutoff = self
+            .max_age_days
+            .map(|days| Utc::now() - chrono::Duration::days(days as i64));
+        let mut report = PruneReport::default();
+
+        if let Some(cutoff) = cutoff {
+            report.api_logs += api_logs::db::Entity::delete_many()
+                .filter(api_logs::db::Column::ProjectId.eq(self.project_id))
+                .filter(api_logs::db::Column::CreatedAt.lt(cutoff))
+                .exec(db)
+                .await?
+                .rows_affected;
+            report.callback_logs += callbacks::db::Entity::delete_many()
+                .filter(callbacks::db::Column::ProjectId.eq(self.project_id))
+                .filter(callbacks::db::Column::CreatedAt.lt(cutoff))
+                .exec(db)
+                .await?
+                .rows_affected;
+        }
+
+        if let Some(max_count) = self.max_count {
+            if let Some(oldest_kept) = api_logs::db::Entity::find()
+                .select_only()
+                .column(api_logs::db::Column::CreatedAt)
+                .filter(api_logs::db::Column::ProjectId.eq(self.project_id))
+                .order_by_desc(api_logs::db::Column::CreatedAt)
+                .offset(max_count.saturating_sub(1) as u64)
+                .into_tuple::<DateTimeUtc>()
+                .one(db)
+                .await?
+            {
+                report.api_logs += api_logs::db::Entity::delete_many()
+                    .filter(api_logs::db::Column::ProjectId.eq(self.project_id))
+                    .filter(api_logs::db::Column::CreatedAt.lt(oldest_kept))
+                    .exec(db)
+                    .await?
+                    .rows_affected;
+            }
+            if let Some(oldest_kept) = callbacks::db::Entity::find()
+                .select_only()
+                .column(callbacks::db::Column::Id)
+                .filter(callbacks::db::Column::ProjectId.eq(self.project_id))
+                .order_by_desc(callbacks::db::Column::Id)
+                .offset(max_count.saturating_sub(1) as u64)
+                .into_tuple::<u32>()
+                .one(db)
+                .await?
+            {
+                report.callback_logs += callbacks::db::Entity::delete_many()
+                    .filter(callbacks::db::Column::ProjectId.eq(self.project_id))
+                    .filter(callbacks::db::Column::Id.lt(oldest_kept))
+                    .exec(db)
+                    .await?
+                    .rows_affected;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Turns free text into an FTS5 query matching logs that contain every term, in any column.
+/// Terms are quoted so IDs like `ws_CO_1912` are matched as they are written.
+pub fn match_query(search: &str) -> Option<String> {
+    let terms: Vec<String> = search
+        .split_whitespace()
+        .filter(|term| term.chars().count() >= MIN_SEARCH_TERM)
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" AND "))
+}
+
+/// [`match_query`] for a search someone typed, failing when none of its terms can be looked up.
+pub fn required_match_query(search: &str) -> anyhow::Result<String> {
+    match_query(search)
+        .ok_or_else(|| anyhow::anyhow!("Search for at least {MIN_SEARCH_TERM} characters"))
+}
+
+/// Limits a query on `api_logs` to the logs matching `query`, from [`match_query`].
+pub fn api_logs_matching(query: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        "api_logs.id IN (SELECT log_id FROM api_log_search_keys WHERE key IN \
+         (SELECT rowid FROM api_logs_fts WHERE api_logs_fts MATCH ?))",
+        [query],
+    )
+}
+
+/// Limits a query on `callback_logs` to the logs matching `query`, from [`match_query`].
+pub fn callback_logs_matching(query: &str) -> SimpleExpr {
+    Expr::cust_with_values(
+        "callback_logs.id IN (SELECT rowid FROM callback_logs_fts WHERE callback_logs_fts MATCH ?)",
+        [query],
+    )
+}
@@ -0,0 +1,123 @@
+use anyhow::{Context, Result, bail};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::{
+    PruneReport, RetentionPolicy, api_logs_matching, callback_logs_matching, required_match_query,
+};
+use crate::{AppContext, api_logs::ApiLog, callbacks::CallbackLog};
+
+#[derive(Deserialize)]
+pub struct LogSearch {
+    project_id: Option<u32>,
+    query: String,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+/// The API and callback logs matching a search, newest first.
+#[derive(Serialize, Debug, Clone)]
+pub struct LogSearchResults {
+    pub api_logs: Vec<ApiLog>,
+    pub callback_logs: Vec<CallbackLog>,
+}
+
+pub async fn get_retention_policy(
+    ctx: &AppContext,
+    project_id: u32,
+) -> Result<Option<RetentionPolicy>> {
+    RetentionPolicy::for_project(&ctx.db, project_id)
+        .await
+        .context("Failed to get retention policy")
+}
+
+/// Saves the policy and prunes the project's logs by it right away.
+pub async fn set_retention_policy(
+    ctx: &AppContext,
+    policy: RetentionPolicy,
+) -> Result<PruneReport> {
+    if policy.max_count == Some(0) {
+        bail!("A retention policy has to keep at least one log");
+    }
+    policy
+        .save(&ctx.db)
+        .await
+        .context("Failed to save retention policy")?;
+    prune_logs(ctx, Some(policy.project_id)).await
+}
+
+pub async fn clear_retention_policy(ctx: &AppContext, project_id: u32) -> Result<bool> {
+    RetentionPolicy::delete(&ctx.db, project_id)
+        .await
+        .context("Failed to delete retention policy")
+}
+
+/// Prunes the project's logs, or every project's, by their retention policies. Projects
+/// without a policy keep everything.
+pub async fn prune_logs(ctx: &AppContext, project_id: Option<u32>) -> Result<PruneReport> {
+    let policies = match project_id {
+        Some(project_id) => RetentionPolicy::for_project(&ctx.db, project_id)
+            .await?
+            .into_iter()
+            .collect(),
+        None => RetentionPolicy::all(&ctx.db).await?,
+    };
+
+    let mut report = PruneReport::default();
+    for policy in policies {
+        let pruned = policy
+            .prune(&ctx.db)
+            .await
+            .with_context(|| format!("Failed to prune logs of project {}", policy.project_id))?;
+        if pruned != PruneReport::default() {
+            let _ = ctx.event_manager.emit_all(
+                "logs_pruned",
+                json!({
+                    "project_id": policy.project_id,
+                    "api_logs": pruned.api_logs,
+                    "callback_logs": pruned.callback_logs,
+                }),
+            );
+        }
+        report += pruned;
+    }
+    Ok(report)
+}
+
+/// Searches the request and response bodies and errors of API logs, and the payloads, responses
+/// and errors of callback logs. Every term has to appear, anywhere in a log.
+pub async fn search_logs(ctx: &AppContext, search: LogSearch) -> Result<LogSearchResults> {
+    let query = required_match_query(&search.query)?;
+
+    let mut api_logs = crate::api_logs::db::Entity::find()
+        .filter(api_logs_matching(&query))
+        .order_by_desc(crate::api_logs::db::Column::CreatedAt);
+    let mut callback_logs = crate::callbacks::db::Entity::find()
+        .filter(callback_logs_matching(&query))
+        .order_by_desc(crate::callbacks::db::Column::CreatedAt);
+
+    if let Some(project_id) = search.project_id {
+        api_logs = api_logs.filter(crate::api_logs::db::Column::ProjectId.eq(project_id));
+        callback_logs =
+            callback_logs.filter(crate::callbacks::db::Column::ProjectId.eq(project_id));
+    }
+
+    let api_logs = api_logs
+        .limit(search.limit)
+        .offset(search.offset)
+        .all(&ctx.db)
+        .await
+        .context("Failed to search API logs")?;
+    let callback_logs = callback_logs
+        .limit(search.limit)
+        .offset(search.offset)
+        .all(&ctx.db)
+        .await
+        .context("Failed to search callback logs")?;
+
+    Ok(LogSearchResults {
+        api_logs: api_logs.into_iter().map(Into::into).collect(),
+        callback_logs: callback_logs.into_iter().map(Into::into).collect(),
+    })
+}
@@ -0,0 +1,294 @@
+//! Prometheus metrics of what the sandboxes are sent and send back.
+//!
+//! Series carry a `project` label, so a sandbox can expose only its own. The ledger is shared by
+//! every project, its series are only in the server wide exposition.
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Upper bounds of the latency buckets, in seconds. STK pushes wait on the user, so the
+/// buckets run up to a few minutes.
+const BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 180.0,
+];
+
+/// The content type of [`Metrics::render`].
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+struct Family<T> {
+    name: &'static str,
+    help: &'static str,
+    series: BTreeMap<Labels, T>,
+}
+
+impl<T: Default> Family<T> {
+    fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            series: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, labels: Labels) -> &mut T {
+        self.series.entry(labels).or_default()
+    }
+
+    /// The series to expose, those of `project_id` if one is given.
+    fn visible(&self, project_id: Option<u32>) -> impl Iterator<Item = (&Labels, &T)> {
+        let project = project_id.map(|id| id.to_string());
+        self.series
+            .iter()
+            .filter(move |(labels, _)| match &project {
+                Some(project) => labels
+                    .iter()
+                    .any(|(name, value)| *name == "project" && value == project),
+                None => true,
+            })
+    }
+}
+
+impl Family<u64> {
+    fn render(&self, out: &mut String, project_id: Option<u32>) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} counter", self.name);
+        for (labels, value) in self.visible(project_id) {
+            let _ = writeln!(out, "{}{} {value}", self.name, format_labels(labels, None));
+        }
+    }
+}
+
+impl Family<Histogram> {
+    fn render(&self, out: &mut String, project_id: Option<u32>) {
+        let name = self.name;
+        let _ = writeln!(out, "# HELP {name} {}", self.help);
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for (labels, histogram) in self.visible(project_id) {
+            for (count, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                let le = bound.to_string();
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{} {count}",
+                    format_labels(labels, Some(&le))
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{name}_bucket{} {}",
+                format_labels(labels, Some("+Inf")),
+                histogram.count
+            );
+            let labels = format_labels(labels, None);
+            let _ = writeln!(out, "{name}_sum{labels} {}", histogram.sum);
+            let _ = writeln!(out, "{name}_count{labels} {}", histogram.count);
+        }
+    }
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{name}=\"{}\"", escape(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{le}\""));
+    }
+    if pairs.is_empty() {
+        return String::new();
+    }
+    format!("{{{}}}", pairs.join(","))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+struct Registry {
+    api_requests: Family<u64>,
+    api_request_duration: Family<Histogram>,
+    async_job_duration: Family<Histogram>,
+    async_results: Family<u64>,
+    callback_attempts: Family<u64>,
+    callback_failures: Family<u64>,
+    callback_duration: Family<Histogram>,
+    ledger_transactions: Family<u64>,
+    ledger_volume: Family<u64>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            api_requests: Family::new(
+                "pesa_api_requests_total",
+                "API requests received by the sandboxes.",
+            ),
+            api_request_duration: Family::new(
+                "pesa_api_request_duration_seconds",
+                "Time taken to answer an API request.",
+            ),
+            async_job_duration: Family::new(
+                "pesa_async_job_duration_seconds",
+                "Time taken by the background part of an asynchronous request.",
+            ),
+            async_results: Family::new(
+                "pesa_async_results_total",
+                "Results of asynchronous requests, such as STK pushes, by result code.",
+            ),
+            callback_attempts: Family::new(
+                "pesa_callback_attempts_total",
+                "Attempts made to deliver callbacks.",
+            ),
+            callback_failures: Family::new(
+                "pesa_callback_failures_total",
+                "Callbacks that could not be delivered.",
+            ),
+            callback_duration: Family::new(
+                "pesa_callback_duration_seconds",
+                "Time taken to deliver a callback, retries included.",
+            ),
+            ledger_transactions: Family::new(
+                "pesa_ledger_transactions_total",
+                "Transactions posted to the ledger.",
+            ),
+            ledger_volume: Family::new(
+                "pesa_ledger_volume_total",
+                "Amount moved by the transactions posted to the ledger.",
+            ),
+        }
+    }
+}
+
+/// The metrics registry, shared by every clone of the [`crate::AppContext`].
+#[derive(Clone, Default)]
+pub struct Metrics(Arc<Mutex<Registry>>);
+
+impl Metrics {
+    /// An API request answered by a sandbox. `route` is the matched route, not the raw path.
+    pub fn observe_request(
+        &self,
+        project_id: u32,
+        method: &str,
+        route: &str,
+        status: u16,
+        duration: Duration,
+    ) {
+        let mut registry = self.0.lock().unwrap();
+        *registry.api_requests.get(vec![
+            ("project", project_id.to_string()),
+            ("method", method.to_string()),
+            ("route", route.to_string()),
+            ("status", status.to_string()),
+        ]) += 1;
+        registry
+            .api_request_duration
+            .get(vec![
+                ("project", project_id.to_string()),
+                ("route", route.to_string()),
+            ])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// The background part of an asynchronous request finished with `result_code`, if the API
+    /// has one.
+    pub fn observe_async_job(
+        &self,
+        project_id: u32,
+        api: &str,
+        result_code: Option<String>,
+        duration: Duration,
+    ) {
+        let mut registry = self.0.lock().unwrap();
+        registry
+            .async_job_duration
+            .get(vec![
+                ("project", project_id.to_string()),
+                ("api", api.to_string()),
+            ])
+            .observe(duration.as_secs_f64());
+        if let Some(result_code) = result_code {
+            *registry.async_results.get(vec![
+                ("project", project_id.to_string()),
+                ("api", api.to_string()),
+                ("result_code", result_code),
+            ]) += 1;
+        }
+    }
+
+    /// A callback dispatch that took `attempts` tries and ended `delivered` or not.
+    pub fn observe_callback(
+        &self,
+        project_id: u32,
+        callback_type: &str,
+        attempts: u32,
+        delivered: bool,
+        duration: Duration,
+    ) {
+        let labels = vec![
+            ("project", project_id.to_string()),
+            ("type", callback_type.to_string()),
+        ];
+        let mut registry = self.0.lock().unwrap();
+        *registry.callback_attempts.get(labels.clone()) += attempts as u64;
+        if !delivered {
+            *registry.callback_failures.get(labels.clone()) += 1;
+        }
+        registry
+            .callback_duration
+            .get(labels)
+            .observe(duration.as_secs_f64());
+    }
+
+    /// A transaction posted to the ledger, `transaction_type` as a
+    /// [`crate::transactions::TransactionType`] is displayed.
+    pub fn observe_transaction(&self, transaction_type: &str, amount: i64) {
+        let labels = vec![("type", transaction_type.to_string())];
+        let mut registry = self.0.lock().unwrap();
+        *registry.ledger_transactions.get(labels.clone()) += 1;
+        *registry.ledger_volume.get(labels) += amount.unsigned_abs();
+    }
+
+    /// The Prometheus text exposition of every series, or of one project's.
+    pub fn render(&self, project_id: Option<u32>) -> String {
+        let registry = self.0.lock().unwrap();
+        let mut out = String::new();
+        registry.api_requests.render(&mut out, project_id);
+        registry.api_request_duration.render(&mut out, project_id);
+        registry.async_job_duration.render(&mut out, project_id);
+        registry.async_results.render(&mut out, project_id);
+        registry.callback_attempts.render(&mut out, project_id);
+        registry.callback_failures.render(&mut out, project_id);
+        registry.callback_duration.render(&mut out, project_id);
+        if project_id.is_none() {
+            registry.ledger_transactions.render(&mut out, None);
+            registry.ledger_volume.render(&mut out, None);
+        }
+        out
+    }
+}
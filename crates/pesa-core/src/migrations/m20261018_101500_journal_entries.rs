@@ -0,0 +1,252 @@
+use chrono::Utc;
+use sea_orm::{ActiveEnum, ConnectionTrait};
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    accounts::{AccountType, SYSTEM_ACCOUNT_ID},
+    journal::EntrySide,
+    transactions::{Ledger, TransactionStatus, TransactionType},
+};
+
+#[derive(Iden)]
+enum JournalEntries {
+    Table,
+    Id,
+    TransactionId,
+    AccountId,
+    Side,
+    Amount,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Transactions {
+    Table,
+    Id,
+    To,
+    Amount,
+    Fee,
+    Currency,
+    TransactionType,
+    Status,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Accounts {
+    Table,
+    Id,
+    Balance,
+    AccountType,
+    CreatedAt,
+    Disabled,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+impl Migration {
+    pub fn journal_entries_table() -> TableCreateStatement {
+        Table::create()
+            .table(JournalEntries::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(JournalEntries::Id)
+                    .integer()
+                    .not_null()
+                    .auto_increment()
+                    .primary_key(),
+            )
+            .col(
+                ColumnDef::new(JournalEntries::TransactionId)
+                    .string()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(JournalEntries::AccountId)
+                    .integer()
+                    .not_null(),
+            )
+            .col(ColumnDef::new(JournalEntries::Side).string().not_null())
+            .col(
+                ColumnDef::new(JournalEntries::Amount)
+                    .big_integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(JournalEntries::CreatedAt)
+                    .timestamp_with_time_zone()
+                    .not_null(),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(JournalEntries::Table, JournalEntries::TransactionId)
+                    .to(Transactions::Table, Transactions::Id)
+                    .on_update(ForeignKeyAction::NoAction)
+                    .on_delete(ForeignKeyAction::NoAction),
+            )
+            .foreign_key(
+                ForeignKey::create()
+                    .from(JournalEntries::Table, JournalEntries::AccountId)
+                    .to(Accounts::Table, Accounts::Id)
+                    .on_update(ForeignKeyAction::NoAction)
+                    .on_delete(ForeignKeyAction::NoAction),
+            )
+            .to_owned()
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        manager.create_table(Self::journal_entries_table()).await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_journal_entries_transaction_id")
+                    .table(JournalEntries::Table)
+                    .col(JournalEntries::TransactionId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_journal_entries_account_id")
+                    .table(JournalEntries::Table)
+                    .col(JournalEntries::AccountId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing balances were built up before the journal existed. Open each of them against
+        // the system account so the journal reproduces today's balances. The tables are read and
+        // written on the columns they have at this point, later migrations add to them.
+        let backend = db.get_database_backend();
+        let now = Utc::now().to_utc();
+        let balances = db
+            .query_all(
+                backend.build(
+                    Query::select()
+                        .columns([Accounts::Id, Accounts::Balance])
+                        .from(Accounts::Table)
+                        .and_where(Expr::col(Accounts::Id).ne(SYSTEM_ACCOUNT_ID))
+                        .and_where(
+                            Expr::col(Accounts::AccountType).ne(AccountType::System.to_string()),
+                        )
+                        .and_where(Expr::col(Accounts::Balance).ne(0))
+                        .order_by(Accounts::Id, Order::Asc),
+                ),
+            )
+            .await?;
+
+        if !balances.is_empty() {
+            let issued = balances
+                .iter()
+                .map(|row| row.try_get::<i64>("", &Accounts::Balance.to_string()))
+                .sum::<Result<i64, _>>()?;
+            // The system account has to exist before entries can be posted against it, and its
+            // balance becomes the negative of everything issued so the ledger sums to zero.
+            manager
+                .exec_stmt(
+                    Query::insert()
+                        .into_table(Accounts::Table)
+                        .columns([
+                            Accounts::Id,
+                            Accounts::Balance,
+                            Accounts::AccountType,
+                            Accounts::CreatedAt,
+                            Accounts::Disabled,
+                        ])
+                        .values_panic([
+                            SYSTEM_ACCOUNT_ID.into(),
+                            (-issued).into(),
+                            AccountType::System.to_string().into(),
+                            now.into(),
+                            false.into(),
+                        ])
+                        .on_conflict(
+                            OnConflict::column(Accounts::Id)
+                                .update_column(Accounts::Balance)
+                                .to_owned(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for row in balances {
+            let account_id: u32 = row.try_get("", &Accounts::Id.to_string())?;
+            let balance: i64 = row.try_get("", &Accounts::Balance.to_string())?;
+            let receipt = Ledger::generate_receipt();
+
+            manager
+                .exec_stmt(
+                    Query::insert()
+                        .into_table(Transactions::Table)
+                        .columns([
+                            Transactions::Id,
+                            Transactions::To,
+                            Transactions::Amount,
+                            Transactions::Fee,
+                            Transactions::Currency,
+                            Transactions::TransactionType,
+                            Transactions::Status,
+                            Transactions::CreatedAt,
+                        ])
+                        .values_panic([
+                            receipt.clone().into(),
+                            account_id.into(),
+                            balance.abs().into(),
+                            0.into(),
+                            "KES".into(),
+                            TransactionType::OpeningBalance.to_string().into(),
+                            TransactionStatus::Completed.to_string().into(),
+                            now.into(),
+                        ])
+                        .to_owned(),
+                )
+                .await?;
+
+            let (account_side, system_side) = if balance > 0 {
+                (EntrySide::Credit, EntrySide::Debit)
+            } else {
+                (EntrySide::Debit, EntrySide::Credit)
+            };
+
+            let mut entries = Query::insert()
+                .into_table(JournalEntries::Table)
+                .columns([
+                    JournalEntries::TransactionId,
+                    JournalEntries::AccountId,
+                    JournalEntries::Side,
+                    JournalEntries::Amount,
+                    JournalEntries::CreatedAt,
+                ])
+                .to_owned();
+            for (account_id, side) in [(account_id, account_side), (SYSTEM_ACCOUNT_ID, system_side)]
+            {
+                entries.values_panic([
+                    receipt.clone().into(),
+                    account_id.into(),
+                    side.to_value().into(),
+                    balance.abs().into(),
+                    now.into(),
+                ]);
+            }
+            manager.exec_stmt(entries).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(JournalEntries::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use sea_orm_migration::prelude::*;
+
+use crate::transactions::TransactionType;
+
+#[derive(Iden)]
+enum TransactionCosts {
+    Table,
+    TransactionType,
+    MinAmount,
+    MaxAmount,
+    FeeFixed,
+    FeePercentage,
+    BusinessId,
+    PaidBy,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionCosts::Table)
+                    .add_column(
+                        ColumnDef::new(TransactionCosts::BusinessId)
+                            .integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionCosts::Table)
+                    .add_column(
+                        ColumnDef::new(TransactionCosts::PaidBy)
+                            .string()
+                            .not_null()
+                            .default("customer"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transaction_costs_business_id")
+                    .table(TransactionCosts::Table)
+                    .col(TransactionCosts::BusinessId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Disbursments used to borrow the send money bands with the fee accrued on the
+        // business. Give them their own business paid bands so that keeps working.
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(TransactionCosts::Table)
+                    .columns([
+                        TransactionCosts::TransactionType,
+                        TransactionCosts::MinAmount,
+                        TransactionCosts::MaxAmount,
+                        TransactionCosts::FeeFixed,
+                        TransactionCosts::FeePercentage,
+                        TransactionCosts::PaidBy,
+                    ])
+                    .select_from(
+                        Query::select()
+                            .expr(Expr::val(TransactionType::Disbursment.to_string()))
+                            .columns([
+                                TransactionCosts::MinAmount,
+                                TransactionCosts::MaxAmount,
+                                TransactionCosts::FeeFixed,
+                                TransactionCosts::FeePercentage,
+                            ])
+                            .expr(Expr::val("business"))
+                            .from(TransactionCosts::Table)
+                            .and_where(
+                                Expr::col(TransactionCosts::TransactionType)
+                                    .eq(TransactionType::SendMoney.to_string()),
+                            )
+                            .and_where(Expr::col(TransactionCosts::BusinessId).is_null())
+                            .to_owned(),
+                    )
+                    .map_err(|err| DbErr::Migration(err.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(TransactionCosts::Table)
+                    .and_where(
+                        Expr::col(TransactionCosts::BusinessId)
+                            .is_not_null()
+                            .or(Expr::col(TransactionCosts::PaidBy).eq("business")),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_transaction_costs_business_id")
+                    .table(TransactionCosts::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionCosts::Table)
+                    .drop_column(TransactionCosts::PaidBy)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionCosts::Table)
+                    .drop_column(TransactionCosts::BusinessId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum TransactionCosts {
+    Table,
+    EffectiveFrom,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing bands keep a NULL date and become the base schedule.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionCosts::Table)
+                    .add_column(
+                        ColumnDef::new(TransactionCosts::EffectiveFrom)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transaction_costs_effective_from")
+                    .table(TransactionCosts::Table)
+                    .col(TransactionCosts::EffectiveFrom)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(TransactionCosts::Table)
+                    .and_where(Expr::col(TransactionCosts::EffectiveFrom).is_not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_transaction_costs_effective_from")
+                    .table(TransactionCosts::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TransactionCosts::Table)
+                    .drop_column(TransactionCosts::EffectiveFrom)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
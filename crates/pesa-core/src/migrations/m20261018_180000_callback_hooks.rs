@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum CallbackLogs {
+    Table,
+    HookDecision,
+    OriginalPayload,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only adds one column per ALTER TABLE.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CallbackLogs::Table)
+                    .add_column(ColumnDef::new(CallbackLogs::HookDecision).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CallbackLogs::Table)
+                    .add_column(
+                        ColumnDef::new(CallbackLogs::OriginalPayload)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [CallbackLogs::HookDecision, CallbackLogs::OriginalPayload] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(CallbackLogs::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum InboxRequests {
+    Table,
+    Id,
+    ProjectId,
+    Inbox,
+    Method,
+    Query,
+    Headers,
+    Body,
+    ResponseStatus,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum InboxResponses {
+    Table,
+    Id,
+    ProjectId,
+    Inbox,
+    Response,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Projects {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(InboxRequests::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InboxRequests::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(InboxRequests::ProjectId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(InboxRequests::Inbox).string().not_null())
+                    .col(ColumnDef::new(InboxRequests::Method).string().not_null())
+                    .col(ColumnDef::new(InboxRequests::Query).string().null())
+                    .col(ColumnDef::new(InboxRequests::Headers).string().not_null())
+                    .col(ColumnDef::new(InboxRequests::Body).string().null())
+                    .col(
+                        ColumnDef::new(InboxRequests::ResponseStatus)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(InboxRequests::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(InboxRequests::Table, InboxRequests::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_inbox_requests_project_inbox")
+                    .table(InboxRequests::Table)
+                    .col(InboxRequests::ProjectId)
+                    .col(InboxRequests::Inbox)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(InboxResponses::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InboxResponses::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(InboxResponses::ProjectId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(InboxResponses::Inbox).string().not_null())
+                    .col(ColumnDef::new(InboxResponses::Response).string().not_null())
+                    .col(
+                        ColumnDef::new(InboxResponses::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(InboxResponses::Table, InboxResponses::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_inbox_responses_project_inbox")
+                    .table(InboxResponses::Table)
+                    .col(InboxResponses::ProjectId)
+                    .col(InboxResponses::Inbox)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InboxResponses::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(InboxRequests::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
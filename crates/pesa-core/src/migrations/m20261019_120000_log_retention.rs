@@ -0,0 +1,178 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum LogRetention {
+    Table,
+    ProjectId,
+    MaxAgeDays,
+    MaxCount,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum ApiLogs {
+    Table,
+    ProjectId,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum CallbackLogs {
+    Table,
+    ProjectId,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Projects {
+    Table,
+    Id,
+}
+
+/// Trigram indexes, so a search matches any part of a phone number or an ID. External content
+/// keeps the text in the log tables, the triggers keep the indexes in step with them.
+const SEARCH_SCHEMA: &str = r#"
+CREATE VIRTUAL TABLE api_logs_fts USING fts5(
+    request_body, response_body, error_desc,
+    content='api_logs', content_rowid='rowid', tokenize='trigram'
+);
+CREATE TRIGGER api_logs_fts_insert AFTER INSERT ON api_logs BEGIN
+    INSERT INTO api_logs_fts(rowid, request_body, response_body, error_desc)
+    VALUES (new.rowid, new.request_body, new.response_body, new.error_desc);
+END;
+CREATE TRIGGER api_logs_fts_delete AFTER DELETE ON api_logs BEGIN
+    INSERT INTO api_logs_fts(api_logs_fts, rowid, request_body, response_body, error_desc)
+    VALUES ('delete', old.rowid, old.request_body, old.response_body, old.error_desc);
+END;
+CREATE TRIGGER api_logs_fts_update AFTER UPDATE ON api_logs BEGIN
+    INSERT INTO api_logs_fts(api_logs_fts, rowid, request_body, response_body, error_desc)
+    VALUES ('delete', old.rowid, old.request_body, old.response_body, old.error_desc);
+    INSERT INTO api_logs_fts(rowid, request_body, response_body, error_desc)
+    VALUES (new.rowid, new.request_body, new.response_body, new.error_desc);
+END;
+INSERT INTO api_logs_fts(api_logs_fts) VALUES ('rebuild');
+
+CREATE VIRTUAL TABLE callback_logs_fts USING fts5(
+    payload, response_body, error,
+    content='callback_logs', content_rowid='id', tokenize='trigram'
+);
+CREATE TRIGGER callback_logs_fts_insert AFTER INSERT ON callback_logs BEGIN
+    INSERT INTO callback_logs_fts(rowid, payload, response_body, error)
+    VALUES (new.id, new.payload, new.response_body, new.error);
+END;
+CREATE TRIGGER callback_logs_fts_delete AFTER DELETE ON callback_logs BEGIN
+    INSERT INTO callback_logs_fts(callback_logs_fts, rowid, payload, response_body, error)
+    VALUES ('delete', old.id, old.payload, old.response_body, old.error);
+END;
+CREATE TRIGGER callback_logs_fts_update AFTER UPDATE ON callback_logs BEGIN
+    INSERT INTO callback_logs_fts(callback_logs_fts, rowid, payload, response_body, error)
+    VALUES ('delete', old.id, old.payload, old.response_body, old.error);
+    INSERT INTO callback_logs_fts(rowid, payload, response_body, error)
+    VALUES (new.id, new.payload, new.response_body, new.error);
+END;
+INSERT INTO callback_logs_fts(callback_logs_fts) VALUES ('rebuild');
+"#;
+
+const DROP_SEARCH_SCHEMA: &str = r#"
+DROP TRIGGER IF EXISTS api_logs_fts_insert;
+DROP TRIGGER IF EXISTS api_logs_fts_delete;
+DROP TRIGGER IF EXISTS api_logs_fts_update;
+DROP TABLE IF EXISTS api_logs_fts;
+DROP TRIGGER IF EXISTS callback_logs_fts_insert;
+DROP TRIGGER IF EXISTS callback_logs_fts_delete;
+DROP TRIGGER IF EXISTS callback_logs_fts_update;
+DROP TABLE IF EXISTS callback_logs_fts;
+"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LogRetention::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LogRetention::ProjectId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LogRetention::MaxAgeDays).integer().null())
+                    .col(ColumnDef::new(LogRetention::MaxCount).integer().null())
+                    .col(
+                        ColumnDef::new(LogRetention::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(LogRetention::Table, LogRetention::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // pruning walks each project's logs by age
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_api_logs_project_created")
+                    .table(ApiLogs::Table)
+                    .col(ApiLogs::ProjectId)
+                    .col(ApiLogs::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_callback_logs_project_created")
+                    .table(CallbackLogs::Table)
+                    .col(CallbackLogs::ProjectId)
+                    .col(CallbackLogs::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(SEARCH_SCHEMA)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(DROP_SEARCH_SCHEMA)
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_callback_logs_project_created")
+                    .table(CallbackLogs::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_api_logs_project_created")
+                    .table(ApiLogs::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LogRetention::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
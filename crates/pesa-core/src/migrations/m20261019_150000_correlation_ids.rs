@@ -0,0 +1,164 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum ApiLogs {
+    Table,
+    ConversationId,
+}
+
+#[derive(Iden)]
+enum Transactions {
+    Table,
+    ConversationId,
+}
+
+#[derive(Iden)]
+enum RequestEvents {
+    Table,
+    Id,
+    ProjectId,
+    ConversationId,
+    Kind,
+    Detail,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Projects {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ApiLogs::Table)
+                    .add_column(ColumnDef::new(ApiLogs::ConversationId).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_api_logs_conversation_id")
+                    .table(ApiLogs::Table)
+                    .col(ApiLogs::ConversationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Transactions::Table)
+                    .add_column(ColumnDef::new(Transactions::ConversationId).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_transactions_conversation_id")
+                    .table(Transactions::Table)
+                    .col(Transactions::ConversationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RequestEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RequestEvents::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RequestEvents::ProjectId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RequestEvents::ConversationId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RequestEvents::Kind).string().not_null())
+                    .col(ColumnDef::new(RequestEvents::Detail).string().null())
+                    .col(
+                        ColumnDef::new(RequestEvents::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RequestEvents::Table, RequestEvents::ProjectId)
+                            .to(Projects::Table, Projects::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_request_events_conversation_id")
+                    .table(RequestEvents::Table)
+                    .col(RequestEvents::ConversationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RequestEvents::Table).to_owned())
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_transactions_conversation_id")
+                    .table(Transactions::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Transactions::Table)
+                    .drop_column(Transactions::ConversationId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_api_logs_conversation_id")
+                    .table(ApiLogs::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ApiLogs::Table)
+                    .drop_column(ApiLogs::ConversationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
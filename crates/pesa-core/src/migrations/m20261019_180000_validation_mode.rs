@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Projects {
+    Table,
+    ValidationMode,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing projects keep accepting what they did
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .add_column(
+                        ColumnDef::new(Projects::ValidationMode)
+                            .string()
+                            .not_null()
+                            .default("Lenient"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Projects::Table)
+                    .drop_column(Projects::ValidationMode)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,94 @@
+use sea_orm_migration::prelude::*;
+
+/// `api_logs` is keyed on a text ID, so the external content index of
+/// `m20261019_120000_log_retention` pointed at its implicit rowids, which a `VACUUM` may renumber.
+/// The index now keeps its own copy of the text, under an integer key each log is given once.
+const SEARCH_SCHEMA: &str = r#"
+DROP TRIGGER IF EXISTS api_logs_fts_insert;
+DROP TRIGGER IF EXISTS api_logs_fts_delete;
+DROP TRIGGER IF EXISTS api_logs_fts_update;
+DROP TABLE IF EXISTS api_logs_fts;
+
+CREATE TABLE api_log_search_keys (
+    key INTEGER PRIMARY KEY,
+    log_id TEXT NOT NULL UNIQUE
+);
+CREATE VIRTUAL TABLE api_logs_fts USING fts5(
+    request_body, response_body, error_desc, tokenize='trigram'
+);
+CREATE TRIGGER api_logs_fts_insert AFTER INSERT ON api_logs BEGIN
+    INSERT INTO api_log_search_keys(log_id) VALUES (new.id);
+    INSERT INTO api_logs_fts(rowid, request_body, response_body, error_desc)
+    SELECT key, new.request_body, new.response_body, new.error_desc
+    FROM api_log_search_keys WHERE log_id = new.id;
+END;
+CREATE TRIGGER api_logs_fts_delete AFTER DELETE ON api_logs BEGIN
+    DELETE FROM api_logs_fts
+    WHERE rowid = (SELECT key FROM api_log_search_keys WHERE log_id = old.id);
+    DELETE FROM api_log_search_keys WHERE log_id = old.id;
+END;
+CREATE TRIGGER api_logs_fts_update AFTER UPDATE ON api_logs BEGIN
+    UPDATE api_log_search_keys SET log_id = new.id WHERE log_id = old.id;
+    UPDATE api_logs_fts
+    SET request_body = new.request_body,
+        response_body = new.response_body,
+        error_desc = new.error_desc
+    WHERE rowid = (SELECT key FROM api_log_search_keys WHERE log_id = new.id);
+END;
+
+INSERT INTO api_log_search_keys(log_id) SELECT id FROM api_logs ORDER BY created_at;
+INSERT INTO api_logs_fts(rowid, request_body, response_body, error_desc)
+SELECT keys.key, logs.request_body, logs.response_body, logs.error_desc
+FROM api_log_search_keys keys JOIN api_logs logs ON logs.id = keys.log_id;
+"#;
+
+/// The external content index this migration replaced.
+const DROP_SEARCH_SCHEMA: &str = r#"
+DROP TRIGGER IF EXISTS api_logs_fts_insert;
+DROP TRIGGER IF EXISTS api_logs_fts_delete;
+DROP TRIGGER IF EXISTS api_logs_fts_update;
+DROP TABLE IF EXISTS api_logs_fts;
+DROP TABLE IF EXISTS api_log_search_keys;
+
+CREATE VIRTUAL TABLE api_logs_fts USING fts5(
+    request_body, response_body, error_desc,
+    content='api_logs', content_rowid='rowid', tokenize='trigram'
+);
+CREATE TRIGGER api_logs_fts_insert AFTER INSERT ON api_logs BEGIN
+    INSERT INTO api_logs_fts(rowid, request_body, response_body, error_desc)
+    VALUES (new.rowid, new.request_body, new.response_body, new.error_desc);
+END;
+CREATE TRIGGER api_logs_fts_delete AFTER DELETE ON api_logs BEGIN
+    INSERT INTO api_logs_fts(api_logs_fts, rowid, request_body, response_body, error_desc)
+    VALUES ('delete', old.rowid, old.request_body, old.response_body, old.error_desc);
+END;
+CREATE TRIGGER api_logs_fts_update AFTER UPDATE ON api_logs BEGIN
+    INSERT INTO api_logs_fts(api_logs_fts, rowid, request_body, response_body, error_desc)
+    VALUES ('delete', old.rowid, old.request_body, old.response_body, old.error_desc);
+    INSERT INTO api_logs_fts(rowid, request_body, response_body, error_desc)
+    VALUES (new.rowid, new.request_body, new.response_body, new.error_desc);
+END;
+INSERT INTO api_logs_fts(api_logs_fts) VALUES ('rebuild');
+"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(SEARCH_SCHEMA)
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(DROP_SEARCH_SCHEMA)
+            .await?;
+        Ok(())
+    }
+}
@@ -2,6 +2,16 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20251227_183827_initial_schema;
 mod m20251228_082822_apply_schema_changes;
+mod m20261018_101500_journal_entries;
+mod m20261018_140000_tariff_scopes;
+mod m20261018_160000_tariff_versions;
+mod m20261018_180000_callback_hooks;
+mod m20261019_090000_callback_inbox;
+mod m20261019_120000_log_retention;
+mod m20261019_150000_correlation_ids;
+mod m20261019_180000_validation_mode;
+mod m20261019_210000_api_log_queries;
+mod m20261019_220000_log_search_keys;
 
 pub struct Migrator;
 
@@ -11,6 +21,16 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20251227_183827_initial_schema::Migration),
             Box::new(m20251228_082822_apply_schema_changes::Migration),
+            Box::new(m20261018_101500_journal_entries::Migration),
+            Box::new(m20261018_140000_tariff_scopes::Migration),
+            Box::new(m20261018_160000_tariff_versions::Migration),
+            Box::new(m20261018_180000_callback_hooks::Migration),
+            Box::new(m20261019_090000_callback_inbox::Migration),
+            Box::new(m20261019_120000_log_retention::Migration),
+            Box::new(m20261019_150000_correlation_ids::Migration),
+            Box::new(m20261019_180000_validation_mode::Migration),
+            Box::new(m20261019_210000_api_log_queries::Migration),
+            Box::new(m20261019_220000_log_search_keys::Migration),
         ]
     }
 }
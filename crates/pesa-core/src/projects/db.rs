@@ -9,6 +9,7 @@ pub struct Model {
     pub business_id: u32,
     pub callback_url: Option<String>,
     pub simulation_mode: String,
+    pub validation_mode: String,
     pub stk_delay: u32,
     pub prefix: Option<String>,
     pub created_at: DateTimeUtc,
@@ -14,6 +14,7 @@ pub struct Project {
     pub name: String,
     pub callback_url: Option<String>,
     pub simulation_mode: SimulationMode,
+    pub validation_mode: ValidationMode,
     pub stk_delay: u32,
     pub prefix: Option<String>,
     pub created_at: DateTimeUtc,
@@ -27,12 +28,24 @@ pub enum SimulationMode {
     Realistic,
 }
 
+/// How closely a sandbox holds requests to Daraja's rules, see [`crate::server::validation`].
+#[derive(Display, EnumString, Debug, PartialEq, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum ValidationMode {
+    /// Takes any request the sandbox can make sense of.
+    #[default]
+    Lenient,
+    /// Turns away what Daraja would.
+    Strict,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CreateProject {
     pub business_id: u32,
     pub name: String,
     pub callback_url: Option<String>,
     pub simulation_mode: SimulationMode,
+    #[serde(default)]
+    pub validation_mode: ValidationMode,
     pub stk_delay: u32,
     pub prefix: Option<String>,
 }
@@ -42,6 +55,7 @@ pub struct UpdateProject {
     pub name: Option<String>,
     pub callback_url: Option<String>,
     pub simulation_mode: Option<SimulationMode>,
+    pub validation_mode: Option<ValidationMode>,
     pub stk_delay: Option<u32>,
     pub prefix: Option<String>,
 }
@@ -59,6 +73,7 @@ pub struct ProjectDetails {
     pub name: String,
     pub callback_url: Option<String>,
     pub simulation_mode: SimulationMode,
+    pub validation_mode: ValidationMode,
     pub business_id: u32,
     pub stk_delay: u32,
     pub prefix: Option<String>,
@@ -121,6 +136,7 @@ impl From<&db::Model> for Project {
                 .simulation_mode
                 .parse()
                 .unwrap_or(SimulationMode::Realistic),
+            validation_mode: value.validation_mode.parse().unwrap_or_default(),
             stk_delay: value.stk_delay,
             prefix: value.prefix.clone(),
             created_at: value.created_at,
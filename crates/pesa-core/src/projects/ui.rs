@@ -27,6 +27,7 @@ pub async fn create_project(ctx: &AppContext, input: CreateProject) -> Result<Pr
         callback_url: Set(input.callback_url),
         prefix: Set(input.prefix),
         simulation_mode: Set(input.simulation_mode.to_string()),
+        validation_mode: Set(input.validation_mode.to_string()),
         stk_delay: Set(input.stk_delay),
         created_at: Set(Utc::now().to_utc()),
         ..Default::default()
@@ -61,6 +62,7 @@ pub async fn create_project(ctx: &AppContext, input: CreateProject) -> Result<Pr
         name: project.name.clone(),
         callback_url: project.callback_url.clone(),
         simulation_mode: input.simulation_mode,
+        validation_mode: input.validation_mode,
         stk_delay: project.stk_delay,
         prefix: project.prefix.clone(),
         created_at: project.created_at,
@@ -100,6 +102,7 @@ pub async fn get_project(ctx: &AppContext, id: u32) -> Result<ProjectDetails> {
             .simulation_mode
             .parse()
             .unwrap_or(super::SimulationMode::Realistic),
+        validation_mode: project.validation_mode.parse().unwrap_or_default(),
         prefix: project.prefix,
         consumer_key: api_key.consumer_key,
         consumer_secret: api_key.consumer_secret,
@@ -184,6 +187,9 @@ pub async fn update_project(
     if let Some(simulation_mode) = input.simulation_mode {
         active_model.simulation_mode = Set(simulation_mode.to_string());
     }
+    if let Some(validation_mode) = input.validation_mode {
+        active_model.validation_mode = Set(validation_mode.to_string());
+    }
     if let Some(stk_delay) = input.stk_delay {
         active_model.stk_delay = Set(stk_delay);
     }
@@ -204,6 +210,7 @@ pub async fn update_project(
             .simulation_mode
             .parse()
             .unwrap_or(super::SimulationMode::Realistic),
+        validation_mode: updated_project.validation_mode.parse().unwrap_or_default(),
         stk_delay: updated_project.stk_delay,
         prefix: updated_project.prefix,
         created_at: updated_project.created_at,
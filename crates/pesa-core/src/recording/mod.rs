@@ -0,0 +1,315 @@
+//! Recording sandbox traffic into session files that can be replayed as regression tests.
+//!
+//! While a project records, the logging middleware captures every inbound request, the
+//! callback orchestrator every outbound callback, the STK push task what the user did with each
+//! prompt and the domain event dispatcher every ledger transaction touching the project's
+//! business. A snapshot of the database taken when recording starts is what a replay resets to.
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result, bail};
+use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::{
+    accounts::{mmf_accounts::MmfAccount, utility_accounts::UtilityAccount},
+    callbacks::{CallbackLog, CallbackStatus, CallbackType},
+    projects,
+    server::api::stkpush::ui::PromptDecision,
+    transactions_log::FullTransactionLog,
+};
+
+pub mod replay;
+pub mod ui;
+
+/// Where sessions and their snapshots are kept, under the app root.
+pub const SESSIONS_DIR: &str = "sessions";
+
+/// Everything a project's sandbox did while it was being recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,
+    pub project_id: u32,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub stopped_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The database snapshot taken when recording started, next to the session file.
+    pub snapshot: String,
+    pub entries: Vec<SessionEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEntry {
+    /// Milliseconds since recording started.
+    pub offset_ms: u64,
+    #[serde(flatten)]
+    pub event: SessionEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionEvent {
+    /// A request received by the sandbox, and what it answered.
+    Request {
+        method: String,
+        /// The path and query the request was sent to.
+        uri: String,
+        headers: BTreeMap<String, String>,
+        body: Option<String>,
+        status: u16,
+        response: Option<String>,
+    },
+    /// A transaction posted to the ledger.
+    Ledger { transaction: FullTransactionLog },
+    /// What the user did with an STK prompt, given back to the prompt when replaying.
+    Prompt { decision: PromptDecision },
+    /// A callback sent, or dropped, by the sandbox.
+    Callback {
+        callback_type: CallbackType,
+        url: String,
+        payload: Value,
+        status: CallbackStatus,
+        response_status: Option<i32>,
+        error: Option<String>,
+    },
+}
+
+impl SessionEvent {
+    /// The recorded form of an inbound request, as seen by the logging middleware.
+    pub fn request(
+        method: &str,
+        uri: &axum::http::Uri,
+        headers: &HashMap<String, String>,
+        body: Option<String>,
+        status: u16,
+        response: Option<String>,
+    ) -> Self {
+        SessionEvent::Request {
+            method: method.to_string(),
+            uri: uri
+                .path_and_query()
+                .map_or_else(|| uri.path().to_string(), |pq| pq.as_str().to_string()),
+            headers: headers
+                .iter()
+                .filter(|(name, _)| !name.eq_ignore_ascii_case("content-length"))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            body,
+            status,
+            response,
+        }
+    }
+}
+
+impl From<&CallbackLog> for SessionEvent {
+    fn from(log: &CallbackLog) -> Self {
+        SessionEvent::Callback {
+            callback_type: log.callback_type.clone(),
+            url: log.callback_url.clone(),
+            payload: log.payload.clone(),
+            status: log.status.clone(),
+            response_status: log.response_status,
+            error: log.error.clone(),
+        }
+    }
+}
+
+/// A session's name and size, without its entries.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub name: String,
+    pub project_id: u32,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub stopped_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub requests: usize,
+    pub callbacks: usize,
+    pub ledger_entries: usize,
+}
+
+impl Session {
+    pub fn requests(&self) -> impl Iterator<Item = &SessionEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.event, SessionEvent::Request { .. }))
+    }
+
+    pub fn callbacks(&self) -> impl Iterator<Item = &SessionEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.event, SessionEvent::Callback { .. }))
+    }
+
+    /// The transactions posted to the ledger, in the order they were posted.
+    pub fn ledger(&self) -> impl Iterator<Item = &FullTransactionLog> {
+        self.entries.iter().filter_map(|entry| match &entry.event {
+            SessionEvent::Ledger { transaction } => Some(transaction),
+            _ => None,
+        })
+    }
+
+    /// The users' decisions on STK prompts, in the order they were made.
+    pub fn prompt_decisions(&self) -> impl Iterator<Item = &PromptDecision> {
+        self.entries.iter().filter_map(|entry| match &entry.event {
+            SessionEvent::Prompt { decision } => Some(decision),
+            _ => None,
+        })
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            name: self.name.clone(),
+            project_id: self.project_id,
+            started_at: self.started_at,
+            stopped_at: self.stopped_at,
+            requests: self.requests().count(),
+            callbacks: self.callbacks().count(),
+            ledger_entries: self.ledger().count(),
+        }
+    }
+
+    pub fn path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{name}.json"))
+    }
+
+    pub fn load(dir: &Path, name: &str) -> Result<Self> {
+        if !is_valid_name(name) {
+            bail!("Invalid session name {name:?}");
+        }
+        let content = std::fs::read_to_string(Self::path(dir, name))
+            .with_context(|| format!("Failed to read session {name}"))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse session {name}"))
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir).context("Failed to create sessions directory")?;
+        let path = Self::path(dir, &self.name);
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write session {}", self.name))?;
+        Ok(path)
+    }
+}
+
+/// Whether `name` is usable as a session file name.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.')
+}
+
+/// Copies the database to `path`, the state a replay starts from.
+pub async fn snapshot_database(db: &DatabaseConnection, path: &Path) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path).context("Failed to replace old snapshot")?;
+    }
+    let target = path.to_string_lossy().replace('\'', "''");
+    db.execute_unprepared(&format!("VACUUM INTO '{target}'"))
+        .await
+        .context("Failed to snapshot database")?;
+    // an in-memory database vacuums into memory as well
+    if !path.exists() {
+        bail!("Only databases kept in a file can be snapshotted");
+    }
+    Ok(())
+}
+
+/// The utility and MMF accounts of the project's business, the ones its transactions touch.
+pub async fn project_accounts<C: ConnectionTrait>(db: &C, project_id: u32) -> Result<HashSet<u32>> {
+    let project = projects::db::Entity::find_by_id(project_id)
+        .one(db)
+        .await?
+        .with_context(|| format!("Project {project_id} not found"))?;
+    let mut accounts = HashSet::new();
+    if let Some(utility) = UtilityAccount::find_by_business_id(db, project.business_id).await? {
+        accounts.insert(utility.account_id);
+    }
+    if let Some(mmf) = MmfAccount::find_by_business_id(db, project.business_id).await? {
+        accounts.insert(mmf.account_id);
+    }
+    Ok(accounts)
+}
+
+struct ActiveRecording {
+    session: Session,
+    started: Instant,
+    /// See [`project_accounts`].
+    accounts: HashSet<u32>,
+}
+
+/// The projects being recorded, shared by every clone of the [`crate::AppContext`].
+#[derive(Clone, Default)]
+pub struct Recordings(Arc<Mutex<HashMap<u32, ActiveRecording>>>);
+
+impl Recordings {
+    /// Starts recording into `session`, with the ledger transactions touching `accounts`. Fails
+    /// if its project is already recording.
+    pub fn start(&self, session: Session, accounts: HashSet<u32>) -> Result<()> {
+        let mut active = self.0.lock().unwrap();
+        if active.contains_key(&session.project_id) {
+            bail!("Project {} is already recording", session.project_id);
+        }
+        active.insert(
+            session.project_id,
+            ActiveRecording {
+                session,
+                started: Instant::now(),
+                accounts,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stops recording the project and hands back what was recorded.
+    pub fn stop(&self, project_id: u32) -> Option<Session> {
+        let recording = self.0.lock().unwrap().remove(&project_id)?;
+        let mut session = recording.session;
+        session.stopped_at = Some(chrono::Utc::now());
+        Some(session)
+    }
+
+    pub fn is_recording(&self, project_id: u32) -> bool {
+        self.0.lock().unwrap().contains_key(&project_id)
+    }
+
+    /// What has been recorded so far.
+    pub fn current(&self, project_id: u32) -> Option<Session> {
+        self.0
+            .lock()
+            .unwrap()
+            .get(&project_id)
+            .map(|recording| recording.session.clone())
+    }
+
+    pub fn record(&self, project_id: u32, event: SessionEvent) {
+        if let Some(recording) = self.0.lock().unwrap().get_mut(&project_id) {
+            let offset_ms = recording.started.elapsed().as_millis() as u64;
+            recording
+                .session
+                .entries
+                .push(SessionEntry { offset_ms, event });
+        }
+    }
+
+    /// Records a ledger transaction in the recordings of the projects whose accounts it touches.
+    /// Transactions don't carry a project.
+    pub fn record_ledger(&self, transaction: &FullTransactionLog) {
+        for recording in self.0.lock().unwrap().values_mut() {
+            let touches = |id: u32| recording.accounts.contains(&id);
+            if !touches(transaction.to_id) && !transaction.from_id.is_some_and(touches) {
+                continue;
+            }
+            let offset_ms = recording.started.elapsed().as_millis() as u64;
+            recording.session.entries.push(SessionEntry {
+                offset_ms,
+                event: SessionEvent::Ledger {
+                    transaction: transaction.clone(),
+                },
+            });
+        }
+    }
+}
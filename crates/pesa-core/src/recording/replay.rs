@@ -0,0 +1,475 @@
+//! Replaying a recorded session against a copy of its snapshot, and what came out differently.
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use axum::{body::Body, http::Request};
+use http_body_util::BodyExt;
+use sea_orm_migration::MigratorTrait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::time::Instant;
+use tower::ServiceExt;
+
+use super::{Session, SessionEntry, SessionEvent, project_accounts};
+use crate::{
+    AppContext, AppEventManager,
+    migrations::Migrator,
+    server::{
+        api::stkpush::ui::{PromptDecision, STK_RESPONSE_REGISTRY, UserResponse},
+        create_router,
+    },
+};
+
+/// How long to wait for callbacks after the last request, unless told otherwise.
+const DEFAULT_SETTLE: Duration = Duration::from_secs(5);
+
+/// Fields that differ on every run, such as generated IDs and timestamps.
+const VOLATILE_FIELDS: [&str; 13] = [
+    "MerchantRequestID",
+    "CheckoutRequestID",
+    "ConversationID",
+    "TransactionID",
+    "TransID",
+    "TransTime",
+    "TransactionReceipt",
+    "MpesaReceiptNumber",
+    "TransactionDate",
+    "TransactionCompletedDateTime",
+    "B2CUtilityAccountAvailableFunds",
+    "access_token",
+    "expires_in",
+];
+
+/// Fields of ledger entries that differ on every run.
+const VOLATILE_LEDGER_FIELDS: [&str; 2] = ["transaction_id", "transaction_date"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayOptions {
+    pub session: String,
+    /// Send the requests back to back instead of with their recorded spacing.
+    #[serde(default)]
+    pub ignore_timing: bool,
+    /// How long to wait for outstanding callbacks after the last request.
+    #[serde(default)]
+    pub settle_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// A request was answered with another status or body.
+    Response,
+    /// A callback was sent with another payload or got another response.
+    Callback,
+    /// A recorded callback was not sent again.
+    MissingCallback,
+    /// A callback was sent that was not recorded.
+    UnexpectedCallback,
+    /// A transaction was posted with other amounts, accounts or balances, or was posted in only
+    /// one of the runs.
+    Ledger,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Divergence {
+    pub kind: DivergenceKind,
+    /// The position of the request, of the callback among those sent to the same URL, or of the
+    /// ledger entry.
+    pub index: usize,
+    /// The request URI, the callback URL or the transaction type.
+    pub target: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub session: String,
+    pub requests: usize,
+    pub callbacks: usize,
+    /// STK prompts the session holds no decision for, answered as timed out.
+    #[serde(default)]
+    pub unanswered_prompts: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    pub fn passed(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Keeps the replay's events out of the app, which still shows the real database, and answers
+/// STK prompts the way the user did while recording.
+struct ReplayEvents {
+    decisions: Mutex<VecDeque<PromptDecision>>,
+    unanswered: AtomicUsize,
+}
+
+impl AppEventManager for ReplayEvents {
+    fn emit_all(&self, event: &str, payload: Value) -> anyhow::Result<()> {
+        if event != "stk_push" {
+            return Ok(());
+        }
+        let Some((_, sender)) = payload["checkout_id"]
+            .as_str()
+            .and_then(|checkout_id| STK_RESPONSE_REGISTRY.remove(checkout_id))
+        else {
+            return Ok(());
+        };
+        let pin = payload["user"]["pin"].as_str().unwrap_or_default();
+
+        let response = match self.decisions.lock().unwrap().pop_front() {
+            Some(decision) => decision.response(pin),
+            None => {
+                self.unanswered.fetch_add(1, Ordering::Relaxed);
+                Some(UserResponse::Timeout)
+            }
+        };
+        // a dropped prompt is replayed by dropping the sender
+        if let Some(response) = response {
+            let _ = sender.send(response);
+        }
+        Ok(())
+    }
+}
+
+/// Replays the session named in `options` from `dir` and compares the outcome with the recording.
+///
+/// The sandbox runs in process on a copy of the session's snapshot, so the app's own database is
+/// left alone. Callbacks are sent for real, to the URLs the requests name. STK prompts get the
+/// decisions recorded with the session.
+pub async fn replay(ctx: &AppContext, dir: &Path, options: ReplayOptions) -> Result<ReplayReport> {
+    let recorded = Session::load(dir, &options.session)?;
+    let project_id = recorded.project_id;
+
+    let work_dir = tempfile::tempdir().context("Failed to create replay directory")?;
+    let db_path = work_dir.path().join("replay.sqlite");
+    std::fs::copy(dir.join(&recorded.snapshot), &db_path)
+        .with_context(|| format!("Failed to copy snapshot {}", recorded.snapshot))?;
+    let db = sea_orm::Database::connect(format!("sqlite://{}?mode=rwc", db_path.display()))
+        .await
+        .context("Failed to open snapshot")?;
+    Migrator::up(&db, None).await?;
+
+    let events = Arc::new(ReplayEvents {
+        decisions: Mutex::new(recorded.prompt_decisions().cloned().collect()),
+        unanswered: AtomicUsize::new(0),
+    });
+    let accounts = project_accounts(&db, project_id).await?;
+    let replay_ctx = AppContext {
+        db,
+        settings: ctx.settings.clone(),
+        event_manager: events.clone(),
+        running: Default::default(),
+        app_root: work_dir.path().to_path_buf(),
+        interceptors: ctx.interceptors.clone(),
+        callback_hooks: ctx.callback_hooks.clone(),
+        recordings: Default::default(),
+        // replayed traffic stays out of the live metrics
+        metrics: Default::default(),
+    };
+    replay_ctx.recordings.start(
+        Session {
+            name: format!("{}-replay", recorded.name),
+            project_id,
+            started_at: chrono::Utc::now(),
+            stopped_at: None,
+            snapshot: String::new(),
+            entries: Vec::new(),
+        },
+        accounts,
+    )?;
+    let router = create_router(replay_ctx.clone(), project_id, true);
+
+    let started = Instant::now();
+    let mut divergences = Vec::new();
+    // tokens issued during the recording mapped to the ones issued during the replay
+    let mut tokens: HashMap<String, String> = HashMap::new();
+
+    for (index, entry) in recorded.requests().enumerate() {
+        let SessionEvent::Request {
+            method,
+            uri,
+            headers,
+            body,
+            status,
+            response,
+        } = &entry.event
+        else {
+            continue;
+        };
+
+        if !options.ignore_timing {
+            tokio::time::sleep_until(started + Duration::from_millis(entry.offset_ms)).await;
+        }
+
+        let mut request = Request::builder().method(method.as_str()).uri(uri.as_str());
+        for (name, value) in headers {
+            if name.eq_ignore_ascii_case("host") || name.eq_ignore_ascii_case("content-length") {
+                continue;
+            }
+            let value = if name.eq_ignore_ascii_case("authorization") {
+                tokens
+                    .iter()
+                    .fold(value.clone(), |value, (old, new)| value.replace(old, new))
+            } else {
+                value.clone()
+            };
+            request = request.header(name.as_str(), value);
+        }
+        let request = request
+            .body(Body::from(body.clone().unwrap_or_default()))
+            .with_context(|| format!("Recorded request {index} is not a valid request"))?;
+
+        let replayed = router.clone().oneshot(request).await?;
+        let replayed_status = replayed.status().as_u16();
+        let bytes = replayed.into_body().collect().await?.to_bytes();
+        let replayed_body = String::from_utf8_lossy(&bytes).into_owned();
+
+        if let (Some(old), Some(new)) = (
+            access_token(response.as_deref()),
+            access_token(Some(&replayed_body)),
+        ) {
+            tokens.insert(old, new);
+        }
+
+        let expected = response_value(*status, response.as_deref());
+        let actual = response_value(replayed_status, Some(&replayed_body));
+        if normalize(&expected) != normalize(&actual) {
+            divergences.push(Divergence {
+                kind: DivergenceKind::Response,
+                index,
+                target: uri.clone(),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    // wait for the async jobs to send their callbacks
+    let expected_callbacks = recorded.callbacks().count();
+    let settle = options
+        .settle_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_SETTLE);
+    let mut deadline = Instant::now() + settle;
+    if !options.ignore_timing
+        && let Some(last) = recorded.callbacks().map(|entry| entry.offset_ms).max()
+    {
+        deadline = deadline.max(started + Duration::from_millis(last) + settle);
+    }
+    while Instant::now() < deadline {
+        let sent = replay_ctx
+            .recordings
+            .current(project_id)
+            .map_or(0, |session| session.callbacks().count());
+        if sent >= expected_callbacks {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let replayed = replay_ctx
+        .recordings
+        .stop(project_id)
+        .context("Replay recording went missing")?;
+    divergences.extend(compare_callbacks(&recorded, &replayed));
+    divergences.extend(compare_ledger(&recorded, &replayed));
+
+    Ok(ReplayReport {
+        session: recorded.name.clone(),
+        requests: recorded.requests().count(),
+        callbacks: expected_callbacks,
+        unanswered_prompts: events.unanswered.load(Ordering::Relaxed),
+        divergences,
+    })
+}
+
+fn access_token(body: Option<&str>) -> Option<String> {
+    let body: Value = serde_json::from_str(body?).ok()?;
+    Some(body["access_token"].as_str()?.to_string())
+}
+
+fn response_value(status: u16, body: Option<&str>) -> Value {
+    let body = body
+        .map(|body| serde_json::from_str(body).unwrap_or(Value::String(body.to_string())))
+        .unwrap_or_default();
+    serde_json::json!({ "status": status, "body": body })
+}
+
+/// Callbacks are compared per URL and type, in the order they were sent to it.
+fn compare_callbacks(recorded: &Session, replayed: &Session) -> Vec<Divergence> {
+    fn group(session: &Session) -> BTreeMap<(String, String), Vec<&SessionEntry>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for entry in session.callbacks() {
+            if let SessionEvent::Callback {
+                callback_type, url, ..
+            } = &entry.event
+            {
+                groups
+                    .entry((url.clone(), callback_type.to_string()))
+                    .or_default()
+                    .push(entry);
+            }
+        }
+        groups
+    }
+
+    fn callback_value(entry: &SessionEntry) -> Value {
+        match &entry.event {
+            SessionEvent::Callback {
+                payload,
+                status,
+                response_status,
+                error,
+                ..
+            } => serde_json::json!({
+                "payload": payload,
+                "status": status,
+                "response_status": response_status,
+                "error": error,
+            }),
+            _ => Value::Null,
+        }
+    }
+
+    let expected = group(recorded);
+    let mut actual = group(replayed);
+    let mut divergences = Vec::new();
+
+    for (key, expected) in expected {
+        let actual = actual.remove(&key).unwrap_or_default();
+        for index in 0..expected.len().max(actual.len()) {
+            let (kind, expected, actual) = match (expected.get(index), actual.get(index)) {
+                (Some(expected), Some(actual)) => {
+                    let (expected, actual) = (callback_value(expected), callback_value(actual));
+                    if normalize(&expected) == normalize(&actual) {
+                        continue;
+                    }
+                    (DivergenceKind::Callback, expected, actual)
+                }
+                (Some(expected), None) => (
+                    DivergenceKind::MissingCallback,
+                    callback_value(expected),
+                    Value::Null,
+                ),
+                (None, Some(actual)) => (
+                    DivergenceKind::UnexpectedCallback,
+                    Value::Null,
+                    callback_value(actual),
+                ),
+                (None, None) => continue,
+            };
+            divergences.push(Divergence {
+                kind,
+                index,
+                target: key.0.clone(),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    for ((url, _), unexpected) in actual {
+        for (index, entry) in unexpected.into_iter().enumerate() {
+            divergences.push(Divergence {
+                kind: DivergenceKind::UnexpectedCallback,
+                index,
+                target: url.clone(),
+                expected: Value::Null,
+                actual: callback_value(entry),
+            });
+        }
+    }
+
+    divergences
+}
+
+/// Ledger entries are compared in the order they were posted.
+fn compare_ledger(recorded: &Session, replayed: &Session) -> Vec<Divergence> {
+    fn comparable(transaction: &Value) -> Value {
+        let mut transaction = transaction.clone();
+        if let Value::Object(map) = &mut transaction {
+            for field in VOLATILE_LEDGER_FIELDS {
+                if let Some(value) = map.get_mut(field) {
+                    *value = Value::Null;
+                }
+            }
+        }
+        normalize(&transaction)
+    }
+
+    let to_values = |session: &Session| -> Vec<Value> {
+        session
+            .ledger()
+            .map(|transaction| serde_json::to_value(transaction).unwrap_or_default())
+            .collect()
+    };
+    let (expected, actual) = (to_values(recorded), to_values(replayed));
+
+    let mut divergences = Vec::new();
+    for index in 0..expected.len().max(actual.len()) {
+        let expected = expected.get(index).cloned().unwrap_or_default();
+        let actual = actual.get(index).cloned().unwrap_or_default();
+        if comparable(&expected) == comparable(&actual) {
+            continue;
+        }
+        let target = [&expected, &actual]
+            .iter()
+            .find_map(|transaction| transaction["transaction_type"].as_str())
+            .unwrap_or_default()
+            .to_string();
+        divergences.push(Divergence {
+            kind: DivergenceKind::Ledger,
+            index,
+            target,
+            expected,
+            actual,
+        });
+    }
+    divergences
+}
+
+/// Blanks out [`VOLATILE_FIELDS`], including `{"Name": .., "Value": ..}` items of callback
+/// metadata and `{"Key": .., "Value": ..}` result parameters.
+pub fn normalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let volatile_item = ["Name", "Key"].iter().any(|key| {
+                map.get(*key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|name| VOLATILE_FIELDS.contains(&name))
+            });
+            Value::Object(
+                map.iter()
+                    .map(|(key, value)| {
+                        let value = if VOLATILE_FIELDS.contains(&key.as_str())
+                            || (volatile_item && key == "Value")
+                        {
+                            Value::Null
+                        } else {
+                            normalize(value)
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+        Value::String(raw) => match serde_json::from_str::<Value>(raw) {
+            // callback and response bodies are sometimes JSON kept as text
+            Ok(json @ (Value::Object(_) | Value::Array(_))) => normalize(&json),
+            _ => value.clone(),
+        },
+        other => other.clone(),
+    }
+}
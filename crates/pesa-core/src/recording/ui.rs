@@ -0,0 +1,137 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use serde_json::json;
+
+use super::{
+    SESSIONS_DIR, Session, SessionSummary, is_valid_name, project_accounts,
+    replay::{self, ReplayOptions, ReplayReport},
+    snapshot_database,
+};
+use crate::AppContext;
+
+fn sessions_dir(ctx: &AppContext) -> PathBuf {
+    ctx.app_root.join(SESSIONS_DIR)
+}
+
+fn emit_status(ctx: &AppContext, project_id: u32, recording: bool, session: &SessionSummary) {
+    let _ = ctx.event_manager.emit_all(
+        "recording_status",
+        json!({
+            "project_id": project_id,
+            "recording": recording,
+            "session": session,
+        }),
+    );
+}
+
+/// Snapshots the database and starts recording the project's sandbox traffic.
+pub async fn start_recording(
+    ctx: &AppContext,
+    project_id: u32,
+    name: Option<String>,
+) -> Result<SessionSummary> {
+    if ctx.recordings.is_recording(project_id) {
+        bail!("Project {project_id} is already recording");
+    }
+    let name = name.unwrap_or_else(|| {
+        format!(
+            "project-{project_id}-{}",
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        )
+    });
+    if !is_valid_name(&name) {
+        bail!("Invalid session name {name:?}, use letters, digits, '-', '_' and '.'");
+    }
+
+    let accounts = project_accounts(&ctx.db, project_id).await?;
+    let dir = sessions_dir(ctx);
+    std::fs::create_dir_all(&dir).context("Failed to create sessions directory")?;
+    let snapshot = format!("{name}.sqlite");
+    snapshot_database(&ctx.db, &dir.join(&snapshot)).await?;
+
+    let session = Session {
+        name,
+        project_id,
+        started_at: chrono::Utc::now(),
+        stopped_at: None,
+        snapshot,
+        entries: Vec::new(),
+    };
+    let summary = session.summary();
+    ctx.recordings.start(session, accounts)?;
+    emit_status(ctx, project_id, true, &summary);
+    Ok(summary)
+}
+
+/// Stops recording the project and saves the session.
+pub async fn stop_recording(ctx: &AppContext, project_id: u32) -> Result<SessionSummary> {
+    let session = ctx
+        .recordings
+        .stop(project_id)
+        .with_context(|| format!("Project {project_id} is not recording"))?;
+    session.save(&sessions_dir(ctx))?;
+    let summary = session.summary();
+    emit_status(ctx, project_id, false, &summary);
+    Ok(summary)
+}
+
+/// The session the project is recording into, if any.
+pub async fn recording_status(ctx: &AppContext, project_id: u32) -> Result<Option<SessionSummary>> {
+    Ok(ctx
+        .recordings
+        .current(project_id)
+        .map(|session| session.summary()))
+}
+
+pub async fn list_sessions(ctx: &AppContext) -> Result<Vec<SessionSummary>> {
+    let dir = sessions_dir(ctx);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for entry in std::fs::read_dir(&dir).context("Failed to read sessions directory")? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match Session::load(&dir, name) {
+            Ok(session) => sessions.push(session.summary()),
+            Err(e) => tracing::warn!("Skipping session {}: {:#}", path.display(), e),
+        }
+    }
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.started_at));
+    Ok(sessions)
+}
+
+pub async fn get_session(ctx: &AppContext, name: String) -> Result<Session> {
+    Session::load(&sessions_dir(ctx), &name)
+}
+
+/// Deletes a session and its snapshot. Returns whether it existed.
+pub async fn delete_session(ctx: &AppContext, name: String) -> Result<bool> {
+    if !is_valid_name(&name) {
+        bail!("Invalid session name {name:?}");
+    }
+    let dir = sessions_dir(ctx);
+    let path = Session::path(&dir, &name);
+    if !path.exists() {
+        return Ok(false);
+    }
+    let session = Session::load(&dir, &name)?;
+    std::fs::remove_file(&path).context("Failed to delete session")?;
+    let snapshot = dir.join(&session.snapshot);
+    if !session.snapshot.is_empty() && snapshot.exists() {
+        std::fs::remove_file(snapshot).context("Failed to delete session snapshot")?;
+    }
+    Ok(true)
+}
+
+/// Replays a saved session against its snapshot and reports where the sandbox now differs.
+pub async fn replay_session(ctx: &AppContext, options: ReplayOptions) -> Result<ReplayReport> {
+    replay::replay(ctx, &sessions_dir(ctx), options).await
+}
@@ -68,6 +68,11 @@ pub async fn start_sandbox(
         }),
     )?;
 
+    // a sandbox starting is when its project's retention policy is applied
+    if let Err(e) = crate::logs::ui::prune_logs(ctx, Some(project_id)).await {
+        tracing::warn!(project_id, "failed to prune logs: {:#}", e);
+    }
+
     let (shutdown_tx, shutdown_rx) = oneshot::channel();
     let ctx_clone = ctx.clone();
     let host_clone = host.clone();
@@ -173,6 +178,14 @@ pub async fn sandbox_status(ctx: &AppContext, project_id: u32) -> Result<Value>
     }
 }
 
+/// The base URL of the project's sandbox, if it is running.
+pub fn sandbox_url(ctx: &AppContext, project_id: u32) -> Option<String> {
+    ctx.running
+        .get(&project_id)
+        .filter(|sandbox| !sandbox.handle.is_finished())
+        .map(|sandbox| format!("http://{}:{}", sandbox.host, sandbox.port))
+}
+
 pub async fn list_running_sandboxes(ctx: &AppContext) -> Result<Vec<Status>> {
     let mut instances: Vec<Status> = Vec::new();
     for rs in ctx.running.iter() {
@@ -13,6 +13,7 @@ use tempfile::{TempDir, tempdir};
 
 use super::{
     api_client::TestApiClient,
+    contract::ContractTarget,
     emitter::MainUiEmitter,
     events::{EventWatcher, TestEventManager},
 };
@@ -50,6 +51,8 @@ pub struct TestContext {
     _tmp_dir: Arc<TempDir>,
     /// API client for making HTTP requests during tests.
     pub api_client: TestApiClient,
+    /// The backend the contract tests run against.
+    pub target: Option<ContractTarget>,
 }
 
 impl TestContext {
@@ -101,6 +104,10 @@ impl TestContext {
             event_manager: test_event_manager.clone(), // Use the test manager for the app
             running: Arc::new(DashMap::new()),
             app_root: temp_path,
+            interceptors: Default::default(),
+            callback_hooks: Default::default(),
+            recordings: Default::default(),
+            metrics: Default::default(),
         };
 
         Ok(Self {
@@ -112,6 +119,7 @@ impl TestContext {
             current_test: None,
             _tmp_dir: Arc::new(temp_dir),
             api_client: TestApiClient::new(),
+            target: None,
         })
     }
 
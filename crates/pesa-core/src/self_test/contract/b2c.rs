@@ -0,0 +1,117 @@
+use chrono::Utc;
+use serde_json::json;
+
+use crate::{
+    self_test::{callback::CallbackManager, context::TestContext, runner::TestStep},
+    server::{
+        api::b2c::{
+            B2CCallbackResponse, B2CResultCodes, CallbackResult, KeyValueEntry, ReferenceData,
+            ResultParameters,
+        },
+        log::generate_conversation_id,
+    },
+    transactions::Ledger,
+};
+
+use super::{ContractCase, Expectation, malformed_cases, run_cases, target_url, with_extra_fields};
+
+pub struct B2CResultContract;
+
+impl TestStep for B2CResultContract {
+    async fn run(
+        &self,
+        context: &mut TestContext,
+        _callback_manager: &mut CallbackManager,
+    ) -> anyhow::Result<()> {
+        let url = target_url(context, |t| &t.b2c_result_path)?;
+        context.log(&format!("== B2C results to {} ==", url)).await;
+
+        let success = result(B2CResultCodes::Success);
+        let mut cases = vec![ContractCase::json(
+            "success",
+            &success,
+            Expectation::Accepted,
+        )?];
+        for code in [
+            B2CResultCodes::InsufficientBalance,
+            B2CResultCodes::BelowMinTransactionLimit,
+            B2CResultCodes::AboveMaxTransactionLimit,
+            B2CResultCodes::DailyTransferLimitExceeded,
+            B2CResultCodes::MaxBalanceExceeded,
+            B2CResultCodes::DebitPartyInvalidState,
+            B2CResultCodes::InitiatorNotAllowed,
+            B2CResultCodes::InvalidInitiatorInfo,
+            B2CResultCodes::AccountRuleDeclined,
+            B2CResultCodes::ProductAssignmentNotPermitted,
+            B2CResultCodes::UnsupportedCustomerType,
+            B2CResultCodes::SecurityCredentialLocked,
+            B2CResultCodes::OperatorDoesNotExist,
+        ] {
+            cases.push(ContractCase::json(
+                format!("result code {}: {}", code.code(), code),
+                &result(code),
+                Expectation::Accepted,
+            )?);
+        }
+        cases.push(ContractCase::json(
+            "duplicate of success",
+            &success,
+            Expectation::Accepted,
+        )?);
+        cases.push(ContractCase::json(
+            "extra fields",
+            &with_extra_fields(&serde_json::to_value(result(B2CResultCodes::Success))?),
+            Expectation::Accepted,
+        )?);
+        cases.extend(malformed_cases(&serde_json::to_value(&success)?)?);
+
+        run_cases(context, &url, cases).await
+    }
+}
+
+/// The result of a new payment, with the payment's parameters when it succeeded.
+fn result(code: B2CResultCodes) -> B2CCallbackResponse {
+    let receipt = Ledger::generate_receipt();
+    let succeeded = matches!(code, B2CResultCodes::Success);
+    let result_parameters = succeeded.then(|| ResultParameters {
+        result_parameter: [
+            ("TransactionAmount", json!(10)),
+            ("TransactionReceipt", json!(receipt)),
+            ("B2CRecipientIsRegisteredCustomer", json!("Y")),
+            ("B2CChargesPaidAccountAvailableFunds", json!(-4510.00)),
+            ("ReceiverPartyPublicName", json!("254708374149 - John Doe")),
+            (
+                "TransactionCompletedDateTime",
+                json!(Utc::now().format("%d.%m.%Y %H:%M:%S").to_string()),
+            ),
+            ("B2CUtilityAccountAvailableFunds", json!(10116.00)),
+            ("B2CWorkingAccountAvailableFunds", json!(900000.00)),
+        ]
+        .into_iter()
+        .map(|(key, value)| KeyValueEntry {
+            key: key.to_string(),
+            value,
+        })
+        .collect(),
+    });
+
+    B2CCallbackResponse {
+        result: CallbackResult {
+            result_type: 0,
+            result_code: code.code().to_string(),
+            result_desc: code.to_string(),
+            originator_conversation_id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: generate_conversation_id(),
+            transaction_id: receipt,
+            result_parameters,
+            reference_data: ReferenceData {
+                reference_item: KeyValueEntry {
+                    key: "QueueTimeoutURL".to_string(),
+                    value: json!(
+                        "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+                    ),
+                },
+            },
+        },
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::Local;
+
+use crate::{
+    self_test::{callback::CallbackManager, context::TestContext, runner::TestStep},
+    server::api::c2b::{C2bTransactionType, ValidationRequest},
+    transactions::{Ledger, ui::mask_msisdn_ke},
+};
+
+use super::{ContractCase, Expectation, malformed_cases, run_cases, target_url, with_extra_fields};
+
+pub struct C2BValidationContract;
+
+impl TestStep for C2BValidationContract {
+    async fn run(
+        &self,
+        context: &mut TestContext,
+        _callback_manager: &mut CallbackManager,
+    ) -> anyhow::Result<()> {
+        let url = target_url(context, |t| &t.c2b_validation_path)?;
+        context
+            .log(&format!("== C2B validation requests to {} ==", url))
+            .await;
+
+        run_cases(context, &url, cases(Expectation::Validation)?).await
+    }
+}
+
+pub struct C2BConfirmationContract;
+
+impl TestStep for C2BConfirmationContract {
+    async fn run(
+        &self,
+        context: &mut TestContext,
+        _callback_manager: &mut CallbackManager,
+    ) -> anyhow::Result<()> {
+        let url = target_url(context, |t| &t.c2b_confirmation_path)?;
+        context
+            .log(&format!("== C2B confirmations to {} ==", url))
+            .await;
+
+        run_cases(context, &url, cases(Expectation::Accepted)?).await
+    }
+}
+
+/// Validation and confirmation requests share a payload, so both endpoints get the same
+/// cases and differ in what counts as a correct answer.
+fn cases(expect: Expectation) -> anyhow::Result<Vec<ContractCase>> {
+    let paybill = request(C2bTransactionType::PayBill, "ACC-001", "100.00");
+    let mut cases = vec![
+        ContractCase::json("paybill payment", &paybill, expect)?,
+        ContractCase::json(
+            "till payment",
+            &request(C2bTransactionType::Till, "", "250.00"),
+            expect,
+        )?,
+        ContractCase::json(
+            "paybill payment without an account number",
+            &request(C2bTransactionType::PayBill, "", "1.00"),
+            expect,
+        )?,
+        ContractCase::json(
+            "large payment",
+            &request(C2bTransactionType::PayBill, "ACC-001", "250000.00"),
+            expect,
+        )?,
+        ContractCase::json("duplicate of paybill payment", &paybill, expect)?,
+        ContractCase::json(
+            "extra fields",
+            &with_extra_fields(&serde_json::to_value(request(
+                C2bTransactionType::PayBill,
+                "ACC-001",
+                "100.00",
+            ))?),
+            expect,
+        )?,
+    ];
+    cases.extend(malformed_cases(&serde_json::to_value(&paybill)?)?);
+    Ok(cases)
+}
+
+fn request(
+    transaction_type: C2bTransactionType,
+    bill_ref_number: &str,
+    amount: &str,
+) -> ValidationRequest {
+    ValidationRequest {
+        transaction_type,
+        transaction_id: Ledger::generate_receipt(),
+        transaction_time: Local::now().format("%Y%m%d%H%M%S").to_string(),
+        transaction_amount: amount.to_string(),
+        business_shortcode: "600000".to_string(),
+        bill_ref_number: bill_ref_number.to_string(),
+        invoice_number: String::new(),
+        org_account_balance: "49197.00".to_string(),
+        third_party_transaction_id: String::new(),
+        msisdn: mask_msisdn_ke("254708374149"),
+        first_name: "John".to_string(),
+        middle_name: String::new(),
+        last_name: "Doe".to_string(),
+    }
+}
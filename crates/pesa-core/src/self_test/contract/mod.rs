@@ -0,0 +1,293 @@
+//! Contract tests: the reverse of the self-tests. Instead of checking the playground, they
+//! send the callbacks M-Pesa sends to a user's backend and check that it answers them the way
+//! M-Pesa expects.
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{define_tests, server::api::c2b::ValidationResponse};
+
+use super::context::TestContext;
+
+pub mod b2c;
+pub mod c2b;
+pub mod stk;
+
+define_tests!(ContractTests as "contract";
+    StkCallback {
+        name: "stk_callback",
+        description: "Sends STK push callbacks for success, every failure code, duplicates and malformed payloads",
+        tags: ["stk"],
+        ctor: stk::StkCallbackContract
+    },
+    B2CResult {
+        name: "b2c_result",
+        description: "Sends B2C results for success, every failure code, duplicates and malformed payloads",
+        tags: ["b2c"],
+        ctor: b2c::B2CResultContract
+    },
+    C2BValidation {
+        name: "c2b_validation",
+        description: "Sends C2B validation requests and checks the answers parse as validation responses",
+        tags: ["c2b"],
+        ctor: c2b::C2BValidationContract
+    },
+    C2BConfirmation {
+        name: "c2b_confirmation",
+        description: "Sends C2B confirmations for paybill and till payments, duplicates and malformed payloads",
+        tags: ["c2b"],
+        ctor: c2b::C2BConfirmationContract
+    },
+);
+
+/// How long M-Pesa waits for an endpoint to answer.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// The backend the contract tests run against, and where its callback endpoints live.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractTarget {
+    pub base_url: String,
+    #[serde(default = "default_stk_callback_path")]
+    pub stk_callback_path: String,
+    #[serde(default = "default_b2c_result_path")]
+    pub b2c_result_path: String,
+    #[serde(default = "default_c2b_validation_path")]
+    pub c2b_validation_path: String,
+    #[serde(default = "default_c2b_confirmation_path")]
+    pub c2b_confirmation_path: String,
+}
+
+fn default_stk_callback_path() -> String {
+    "/mpesa/stk/callback".to_string()
+}
+
+fn default_b2c_result_path() -> String {
+    "/mpesa/b2c/result".to_string()
+}
+
+fn default_c2b_validation_path() -> String {
+    "/mpesa/c2b/validation".to_string()
+}
+
+fn default_c2b_confirmation_path() -> String {
+    "/mpesa/c2b/confirmation".to_string()
+}
+
+impl ContractTarget {
+    /// A target with the default endpoint paths.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            stk_callback_path: default_stk_callback_path(),
+            b2c_result_path: default_b2c_result_path(),
+            c2b_validation_path: default_c2b_validation_path(),
+            c2b_confirmation_path: default_c2b_confirmation_path(),
+        }
+    }
+
+    /// Checks that the base URL is an http(s) URL.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let url = reqwest::Url::parse(&self.base_url)
+            .with_context(|| format!("Invalid target URL '{}'", self.base_url))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!(
+                "Target URL '{}' must be an http or https URL",
+                self.base_url
+            );
+        }
+        Ok(())
+    }
+
+    /// The full URL of one of the target's endpoints.
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+/// The URL of the endpoint `path` picks from the context's target.
+fn target_url(
+    context: &TestContext,
+    path: impl Fn(&ContractTarget) -> &str,
+) -> anyhow::Result<String> {
+    let target = context
+        .target
+        .as_ref()
+        .context("No contract target set, contract tests need the URL of the backend to test")?;
+    Ok(target.url(path(target)))
+}
+
+/// What a correct answer to a contract case looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expectation {
+    /// A 2xx status.
+    Accepted,
+    /// A 2xx status and a body that parses as a `c2b::ValidationResponse`.
+    Validation,
+    /// Anything but a server error. Malformed payloads may be rejected, but must not break the
+    /// endpoint.
+    Handled,
+}
+
+/// One request sent to an endpoint of the target.
+struct ContractCase {
+    name: String,
+    body: String,
+    expect: Expectation,
+}
+
+impl ContractCase {
+    fn json(
+        name: impl Into<String>,
+        body: &impl Serialize,
+        expect: Expectation,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: name.into(),
+            body: serde_json::to_string(body)?,
+            expect,
+        })
+    }
+
+    fn raw(name: impl Into<String>, body: &str) -> Self {
+        Self {
+            name: name.into(),
+            body: body.to_string(),
+            expect: Expectation::Handled,
+        }
+    }
+}
+
+/// Cases that break a valid payload: truncated, empty, null and mistyped.
+fn malformed_cases(valid: &Value) -> anyhow::Result<Vec<ContractCase>> {
+    let text = valid.to_string();
+    let half = (0..=text.len() / 2)
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0);
+    Ok(vec![
+        ContractCase::raw("malformed: truncated JSON", &text[..half]),
+        ContractCase::raw("malformed: empty body", ""),
+        ContractCase::raw("malformed: empty object", "{}"),
+        ContractCase::json(
+            "malformed: null values",
+            &map_leaves(valid, &|_| Value::Null),
+            Expectation::Handled,
+        )?,
+        ContractCase::json(
+            "malformed: wrong value types",
+            &map_leaves(valid, &|leaf| match leaf {
+                Value::String(_) => Value::from(0),
+                _ => Value::from("0"),
+            }),
+            Expectation::Handled,
+        )?,
+    ])
+}
+
+/// `value` with an unknown field added to every object, as M-Pesa does when it extends a
+/// payload.
+fn with_extra_fields(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut map: serde_json::Map<String, Value> = map
+                .iter()
+                .map(|(key, value)| (key.clone(), with_extra_fields(value)))
+                .collect();
+            map.insert("UnexpectedField".to_string(), Value::from("unexpected"));
+            Value::Object(map)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(with_extra_fields).collect()),
+        leaf => leaf.clone(),
+    }
+}
+
+fn map_leaves(value: &Value, f: &impl Fn(&Value) -> Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), map_leaves(value, f)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|v| map_leaves(v, f)).collect()),
+        leaf => f(leaf),
+    }
+}
+
+/// Sends every case to `url`, in order, and fails listing the cases that got a wrong answer.
+async fn run_cases(
+    context: &TestContext,
+    url: &str,
+    cases: Vec<ContractCase>,
+) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
+    let total = cases.len();
+    let mut failures = Vec::new();
+
+    for case in cases {
+        let started = Instant::now();
+        let result = client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(case.body)
+            .timeout(RESPONSE_TIMEOUT)
+            .send()
+            .await;
+
+        let verdict = match result {
+            Err(e) if e.is_timeout() => Err(format!("no answer within {:?}", RESPONSE_TIMEOUT)),
+            Err(e) => Err(format!("request failed: {}", e)),
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                context
+                    .log(&format!(
+                        "{}: {} in {}ms {}",
+                        case.name,
+                        status,
+                        started.elapsed().as_millis(),
+                        body
+                    ))
+                    .await;
+                check(case.expect, status, &body)
+            }
+        };
+        if let Err(reason) = verdict {
+            context
+                .log(&format!("FAILED {}: {}", case.name, reason))
+                .await;
+            failures.push(format!("{}: {}", case.name, reason));
+        }
+    }
+
+    if !failures.is_empty() {
+        bail!(
+            "{} of {} cases got a wrong answer from {}:\n{}",
+            failures.len(),
+            total,
+            url,
+            failures.join("\n")
+        );
+    }
+    Ok(())
+}
+
+fn check(expect: Expectation, status: reqwest::StatusCode, body: &str) -> Result<(), String> {
+    match expect {
+        Expectation::Handled if status.is_server_error() => Err(format!(
+            "server error {}, expected the payload to be rejected or ignored",
+            status
+        )),
+        Expectation::Handled => Ok(()),
+        _ if !status.is_success() => Err(format!("status {}, expected 2xx", status)),
+        Expectation::Accepted => Ok(()),
+        Expectation::Validation => serde_json::from_str::<ValidationResponse>(body)
+            .map(|_| ())
+            .map_err(|e| format!("answer is not a validation response ({}): {}", e, body)),
+    }
+}
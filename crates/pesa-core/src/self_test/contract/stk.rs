@@ -0,0 +1,106 @@
+use chrono::Utc;
+use serde_json::json;
+
+use crate::{
+    self_test::{callback::CallbackManager, context::TestContext, runner::TestStep},
+    server::api::stkpush::{
+        CallbackItem, CallbackMetadata, StkCallback, StkCallbackBody, StkCallbackBodyWrapper,
+        StkPushResultCode, generate_checkout_request_id, generate_merchant_request_id,
+    },
+    transactions::Ledger,
+};
+
+use super::{ContractCase, Expectation, malformed_cases, run_cases, target_url, with_extra_fields};
+
+pub struct StkCallbackContract;
+
+impl TestStep for StkCallbackContract {
+    async fn run(
+        &self,
+        context: &mut TestContext,
+        _callback_manager: &mut CallbackManager,
+    ) -> anyhow::Result<()> {
+        let url = target_url(context, |t| &t.stk_callback_path)?;
+        context
+            .log(&format!("== STK push callbacks to {} ==", url))
+            .await;
+
+        let success = callback(StkPushResultCode::Success);
+        let mut cases = vec![ContractCase::json(
+            "success",
+            &success,
+            Expectation::Accepted,
+        )?];
+        for code in [
+            StkPushResultCode::InsufficientBalance,
+            StkPushResultCode::UnableToObtainSubscriberLock,
+            StkPushResultCode::TransactionHasExpired,
+            StkPushResultCode::SystemError,
+            StkPushResultCode::RequestCancelledByUser,
+            StkPushResultCode::DSTimeout,
+            StkPushResultCode::InitiatorInformationInvalid,
+            StkPushResultCode::ErrorSendingPushRequest,
+        ] {
+            cases.push(ContractCase::json(
+                format!("result code {}: {}", code.code(), code),
+                &callback(code),
+                Expectation::Accepted,
+            )?);
+        }
+        cases.push(ContractCase::json(
+            "duplicate of success",
+            &success,
+            Expectation::Accepted,
+        )?);
+        cases.push(ContractCase::json(
+            "extra fields",
+            &with_extra_fields(&serde_json::to_value(callback(StkPushResultCode::Success))?),
+            Expectation::Accepted,
+        )?);
+        cases.extend(malformed_cases(&serde_json::to_value(&success)?)?);
+
+        run_cases(context, &url, cases).await
+    }
+}
+
+/// A callback for a new request, with the payment's metadata when it succeeded.
+fn callback(result_code: StkPushResultCode) -> StkCallbackBodyWrapper {
+    let metadata = matches!(result_code, StkPushResultCode::Success).then(|| CallbackMetadata {
+        item: vec![
+            CallbackItem {
+                name: "Amount".to_string(),
+                value: json!(1.0),
+            },
+            CallbackItem {
+                name: "MpesaReceiptNumber".to_string(),
+                value: Ledger::generate_receipt().into(),
+            },
+            CallbackItem {
+                name: "TransactionDate".to_string(),
+                value: json!(
+                    Utc::now()
+                        .format("%Y%m%d%H%M%S")
+                        .to_string()
+                        .parse::<u64>()
+                        .unwrap_or_default()
+                ),
+            },
+            CallbackItem {
+                name: "PhoneNumber".to_string(),
+                value: json!(254708374149u64),
+            },
+        ],
+    });
+
+    StkCallbackBodyWrapper {
+        body: StkCallbackBody {
+            callback: StkCallback {
+                merchant_request_id: generate_merchant_request_id(),
+                checkout_request_id: generate_checkout_request_id(),
+                result_code: result_code.code(),
+                result_desc: result_code.to_string(),
+                metadata,
+            },
+        },
+    }
+}
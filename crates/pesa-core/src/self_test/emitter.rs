@@ -9,7 +9,7 @@ use super::context::TestMode;
 pub use super::context::SELF_TEST_PROGRESS_LOG;
 pub use super::runner::{SELF_TEST_FINISH, SELF_TEST_PLAN, SELF_TEST_START, SELF_TEST_STEP_UPDATE};
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TestStatus {
     Pending,
@@ -1,15 +1,23 @@
 /// Defines the `SelfTests` enum and the `all_tests` function, which together
 /// constitute the full, ordered suite of self-tests.
 ///
+/// Other suites name their enum and report name first, as in
+/// `define_tests!(ContractTests as "contract"; ...)`. The enum implements
+/// `TestSuite`, so `TestRunner::run_suite` can run it.
+///
 /// This macro is the central point of registration for all `TestStep` implementations.
 /// The order of declaration in the macro determines the order of execution.
 ///
 /// # Syntax
 ///
 /// The macro takes a comma-separated list of test definitions. Each definition
-/// has a `VariantName` for the enum, a UI display `name`, a `description`, and
-/// a `ctor` (constructor) which should be an expression that creates an instance
-/// of a struct that implements the `TestStep` trait.
+/// has a `VariantName` for the enum, a UI display `name`, a `description`,
+/// optional `tags` and `depends_on`, and a `ctor` (constructor) which should be
+/// an expression that creates an instance of a struct that implements the
+/// `TestStep` trait.
+///
+/// Tests listed in `depends_on` run first whenever the test is selected, and
+/// must be declared before it.
 ///
 /// ```rust,ignore
 /// define_tests!(
@@ -19,6 +27,10 @@
 ///         name: "my_test",
 ///         // The description displayed in the UI
 ///         description: "Tests a specific feature.",
+///         // Optional labels to select tests by
+///         tags: ["daraja"],
+///         // Optional tests whose state this test reads
+///         depends_on: [CreateProject],
 ///         // An expression that constructs the TestStep struct
 ///         ctor: path::to::MyTestStepStruct
 ///     },
@@ -28,7 +40,7 @@
 ///
 /// # Example
 ///
-/// ```rust
+/// ```rust,ignore
 /// // in `my_test.rs`
 /// // pub struct MyTestStep;
 /// // impl TestStep for MyTestStep { ... }
@@ -52,21 +64,25 @@
 #[macro_export]
 macro_rules! define_tests {
     (
+        $suite:ident as $suite_name:literal;
         $(
             $variant:ident {
                 name: $name:expr,
                 description: $desc:expr,
+                $(tags: [$($tag:expr),* $(,)?],)?
+                $(depends_on: [$($dep:ident),* $(,)?],)?
                 ctor: $ctor:expr $(,)?
             }
         ),+ $(,)?
     ) => {
-        pub enum SelfTests {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $suite {
             $(
                 $variant,
             )+
         }
 
-        impl SelfTests {
+        impl $suite {
             pub fn name(&self) -> &'static str {
                 match self {
                     $(
@@ -83,6 +99,23 @@ macro_rules! define_tests {
                 }
             }
 
+            pub fn tags(&self) -> &'static [&'static str] {
+                match self {
+                    $(
+                        Self::$variant => &[$($($tag),*)?],
+                    )+
+                }
+            }
+
+            /// The tests that must pass before this one can run.
+            pub fn dependencies(&self) -> &'static [$suite] {
+                match self {
+                    $(
+                        Self::$variant => &[$($($suite::$dep),*)?],
+                    )+
+                }
+            }
+
             pub async fn run(
                 &self,
                 ctx: &mut $crate::self_test::context::TestContext,
@@ -92,19 +125,55 @@ macro_rules! define_tests {
                     $(
                         Self::$variant => {
                             let t = $ctor;
-                            t.run(ctx, cb).await
+                            $crate::self_test::runner::TestStep::run(&t, ctx, cb).await
                         }
                     )+
                 }
             }
         }
 
-        pub fn all_tests() -> Vec<SelfTests> {
+        pub fn all_tests() -> Vec<$suite> {
             vec![
                 $(
-                    SelfTests::$variant,
+                    $suite::$variant,
                 )+
             ]
         }
+
+        impl $crate::self_test::runner::TestSuite for $suite {
+            const NAME: &'static str = $suite_name;
+
+            fn all() -> Vec<Self> {
+                all_tests()
+            }
+
+            fn name(&self) -> &'static str {
+                Self::name(self)
+            }
+
+            fn description(&self) -> &'static str {
+                Self::description(self)
+            }
+
+            fn tags(&self) -> &'static [&'static str] {
+                Self::tags(self)
+            }
+
+            fn dependencies(&self) -> &'static [Self] {
+                Self::dependencies(self)
+            }
+
+            fn run<'a>(
+                &'a self,
+                context: &'a mut $crate::self_test::context::TestContext,
+                callback_manager: &'a mut $crate::self_test::callback::CallbackManager,
+            ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send + 'a {
+                Self::run(self, context, callback_manager)
+            }
+        }
+    };
+    // the playground's own suite
+    ($($tests:tt)+) => {
+        $crate::define_tests!(SelfTests as "self-test"; $($tests)+);
     };
 }
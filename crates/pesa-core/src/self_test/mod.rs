@@ -1,9 +1,11 @@
 pub mod api_client;
 pub mod callback;
 pub mod context;
+pub mod contract;
 pub mod emitter;
 pub mod events;
 pub mod macros;
+pub mod report;
 pub mod runner;
 pub mod tests;
 pub mod ui;
@@ -0,0 +1,162 @@
+//! Test results in the formats CI systems read: JUnit XML and JSON.
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use super::emitter::TestStatus;
+
+/// The outcome of a single test.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCaseReport {
+    pub name: String,
+    pub status: TestStatus,
+    /// Why the test did not pass, empty when it passed.
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+/// The tests of one file or suite.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestSuiteReport {
+    pub name: String,
+    pub cases: Vec<TestCaseReport>,
+}
+
+/// Every suite of a test run.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestReport {
+    pub name: String,
+    pub suites: Vec<TestSuiteReport>,
+}
+
+impl TestSuiteReport {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            cases: Vec::new(),
+        }
+    }
+
+    fn count(&self, status: &[TestStatus]) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| status.contains(&case.status))
+            .count()
+    }
+
+    fn duration_secs(&self) -> f64 {
+        self.cases.iter().map(|c| c.duration_ms).sum::<u64>() as f64 / 1000.0
+    }
+}
+
+impl TestReport {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            suites: Vec::new(),
+        }
+    }
+
+    pub fn cases(&self) -> impl Iterator<Item = &TestCaseReport> {
+        self.suites.iter().flat_map(|suite| suite.cases.iter())
+    }
+
+    /// Number of tests that ran and did not pass.
+    pub fn failures(&self) -> usize {
+        self.cases()
+            .filter(|case| {
+                matches!(
+                    case.status,
+                    TestStatus::Failed | TestStatus::Panicked | TestStatus::TimedOut
+                )
+            })
+            .count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+    }
+
+    /// Renders the report as JUnit XML. Failed tests become `<failure>`, panicked and timed out
+    /// ones `<error>` and tests that never ran `<skipped>`.
+    pub fn to_junit_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let suites = &self.suites;
+        let failures: usize = suites.iter().map(|s| s.count(&[TestStatus::Failed])).sum();
+        let errors: usize = suites
+            .iter()
+            .map(|s| s.count(&[TestStatus::Panicked, TestStatus::TimedOut]))
+            .sum();
+        let _ = writeln!(
+            xml,
+            r#"<testsuites name="{}" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+            escape(&self.name),
+            self.cases().count(),
+            failures,
+            errors,
+            suites.iter().map(|s| s.duration_secs()).sum::<f64>(),
+        );
+
+        for suite in suites {
+            let _ = writeln!(
+                xml,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+                escape(&suite.name),
+                suite.cases.len(),
+                suite.count(&[TestStatus::Failed]),
+                suite.count(&[TestStatus::Panicked, TestStatus::TimedOut]),
+                suite.count(&[TestStatus::Pending]),
+                suite.duration_secs(),
+            );
+            for case in &suite.cases {
+                let _ = write!(
+                    xml,
+                    r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+                    escape(&case.name),
+                    escape(&suite.name),
+                    case.duration_ms as f64 / 1000.0,
+                );
+                let element = match case.status {
+                    TestStatus::Failed => Some("failure"),
+                    TestStatus::Panicked | TestStatus::TimedOut => Some("error"),
+                    TestStatus::Pending => Some("skipped"),
+                    TestStatus::Passed | TestStatus::Running => None,
+                };
+                match element {
+                    Some(element) => {
+                        let summary = case.message.lines().next().unwrap_or_default();
+                        let _ = writeln!(
+                            xml,
+                            "><{element} message=\"{}\">{}</{element}></testcase>",
+                            escape(summary),
+                            escape(&case.message),
+                        );
+                    }
+                    None => {
+                        let _ = writeln!(xml, "/>");
+                    }
+                }
+            }
+            let _ = writeln!(xml, "  </testsuite>");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // control characters other than whitespace are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
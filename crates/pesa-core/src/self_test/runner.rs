@@ -1,20 +1,25 @@
-use anyhow::anyhow;
+use anyhow::bail;
 use futures::FutureExt;
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
+    hash::Hash,
     panic::{AssertUnwindSafe, PanicHookInfo},
     path::PathBuf,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::time::timeout;
 use tracing::error;
 
 use super::{
     context::{TestContext, TestMode},
+    contract::ContractTarget,
     emitter::{
         MainUiEmitter, TestFinishPayload, TestPlanPayload, TestStartPayload, TestStatus,
         TestStepInfo, TestStepUpdatePayload,
     },
+    report::{TestCaseReport, TestReport, TestSuiteReport},
 };
 use crate::{AppEventManager, self_test::callback::CallbackManager};
 
@@ -31,8 +36,10 @@ pub const SELF_TEST_FINISH: &str = "self_test_finish";
 
 /// Represents a single, sequential step in the self-test suite.
 ///
-/// Each test step is run in order, and the suite stops on the first failure.
-/// Steps can share state with each other through the `TestContext`.
+/// Each test step is run in order. By default the suite stops on the first failure and
+/// reports the remaining steps as pending; with [`TestRunner::continue_on_failure`] only the
+/// steps depending on a failed one are skipped. Steps can share state with each other through
+/// the `TestContext`.
 ///
 /// # Example
 ///
@@ -40,7 +47,7 @@ pub const SELF_TEST_FINISH: &str = "self_test_finish";
 /// waiting for a confirmation callback, and storing the new user's ID in the
 /// shared context for the next test to use.
 ///
-/// ```rust
+/// ```rust,ignore
 /// use std::time::Duration;
 /// use anyhow::{Context, bail};
 /// use serde::{Serialize, Deserialize};
@@ -121,10 +128,80 @@ pub trait TestStep: Send + Sync + 'static {
     ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send;
 }
 
+/// An ordered suite of test steps, implemented by the enums `define_tests!` generates.
+pub trait TestSuite: Copy + Eq + Hash + Send + Sync + 'static {
+    /// The name of the suite in reports.
+    const NAME: &'static str;
+
+    /// Every step of the suite, in execution order.
+    fn all() -> Vec<Self>;
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn tags(&self) -> &'static [&'static str];
+    /// The steps that must pass before this one can run.
+    fn dependencies(&self) -> &'static [Self];
+    fn run<'a>(
+        &'a self,
+        context: &'a mut TestContext,
+        callback_manager: &'a mut CallbackManager,
+    ) -> impl std::future::Future<Output = anyhow::Result<()>> + Send + 'a;
+}
+
+/// Which self-tests to run, by name or tag. An empty selection runs every test.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestSelection {
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl TestSelection {
+    fn matches<T: TestSuite>(&self, test: &T) -> bool {
+        (self.names.is_empty() && self.tags.is_empty())
+            || self.names.iter().any(|name| name == test.name())
+            || test
+                .tags()
+                .iter()
+                .any(|tag| self.tags.iter().any(|t| t == tag))
+    }
+
+    /// The selected tests and the tests they depend on, in declaration order.
+    pub fn plan<T: TestSuite>(&self) -> anyhow::Result<Vec<T>> {
+        let all = T::all();
+        if let Some(unknown) = self
+            .names
+            .iter()
+            .find(|name| !all.iter().any(|test| test.name() == name.as_str()))
+        {
+            bail!("Unknown self-test '{}'", unknown);
+        }
+
+        let mut wanted: Vec<T> = all.iter().filter(|t| self.matches(*t)).copied().collect();
+        if wanted.is_empty() {
+            bail!("No self-tests match tags {:?}", self.tags);
+        }
+        let mut i = 0;
+        while i < wanted.len() {
+            for dependency in wanted[i].dependencies() {
+                if !wanted.contains(dependency) {
+                    wanted.push(*dependency);
+                }
+            }
+            i += 1;
+        }
+
+        Ok(all.into_iter().filter(|t| wanted.contains(t)).collect())
+    }
+}
+
 /// The main orchestrator for running the self-test suite.
 pub struct TestRunner {
     test_timeout: Duration,
     callback_timeout: Duration,
+    selection: TestSelection,
+    continue_on_failure: bool,
+    target: Option<ContractTarget>,
 }
 
 impl TestRunner {
@@ -132,19 +209,45 @@ impl TestRunner {
         Self {
             test_timeout,
             callback_timeout,
+            selection: TestSelection::default(),
+            continue_on_failure: false,
+            target: None,
         }
     }
 
-    pub async fn run_suite(
+    /// Runs only the selected tests and their dependencies.
+    pub fn with_selection(mut self, selection: TestSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Keeps running after a failure, skipping only the tests that depend on the failed one.
+    pub fn continue_on_failure(mut self, continue_on_failure: bool) -> Self {
+        self.continue_on_failure = continue_on_failure;
+        self
+    }
+
+    /// Points the contract tests at the backend they exercise.
+    pub fn with_target(mut self, target: ContractTarget) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Runs the selected steps in order, by default stopping at the first one that doesn't
+    /// pass.
+    ///
+    /// The report lists the steps that didn't run as pending. Errors are reserved for failing
+    /// to set the suite up.
+    pub async fn run_suite<T: TestSuite>(
         &self,
         mode: TestMode,
         main_ui_emitter: Arc<dyn AppEventManager + Send + Sync>,
         app_root: PathBuf,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TestReport> {
         let emitter = MainUiEmitter::new(main_ui_emitter.clone());
         emitter.log_runner("Starting test suite execution");
 
-        let tests = super::tests::all_tests();
+        let tests = self.selection.plan::<T>()?;
         let steps: Vec<TestStepInfo> = tests
             .iter()
             .map(|test| TestStepInfo {
@@ -165,6 +268,7 @@ impl TestRunner {
             app_root,
         )
         .await?;
+        context.target = self.target.clone();
 
         emitter.log_runner("Initializing test callback manager");
 
@@ -176,9 +280,40 @@ impl TestRunner {
         })?;
 
         let panic_report_mx = Arc::new(Mutex::new(None));
+        let mut suite = TestSuiteReport::new(T::NAME);
+        let mut outcomes: HashMap<T, TestStatus> = HashMap::new();
+        let mut stopped_by: Option<&str> = None;
 
         for (index, step) in tests.iter().enumerate() {
             let step_name = step.name();
+            let blocked_by = match stopped_by {
+                Some(failed) => Some(format!("Not run, step '{}' failed", failed)),
+                None => step
+                    .dependencies()
+                    .iter()
+                    .find(|dependency| outcomes.get(dependency) != Some(&TestStatus::Passed))
+                    .map(|dependency| {
+                        format!("Not run, dependency '{}' did not pass", dependency.name())
+                    }),
+            };
+            if let Some(message) = blocked_by {
+                emitter.emit_step_update(&TestStepUpdatePayload {
+                    index,
+                    name: step_name,
+                    status: TestStatus::Pending,
+                    message: message.clone(),
+                })?;
+                suite.cases.push(TestCaseReport {
+                    name: step_name.to_string(),
+                    status: TestStatus::Pending,
+                    message,
+                    duration_ms: 0,
+                });
+                outcomes.insert(*step, TestStatus::Pending);
+                continue;
+            }
+
+            let started = Instant::now();
             emitter.log_runner(&format!("Running step: {}", step_name));
             context.current_test = Some((step_name.to_string(), index));
 
@@ -274,30 +409,45 @@ impl TestRunner {
                 index,
                 name: step_name,
                 status: status.clone(),
-                message,
+                message: message.clone(),
             })?;
+            suite.cases.push(TestCaseReport {
+                name: step_name.to_string(),
+                status: status.clone(),
+                message,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
 
             // Check if the callback server has panicked
             callback_manager.check_server_panic().await?;
 
             // If a step fails or times out, we might want to stop the whole suite
-            if !matches!(status, TestStatus::Passed) {
-                emitter.emit_finish(&TestFinishPayload {
-                    status: TestStatus::Failed,
-                })?;
-
-                return Err(anyhow!(
+            if !matches!(status, TestStatus::Passed) && !self.continue_on_failure {
+                emitter.log_runner(&format!(
                     "Self-test suite stopped due to failure in step '{}'",
                     step_name
                 ));
+                stopped_by = Some(step_name);
             }
+            outcomes.insert(*step, status);
             context.current_test = None;
         }
         context.current_test = None;
-        context.log("Self-test suite completed successfully.").await;
+
+        let passed = outcomes.values().all(|s| *s == TestStatus::Passed);
+        if passed {
+            context.log("Self-test suite completed successfully.").await;
+        }
         emitter.emit_finish(&TestFinishPayload {
-            status: TestStatus::Passed,
+            status: if passed {
+                TestStatus::Passed
+            } else {
+                TestStatus::Failed
+            },
         })?;
-        Ok(())
+        Ok(TestReport {
+            name: T::NAME.to_string(),
+            suites: vec![suite],
+        })
     }
 }
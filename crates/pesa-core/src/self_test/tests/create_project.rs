@@ -89,6 +89,7 @@ impl TestStep for InitProjectTest {
                 name: "Test Framework Biz Project".to_string(),
                 callback_url: None,
                 simulation_mode: projects::SimulationMode::Realistic,
+                validation_mode: Default::default(),
                 stk_delay: 0,
                 prefix: None,
             },
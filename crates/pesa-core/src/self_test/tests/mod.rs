@@ -3,9 +3,7 @@ use axum::http::{HeaderMap, HeaderValue};
 use base64::{Engine, engine::general_purpose};
 
 use crate::{
-    define_tests,
-    projects::ProjectDetails,
-    self_test::{context::TestContext, runner::TestStep},
+    define_tests, projects::ProjectDetails, self_test::context::TestContext,
     server::api::auth::AuthResponse,
 };
 
@@ -20,31 +18,42 @@ define_tests!(
     InitProject {
         name: "init_project",
         description: "Prepares a test project for the next sequence of tests",
+        tags: ["setup"],
         ctor: create_project::InitProjectTest
     },
     SendMoney {
         name: "send_money",
         description: "Validates user-to-user (P2P) transfers, covering successful transactions, insufficient funds, and invalid account scenarios.",
+        tags: ["transfers"],
+        depends_on: [InitProject],
         ctor: send_money::SendMoneyTest
     },
     Stkpush {
         name: "stkpush",
         description: "Performs stkpush",
+        tags: ["daraja", "payments"],
+        depends_on: [InitProject],
         ctor: stkpush::StkpushTest
     },
     C2B {
         name: "c2b",
         description: "Performs C2B tests",
+        tags: ["daraja", "payments"],
+        depends_on: [InitProject],
         ctor: c2b::C2BTest
     },
     B2C {
         name: "b2c",
         description: "Performs B2C tests",
+        tags: ["daraja", "payouts"],
+        depends_on: [InitProject],
         ctor: b2c::B2CTest
     },
     BalanceQuery {
         name: "balance_query",
         description: "Performs Balance Query tests",
+        tags: ["daraja"],
+        depends_on: [InitProject],
         ctor: balance_query::BalanceQueryTest
     },
 );
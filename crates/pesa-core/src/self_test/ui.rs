@@ -1,32 +1,140 @@
+use serde::{Deserialize, Serialize};
 use tokio::time::Duration;
 
 use crate::AppContext;
 
-use super::{context::TestMode, emitter::MainUiEmitter, runner::TestRunner};
+use super::{
+    context::TestMode,
+    contract::{ContractTarget, ContractTests},
+    emitter::MainUiEmitter,
+    runner::{TestRunner, TestSelection, TestSuite},
+    tests::SelfTests,
+};
+
+/// A self-test as listed for selection.
+#[derive(Serialize, Debug, Clone)]
+pub struct SelfTestInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub tags: Vec<&'static str>,
+    pub depends_on: Vec<&'static str>,
+}
+
+/// What `run_selected_self_tests` runs, and how.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SelfTestOptions {
+    pub mode: TestMode,
+    #[serde(flatten)]
+    pub selection: TestSelection,
+    /// Report every result instead of stopping at the first failure.
+    #[serde(default)]
+    pub continue_on_failure: bool,
+}
+
+/// What `run_contract_tests` runs, and against which backend.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ContractTestOptions {
+    pub target: ContractTarget,
+    #[serde(flatten)]
+    pub selection: TestSelection,
+    /// Report every result instead of stopping at the first failure.
+    #[serde(default)]
+    pub continue_on_failure: bool,
+}
 
 /// Runs the full self-test suite.
 ///
 /// It sets up the test environment, executes all steps, and reports progress via events.
 pub async fn run_self_tests(app_ctx: &AppContext, mode: TestMode) -> Result<(), String> {
+    spawn_suite::<SelfTests>(
+        app_ctx,
+        TestRunner::new(Duration::from_secs(60), Duration::from_secs(30)),
+        mode,
+    );
+    Ok(())
+}
+
+/// Runs the selected self-tests and the tests they depend on, reporting progress via events.
+pub async fn run_selected_self_tests(
+    app_ctx: &AppContext,
+    options: SelfTestOptions,
+) -> Result<(), String> {
+    // reject unknown names here, the suite itself runs in the background
+    options
+        .selection
+        .plan::<SelfTests>()
+        .map_err(|e| e.to_string())?;
+
+    let runner = TestRunner::new(Duration::from_secs(60), Duration::from_secs(30))
+        .with_selection(options.selection)
+        .continue_on_failure(options.continue_on_failure);
+    spawn_suite::<SelfTests>(app_ctx, runner, options.mode);
+    Ok(())
+}
+
+pub async fn list_self_tests(_app_ctx: &AppContext) -> Result<Vec<SelfTestInfo>, String> {
+    Ok(list::<SelfTests>())
+}
+
+/// Runs the contract tests against the user's backend, reporting progress via the self-test
+/// events.
+pub async fn run_contract_tests(
+    app_ctx: &AppContext,
+    options: ContractTestOptions,
+) -> Result<(), String> {
+    options.target.validate().map_err(|e| e.to_string())?;
+    options
+        .selection
+        .plan::<ContractTests>()
+        .map_err(|e| e.to_string())?;
+
+    let runner = TestRunner::new(Duration::from_secs(120), Duration::from_secs(30))
+        .with_selection(options.selection)
+        .continue_on_failure(options.continue_on_failure)
+        .with_target(options.target);
+    spawn_suite::<ContractTests>(app_ctx, runner, TestMode::Fresh);
+    Ok(())
+}
+
+pub async fn list_contract_tests(_app_ctx: &AppContext) -> Result<Vec<SelfTestInfo>, String> {
+    Ok(list::<ContractTests>())
+}
+
+fn list<T: TestSuite>() -> Vec<SelfTestInfo> {
+    T::all()
+        .iter()
+        .map(|test| SelfTestInfo {
+            name: test.name(),
+            description: test.description(),
+            tags: test.tags().to_vec(),
+            depends_on: test.dependencies().iter().map(|d| d.name()).collect(),
+        })
+        .collect()
+}
+
+fn spawn_suite<T: TestSuite>(app_ctx: &AppContext, runner: TestRunner, mode: TestMode) {
     // Clone the main application's event manager to report test progress back to the UI
     let ui_emitter = app_ctx.event_manager.clone();
     let app_root = app_ctx.app_root.clone();
 
     // Spawn a background task to run the tests to avoid blocking the caller
     tokio::spawn(async move {
-        let runner = TestRunner::new(Duration::from_secs(60), Duration::from_secs(30));
         let emitter = MainUiEmitter::new(ui_emitter.clone());
 
-        match runner.run_suite(mode, ui_emitter, app_root).await {
-            Ok(_) => {
+        match runner.run_suite::<T>(mode, ui_emitter, app_root).await {
+            Ok(report) if report.passed() => {
                 tracing::info!("Self-test completed successfully.");
             }
+            Ok(report) => {
+                tracing::error!(
+                    "Self-test failed: {} step(s) did not pass",
+                    report.failures()
+                );
+            }
             Err(e) => {
                 tracing::error!("Self-test failed: {:?}", e);
                 emitter.log_runner(&format!("Self test failed: {:?}", e));
             }
         }
     });
-
-    Ok(())
 }
@@ -13,6 +13,7 @@ use sea_orm::ColumnTrait;
 use sea_orm::QueryFilter;
 use sea_orm::{EntityTrait, Set};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::server::ApiState;
 use crate::{
@@ -37,7 +38,7 @@ enum GrantType {
     Unknown,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AuthResponse {
     pub access_token: String,
     pub expires_in: String,
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::{
     server::{api::b2c::task::B2C, async_handler::IntoCallbackPayload},
@@ -7,14 +8,15 @@ use crate::{
 
 pub mod task;
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[schema(as = B2CCommandID)]
 pub enum CommandID {
     SalaryPayment,
     BusinessPayment,
     PromotionPayment,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct B2CRequest {
     #[serde(rename = "OriginatorConversationID")]
     pub originator_conversation_id: String,
@@ -40,7 +42,7 @@ pub struct B2CRequest {
     pub occassion: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct B2CRequestResponse {
     #[serde(rename = "ConversationID")]
     pub conversation_id: String,
@@ -52,13 +54,13 @@ pub struct B2CRequestResponse {
     pub response_description: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResultParameters {
     #[serde(rename = "ResultParameter")]
     pub result_parameter: Vec<KeyValueEntry>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct KeyValueEntry {
     #[serde(rename = "Key")]
     pub key: String,
@@ -66,16 +68,16 @@ pub struct KeyValueEntry {
     pub value: serde_json::Value,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ReferenceData {
     #[serde(rename = "ReferenceItem")]
     pub reference_item: KeyValueEntry,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CallbackResult {
     #[serde(rename = "ResultType")]
-    result_type: u16,
+    pub result_type: u16,
     #[serde(rename = "ResultCode")]
     pub result_code: String,
     #[serde(rename = "ResultDesc")]
@@ -92,7 +94,7 @@ pub struct CallbackResult {
     pub reference_data: ReferenceData,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct B2CCallbackResponse {
     #[serde(rename = "Result")]
     pub result: CallbackResult,
@@ -167,7 +169,7 @@ pub enum B2CResultCodes {
 }
 
 impl B2CResultCodes {
-    fn code(&self) -> &str {
+    pub fn code(&self) -> &str {
         match self {
             B2CResultCodes::Success => "0",
             B2CResultCodes::InsufficientBalance => "1",
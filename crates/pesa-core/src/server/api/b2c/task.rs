@@ -20,6 +20,7 @@ use crate::{
             },
         },
         async_handler::PpgAsyncRequest,
+        validation,
     },
     transactions::{Ledger, TransactionNote, TransactionType},
 };
@@ -57,15 +58,7 @@ impl PpgAsyncRequest for B2C {
         Self: Sized,
     {
         // FIXME - not entirely sure if b2c amount expects a string or just a normal number. So we have to do some parsing here.
-        let amount: f64 = req.amount.parse().map_err(|error| {
-            ApiError::new(
-                crate::server::MpesaError::InternalError,
-                format!(
-                    "Failed to parse amount: {}, value passed: {}",
-                    error, req.amount
-                ),
-            )
-        })?;
+        let amount = validation::amount(&req.amount)?;
         let business = Business::get_by_short_code(&state.context.db, &req.party_a)
             .await
             .map_err(|error| {
@@ -209,7 +202,7 @@ impl PpgAsyncRequest for B2C {
                 conversation_id: conversation_id.to_string(),
                 originator_conversation_id: req.originator_conversation_id,
                 result_url: req.result_url,
-                amount: (amount * 100.0) as i64,
+                amount,
                 business,
                 utility_account,
                 mmf_account,
@@ -237,8 +230,9 @@ impl PpgAsyncRequest for B2C {
             return Ok(self.create_response(B2CResultCodes::InsufficientBalance, &receipt));
         }
 
-        let (transaction, events) = Ledger::transfer(
+        let (transaction, events) = Ledger::transfer_in_conversation(
             &txn,
+            Some(&self.conversation_id),
             Some(self.utility_account.account_id),
             self.user.account_id,
             self.amount,
@@ -252,10 +246,11 @@ impl PpgAsyncRequest for B2C {
 
         receipt = transaction.id;
 
-        self.business =
-            Business::increment_charges_amount(&txn, self.business.id, -transaction.fee)
-                .await
-                .context("Failed to increment business charges")?;
+        // the ledger accrues the disbursment fee on the business charges account
+        self.business = Business::get_by_id(&txn, self.business.id)
+            .await
+            .context("Failed to fetch business")?
+            .context("Business not found")?;
 
         if let Some(utility_account) =
             UtilityAccount::find_by_id(&txn, self.utility_account.account_id)
@@ -265,13 +260,13 @@ impl PpgAsyncRequest for B2C {
             self.utility_account = utility_account;
         }
 
-        DomainEventDispatcher::dispatch_events(&state.context, events)
-            .context("Failed to emit events ")?;
-
         txn.commit()
             .await
             .context("Failed to commit transaction.")?;
 
+        DomainEventDispatcher::dispatch_events(&state.context, events)
+            .context("Failed to emit events ")?;
+
         Ok(self.create_response(B2CResultCodes::Success, &receipt))
     }
 
@@ -282,6 +277,10 @@ impl PpgAsyncRequest for B2C {
     fn get_originator_id(&self) -> &str {
         &self.originator_conversation_id
     }
+
+    fn get_result_code(payload: &Self::CallbackPayload) -> Option<String> {
+        Some(payload.result.result_code.clone())
+    }
 }
 
 impl B2C {
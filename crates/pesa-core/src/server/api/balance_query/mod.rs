@@ -1,5 +1,6 @@
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::{
     server::{api::balance_query::task::BalanceQuery, async_handler::IntoCallbackPayload},
@@ -9,20 +10,21 @@ use crate::{
 pub mod task;
 
 // --- Request Payload ---
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
+#[schema(as = BalanceQueryCommandID)]
 pub enum CommandID {
     AccountBalance,
     WorkingAccountBalance,
     UtilityAccountBalance,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub enum IdentifierType {
     #[serde(rename = "4")]
     OrganisationShortCode,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct BalanceQueryRequest {
     #[serde(rename = "Initiator")]
     pub initiator: String,
@@ -43,7 +45,7 @@ pub struct BalanceQueryRequest {
 }
 
 // --- Synchronous Response ---
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct BalanceQueryRequestResponse {
     #[serde(rename = "ConversationID")]
     pub conversation_id: String,
@@ -56,13 +58,13 @@ pub struct BalanceQueryRequestResponse {
 }
 
 // --- Asynchronous Callback Payload ---
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct BalanceQueryCallbackResponse {
     #[serde(rename = "Result")]
     pub result: CallbackResult,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct CallbackResult {
     #[serde(rename = "ResultType")]
     pub result_type: u16,
@@ -82,13 +84,13 @@ pub struct CallbackResult {
     pub reference_data: ReferenceData,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct ResultParameters {
     #[serde(rename = "ResultParameter")]
     pub result_parameter: Vec<KeyValueEntry>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct KeyValueEntry {
     #[serde(rename = "Key")]
     pub key: String,
@@ -96,7 +98,7 @@ pub struct KeyValueEntry {
     pub value: serde_json::Value,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct ReferenceData {
     #[serde(rename = "ReferenceItem")]
     pub reference_item: KeyValueEntry,
@@ -197,4 +197,8 @@ impl PpgAsyncRequest for BalanceQuery {
     fn get_originator_id(&self) -> &str {
         &self.originator_conversation_id
     }
+
+    fn get_result_code(payload: &Self::CallbackPayload) -> Option<String> {
+        Some(payload.result.result_code.clone())
+    }
 }
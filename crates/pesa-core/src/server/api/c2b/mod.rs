@@ -1,10 +1,11 @@
 use strum::{Display, EnumString};
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub mod register;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Display, EnumString, ToSchema)]
 #[serde(rename_all = "PascalCase")]
 #[strum(serialize_all = "PascalCase")]
 pub enum ResponseType {
@@ -12,7 +13,7 @@ pub enum ResponseType {
     Cancelled,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
 pub enum C2bTransactionType {
     #[serde(rename = "Pay Bill")]
     PayBill,
@@ -20,7 +21,7 @@ pub enum C2bTransactionType {
     Till,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, ToSchema)]
 pub struct ValidationRequest {
     #[serde(rename = "TransactionType")]
     pub transaction_type: C2bTransactionType,
@@ -50,7 +51,7 @@ pub struct ValidationRequest {
     pub last_name: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, ToSchema)]
 pub enum ResultCode {
     C2B00011,
     C2B00012,
@@ -62,7 +63,7 @@ pub enum ResultCode {
     Ok,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct ResultResponse {
     #[serde(rename = "ResultCode")]
     pub result_code: ResultCode,
@@ -70,7 +71,7 @@ pub struct ResultResponse {
     pub result_desc: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct ValidationResponse {
     #[serde(rename = "ResultCode")]
     pub result_code: ResultCode,
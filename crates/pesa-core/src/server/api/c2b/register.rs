@@ -1,17 +1,19 @@
 use axum::{Json, extract::State, http::HeaderMap};
 use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::{
     accounts::{paybill_accounts::PaybillAccount, till_accounts::TillAccount},
     server::{
         ApiError, ApiState, MpesaError,
         api::{auth, c2b::ResponseType},
+        validation::{self, DarajaJson},
     },
 };
 
 /// Request to register validation and confirmation URLs
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct RegisterUrlRequest {
     #[serde(rename = "ShortCode")]
     pub short_code: u32,
@@ -24,7 +26,7 @@ pub struct RegisterUrlRequest {
 }
 
 /// Response after registering URLs
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RegisterUrlResponse {
     #[serde(rename = "ResponseCode")]
     pub response_code: String,
@@ -37,11 +39,12 @@ pub struct RegisterUrlResponse {
 pub async fn registerurl(
     headers: HeaderMap,
     State(state): State<ApiState>,
-    Json(req): Json<RegisterUrlRequest>,
+    DarajaJson(req): DarajaJson<RegisterUrlRequest>,
 ) -> Result<Json<RegisterUrlResponse>, ApiError> {
     let urls_already_registered = "URLS_ALREADY_REGISTERED";
 
     let _api_key = auth::validate_bearer_token(&headers, &state).await?;
+    validation::validate(&state, &req).await?;
 
     let short_code = req.short_code;
     if let Some(paybill) = PaybillAccount::get_by_paybill_number(&state.context.db, short_code)
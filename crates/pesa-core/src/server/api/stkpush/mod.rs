@@ -1,18 +1,19 @@
 use chrono::Utc;
 use rand::{Rng, distributions::Alphanumeric, seq::SliceRandom};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub mod task;
 pub mod ui;
 
-#[derive(Clone, Deserialize, Serialize, Debug)]
+#[derive(Clone, Deserialize, Serialize, Debug, ToSchema)]
 #[serde(rename_all = "PascalCase")]
 pub enum TransactionType {
     CustomerPayBillOnline,
     CustomerBuyGoodsOnline,
 }
 
-#[derive(Clone, Deserialize, Debug, Serialize)]
+#[derive(Clone, Deserialize, Debug, Serialize, ToSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct StkPushRequest {
     pub business_short_code: String,
@@ -28,7 +29,7 @@ pub struct StkPushRequest {
     pub transaction_desc: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 #[serde(rename_all = "PascalCase")]
 pub struct StkPushResponse {
     #[serde(rename = "MerchantRequestID")]
@@ -43,7 +44,7 @@ pub struct StkPushResponse {
     pub customer_message: String,
 }
 
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
 pub struct CallbackItem {
     #[serde(rename = "Name")]
     pub name: String,
@@ -51,13 +52,13 @@ pub struct CallbackItem {
     pub value: serde_json::Value,
 }
 
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
 pub struct CallbackMetadata {
     #[serde(rename = "Item")]
     pub item: Vec<CallbackItem>,
 }
 
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
 pub struct StkCallback {
     #[serde(rename = "MerchantRequestID")]
     pub merchant_request_id: String,
@@ -71,13 +72,13 @@ pub struct StkCallback {
     pub metadata: Option<CallbackMetadata>,
 }
 
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
 pub struct StkCallbackBody {
     #[serde(rename = "stkCallback")]
     pub callback: StkCallback,
 }
 
-#[derive(Serialize, Debug, Deserialize)]
+#[derive(Serialize, Debug, Deserialize, ToSchema)]
 pub struct StkCallbackBodyWrapper {
     #[serde(rename = "Body")]
     pub body: StkCallbackBody,
@@ -4,14 +4,14 @@ use anyhow::Context;
 use base64::{Engine, engine::general_purpose};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{Value, json};
 use tokio::sync::oneshot;
 
 use crate::accounts::{
     paybill_accounts::PaybillAccount, till_accounts::TillAccount, user_profiles::User,
     utility_accounts::UtilityAccount,
 };
-use crate::server::api::stkpush::ui::{STK_RESPONSE_REGISTRY, UserResponse};
+use crate::server::api::stkpush::ui::{PromptDecision, STK_RESPONSE_REGISTRY, UserResponse};
 use crate::server::{
     ApiError, ApiState, MpesaError,
     api::{
@@ -23,16 +23,20 @@ use crate::server::{
         },
     },
     async_handler::{IntoCallbackPayload, PpgAsyncRequest},
+    validation,
 };
 use crate::{
     api_keys::ApiKey,
     business::Business,
     events::DomainEventDispatcher,
     projects::Project,
+    recording::SessionEvent,
+    timeline::{RequestEvent, RequestEventKind},
     transactions::{Ledger, TransactionEngineError, TransactionNote, TransactionType},
 };
 
 pub struct Stkpush {
+    pub conversation_id: String,
     pub business: Business,
     pub user: User,
     pub project: Project,
@@ -86,7 +90,7 @@ impl PpgAsyncRequest for Stkpush {
     async fn init(
         state: &ApiState,
         req: Self::RequestData,
-        _conversation_id: &str,
+        conversation_id: &str,
         api_key: ApiKey,
     ) -> Result<(Self::SyncResponseData, Self), ApiError> {
         let passkey = api_key.passkey;
@@ -96,7 +100,7 @@ impl PpgAsyncRequest for Stkpush {
             StkTransactionType::CustomerBuyGoodsOnline => {
                 let till = match TillAccount::get_by_till_number(
                     &state.context.db,
-                    req.party_b.parse().unwrap_or_default(),
+                    validation::shortcode("PartyB", &req.party_b)?,
                 )
                 .await
                 {
@@ -121,7 +125,7 @@ impl PpgAsyncRequest for Stkpush {
             StkTransactionType::CustomerPayBillOnline => {
                 let paybill = match PaybillAccount::get_by_paybill_number(
                     &state.context.db,
-                    req.party_b.parse().unwrap_or_default(),
+                    validation::shortcode("PartyB", &req.party_b)?,
                 )
                 .await
                 {
@@ -189,15 +193,7 @@ impl PpgAsyncRequest for Stkpush {
             ));
         }
 
-        let amount = req.amount.parse::<f64>().map_err(|err| {
-            ApiError::new(
-                crate::server::MpesaError::InternalError,
-                format!(
-                    "Failed to parse amount as number: {}, you provided: {} ",
-                    err, req.amount
-                ),
-            )
-        })?;
+        let amount = validation::amount(&req.amount)?;
 
         let user = match User::get_user_by_phone(&state.context.db, &req.phone_number)
             .await
@@ -232,7 +228,6 @@ impl PpgAsyncRequest for Stkpush {
             }
         };
 
-        let amount = (amount * 100.0).round() as i64;
         let merchant_id = generate_merchant_request_id();
         let checkout_id = generate_checkout_request_id();
 
@@ -247,6 +242,7 @@ impl PpgAsyncRequest for Stkpush {
                 customer_message: String::from("Success"),
             },
             Self {
+                conversation_id: conversation_id.to_string(),
                 business,
                 user,
                 utility_account,
@@ -318,13 +314,42 @@ impl PpgAsyncRequest for Stkpush {
         {
             return Ok(self.create_body(StkPushResultCode::ErrorSendingPushRequest, None));
         }
+        self.record_event(
+            state,
+            RequestEventKind::PromptShown,
+            json!({ "phone": user.phone, "amount": self.amount }),
+        )
+        .await;
+
+        let response = tokio::time::timeout(Duration::from_secs(30), rx).await;
+        let decision = match &response {
+            Ok(Ok(UserResponse::Accepted { pin })) => PromptDecision::Accepted {
+                pin_valid: pin.eq(&user.pin),
+            },
+            Ok(Ok(UserResponse::Cancelled)) => PromptDecision::Cancelled,
+            Ok(Ok(UserResponse::Offline)) => PromptDecision::Offline,
+            Ok(Ok(UserResponse::Timeout)) | Err(_) => PromptDecision::Timeout,
+            Ok(Ok(UserResponse::Failed(reason))) => PromptDecision::Failed(reason.clone()),
+            Ok(Err(_)) => PromptDecision::Dropped,
+        };
+        self.record_event(
+            state,
+            RequestEventKind::UserResponded,
+            json!({ "response": decision }),
+        )
+        .await;
+        state
+            .context
+            .recordings
+            .record(state.project_id, SessionEvent::Prompt { decision });
 
-        let status = match tokio::time::timeout(Duration::from_secs(30), rx).await {
+        let status = match response {
             Ok(Ok(value)) => match value {
                 UserResponse::Accepted { pin } => {
                     if pin.eq(&user.pin) {
-                        match Ledger::transfer(
+                        match Ledger::transfer_in_conversation(
                             &state.context.db,
+                            Some(&self.conversation_id),
                             Some(user.account_id),
                             self.utility_account.account_id,
                             self.amount,
@@ -375,12 +400,32 @@ impl PpgAsyncRequest for Stkpush {
     fn get_originator_id(&self) -> &str {
         &self.checkout_id
     }
+
+    fn get_result_code(payload: &Self::CallbackPayload) -> Option<String> {
+        Some(payload.body.callback.result_code.to_string())
+    }
+
     fn get_callback_url(&self) -> Option<&str> {
         Some(&self.callback_url)
     }
 }
 
 impl Stkpush {
+    /// Notes a step of the prompt on the request's timeline.
+    async fn record_event(&self, state: &ApiState, kind: RequestEventKind, detail: Value) {
+        if let Err(e) = RequestEvent::record(
+            &state.context.db,
+            state.project_id,
+            &self.conversation_id,
+            kind,
+            detail,
+        )
+        .await
+        {
+            tracing::warn!(target: "stkpush", "Failed to record {} event: {e}", self.checkout_id);
+        }
+    }
+
     pub fn create_body(
         &self,
         result_code: StkPushResultCode,
@@ -19,6 +19,43 @@ pub enum UserResponse {
     Failed(String),
 }
 
+/// What the user did with a prompt, as kept on the timeline and in recordings. The PIN they
+/// typed is reduced to whether it was right.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptDecision {
+    Accepted {
+        pin_valid: bool,
+    },
+    Cancelled,
+    Offline,
+    /// Timed out on the phone, or went unanswered.
+    Timeout,
+    Failed(String),
+    /// The prompt was dropped before anyone answered it.
+    Dropped,
+}
+
+impl PromptDecision {
+    /// The answer that makes the same decision for a user whose PIN is `pin`, `None` for a
+    /// dropped prompt.
+    pub fn response(&self, pin: &str) -> Option<UserResponse> {
+        Some(match self {
+            PromptDecision::Accepted { pin_valid: true } => UserResponse::Accepted {
+                pin: pin.to_string(),
+            },
+            PromptDecision::Accepted { pin_valid: false } => UserResponse::Accepted {
+                pin: format!("{pin}0"),
+            },
+            PromptDecision::Cancelled => UserResponse::Cancelled,
+            PromptDecision::Offline => UserResponse::Offline,
+            PromptDecision::Timeout => UserResponse::Timeout,
+            PromptDecision::Failed(reason) => UserResponse::Failed(reason.clone()),
+            PromptDecision::Dropped => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum StkCodes {
     /// 0
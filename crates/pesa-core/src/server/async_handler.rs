@@ -1,18 +1,28 @@
 //! A generic, type-safe framework for handling asynchronous M-Pesa API requests.
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Instant};
 
-use axum::{Json, extract::State, http::HeaderMap};
+use axum::{Extension, Json, extract::State, http::HeaderMap};
 use serde::{Serialize, de::DeserializeOwned};
 use tokio::task;
-use tracing;
+use tracing::Instrument;
 
 use super::{ApiError, ApiState};
 use crate::{
     api_keys::ApiKey,
     callbacks::{CreateCallbackParams, orchestrator::CallbackOrchestrator},
-    server::{api::auth, log::generate_conversation_id},
+    server::{
+        api::auth,
+        log::generate_conversation_id,
+        trace_context::TraceContext,
+        validation::{self, DarajaJson, Validate},
+    },
 };
 
+/// The conversation an accepted request started. Set on the response for the logging
+/// middleware, which stores it on the request's [`crate::api_logs::ApiLog`].
+#[derive(Debug, Clone)]
+pub struct ConversationId(pub String);
+
 pub trait IntoCallbackPayload<C, T> {
     fn get_payload(&self, ctx: &C) -> T;
 }
@@ -20,7 +30,7 @@ pub trait IntoCallbackPayload<C, T> {
 /// A generic trait for defining a two-step, stateful asynchronous API operation.
 pub trait PpgAsyncRequest: Sized + Send + 'static {
     /// The struct for the incoming request from the client.
-    type RequestData: DeserializeOwned;
+    type RequestData: DeserializeOwned + Validate;
     /// The struct for the immediate response sent back to the client.
     type SyncResponseData: Serialize;
     /// The struct for the final callback payload.
@@ -58,6 +68,11 @@ pub trait PpgAsyncRequest: Sized + Send + 'static {
         None
     }
 
+    /// Extracts the result code from the final callback payload, for the metrics.
+    fn get_result_code(_payload: &Self::CallbackPayload) -> Option<String> {
+        None
+    }
+
     /// Gets the static name of the API for logging purposes.
     fn api_name() -> &'static str;
 }
@@ -66,26 +81,59 @@ pub trait PpgAsyncRequest: Sized + Send + 'static {
 pub async fn handle_async_request<T: PpgAsyncRequest>(
     State(state): State<ApiState>,
     headers: HeaderMap,
-    Json(req_data): Json<T::RequestData>,
-) -> Result<Json<T::SyncResponseData>, ApiError> {
-    let api_key = auth::validate_bearer_token(&headers, &state).await?;
+    DarajaJson(req_data): DarajaJson<T::RequestData>,
+) -> Result<(Extension<ConversationId>, Json<T::SyncResponseData>), ApiError> {
     let conversation_id = generate_conversation_id();
+    let trace = TraceContext::from_headers(&headers);
+    let span = tracing::info_span!(
+        "mpesa.request",
+        api = T::api_name(),
+        project_id = state.project_id,
+        conversation_id = %conversation_id,
+    );
+    if let Some(trace) = &trace {
+        trace.attach(&span);
+    }
 
-    let (sync_response, job) = T::init(&state, req_data, conversation_id.as_str(), api_key).await?;
-    spawn_async_job::<T>(state, conversation_id, job);
-
-    Ok(Json(sync_response))
+    async {
+        let api_key = auth::validate_bearer_token(&headers, &state).await?;
+        validation::validate(&state, &req_data).await?;
+        let (sync_response, job) =
+            T::init(&state, req_data, conversation_id.as_str(), api_key).await?;
+        spawn_async_job::<T>(state, conversation_id.clone(), job, trace);
+
+        Ok((
+            Extension(ConversationId(conversation_id)),
+            Json(sync_response),
+        ))
+    }
+    .instrument(span)
+    .await
 }
 
 /// Spawns a background Tokio task to run the `execute` step on the job object.
-fn spawn_async_job<T: PpgAsyncRequest>(state: ApiState, conversation_id: String, mut job: T) {
-    task::spawn(async move {
+///
+/// The job runs in a span under the request's, and passes the caller's `trace` on to its callback.
+fn spawn_async_job<T: PpgAsyncRequest>(
+    state: ApiState,
+    conversation_id: String,
+    mut job: T,
+    trace: Option<TraceContext>,
+) {
+    let span = tracing::info_span!(
+        "mpesa.async_job",
+        api = T::api_name(),
+        project_id = state.project_id,
+        conversation_id = %conversation_id,
+    );
+    let job_future = async move {
         tracing::trace!(
             "Starting async job for {} on project {}. Conversation Id: {}",
             T::api_name(),
             state.project_id,
             conversation_id
         );
+        let started = Instant::now();
 
         // Execute the core business logic using the state held by the job object.
         let result = job.execute(&state).await;
@@ -103,6 +151,12 @@ fn spawn_async_job<T: PpgAsyncRequest>(state: ApiState, conversation_id: String,
                 e.get_payload(&job)
             }
         };
+        state.context.metrics.observe_async_job(
+            state.project_id,
+            T::api_name(),
+            T::get_result_code(&final_payload),
+            started.elapsed(),
+        );
 
         if let Some(url) = job.get_callback_url() {
             let params = CreateCallbackParams {
@@ -116,7 +170,7 @@ fn spawn_async_job<T: PpgAsyncRequest>(state: ApiState, conversation_id: String,
             };
 
             // Delegate the entire callback lifecycle to the orchestrator.
-            CallbackOrchestrator::handle_callback(&state.context, params, final_payload).await;
+            CallbackOrchestrator::handle_callback(&state.context, params, trace).await;
         }
 
         tracing::trace!(
@@ -124,5 +178,6 @@ fn spawn_async_job<T: PpgAsyncRequest>(state: ApiState, conversation_id: String,
             T::api_name(),
             state.project_id
         );
-    });
+    };
+    task::spawn(job_future.instrument(span));
 }
@@ -0,0 +1,94 @@
+//! The handler behind [`crate::inbox::INBOX_ROUTE`].
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{HeaderMap, Method, StatusCode, Uri, header},
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+use crate::{
+    inbox::{CannedResponse, InboxRequest, NewInboxRequest, is_valid_name},
+    server::{ApiError, ApiState, MpesaError},
+};
+
+pub async fn receive(
+    State(state): State<ApiState>,
+    Path(name): Path<String>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ApiError> {
+    if !is_valid_name(&name) {
+        return Err(ApiError::new(
+            MpesaError::InvalidCallbackUrl,
+            format!("Invalid inbox name {name:?}"),
+        ));
+    }
+
+    let db = &state.context.db;
+    let response = CannedResponse::for_inbox(db, state.project_id, &name)
+        .await
+        .map_err(|err| {
+            ApiError::new(
+                MpesaError::InternalError,
+                format!("Failed to get inbox response: {err}"),
+            )
+        })?;
+
+    let request = InboxRequest::record(
+        db,
+        NewInboxRequest {
+            project_id: state.project_id,
+            inbox: name,
+            method: method.to_string(),
+            query: uri.query().map(str::to_string),
+            headers: headers
+                .iter()
+                .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_string())))
+                .collect(),
+            body: (!body.is_empty()).then(|| String::from_utf8_lossy(&body).into_owned()),
+            response_status: response.status(),
+        },
+    )
+    .await
+    .map_err(|err| {
+        ApiError::new(
+            MpesaError::InternalError,
+            format!("Failed to store inbox request: {err}"),
+        )
+    })?;
+
+    let _ = state
+        .context
+        .event_manager
+        .emit_all("inbox_request", json!(request));
+
+    Ok(into_response(&response))
+}
+
+fn into_response(response: &CannedResponse) -> Response {
+    let status = StatusCode::from_u16(response.status()).unwrap_or(StatusCode::OK);
+    let headers = response.headers();
+    let mut builder = Response::builder().status(status);
+    if !headers
+        .keys()
+        .any(|name| name.eq_ignore_ascii_case("content-type"))
+    {
+        builder = builder.header(header::CONTENT_TYPE, "application/json");
+    }
+    for (name, value) in &headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+
+    let body = match response.body() {
+        serde_json::Value::Null => Vec::new(),
+        serde_json::Value::String(raw) => raw.into_bytes(),
+        json => json.to_string().into_bytes(),
+    };
+
+    builder
+        .body(Body::from(body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
@@ -0,0 +1,218 @@
+//! Hooks letting a script host inspect, rewrite or answer sandbox requests before their handler.
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use http_body_util::BodyExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::server::ApiState;
+
+/// The incoming Daraja request handed to an interceptor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterceptedRequest {
+    pub project_id: u32,
+    pub method: String,
+    /// The matched route, e.g. `/mpesa/stkpush/v1/processrequest`.
+    pub path: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// The JSON body, or the raw body as a string when it is not JSON.
+    #[serde(default)]
+    pub body: Value,
+}
+
+/// A response returned by an interceptor instead of calling the route handler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterceptResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub body: Value,
+}
+
+#[derive(Debug, Clone)]
+pub enum Interception {
+    /// Pass the request, possibly rewritten, on to the handler.
+    Forward(InterceptedRequest),
+    /// Answer the request without reaching the handler.
+    Respond(InterceptResponse),
+}
+
+pub trait RequestInterceptor: Send + Sync {
+    /// Whether requests to `path` of `project_id` should be buffered and intercepted.
+    fn handles(&self, project_id: u32, path: &str) -> bool;
+
+    fn intercept(
+        &self,
+        request: InterceptedRequest,
+    ) -> BoxFuture<'static, anyhow::Result<Interception>>;
+}
+
+/// The interceptor installed by the script host, shared by every clone of the [`crate::AppContext`].
+#[derive(Clone, Default)]
+pub struct Interceptors(Arc<RwLock<Option<Arc<dyn RequestInterceptor>>>>);
+
+impl Interceptors {
+    pub fn set(&self, interceptor: Arc<dyn RequestInterceptor>) {
+        *self.0.write().unwrap() = Some(interceptor);
+    }
+
+    pub fn get(&self) -> Option<Arc<dyn RequestInterceptor>> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.0.read().unwrap().is_some()
+    }
+}
+
+pub async fn intercept_middleware(
+    State(state): State<ApiState>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = matched_path
+        .as_ref()
+        .map(|mp| mp.as_str())
+        .unwrap_or(request.uri().path())
+        .to_string();
+
+    let Some(interceptor) = state.context.interceptors.get() else {
+        return next.run(request).await;
+    };
+    if !interceptor.handles(state.project_id, &path) {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let body_bytes = match body.collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+
+    let intercepted = InterceptedRequest {
+        project_id: state.project_id,
+        method: parts.method.to_string(),
+        path,
+        headers: shown_headers(&parts.headers),
+        body: serde_json::from_slice(&body_bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body_bytes).into_owned())),
+    };
+
+    let shown = intercepted.headers.clone();
+    match interceptor.intercept(intercepted).await {
+        Ok(Interception::Forward(rewritten)) => {
+            parts.headers = forwarded_headers(&parts.headers, &shown, &rewritten.headers);
+
+            let body = body_to_bytes(&rewritten.body);
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, body.len().into());
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+        Ok(Interception::Respond(response)) => into_response(response),
+        Err(err) => {
+            // a broken script must not take the sandbox down with it
+            tracing::error!("Request interceptor failed, passing the request through: {err:#}");
+            next.run(Request::from_parts(parts, Body::from(body_bytes)))
+                .await
+        }
+    }
+}
+
+/// The headers as an interceptor sees them, repeated ones joined with a comma. Values that are not
+/// UTF-8 are left out.
+fn shown_headers(headers: &HeaderMap) -> HashMap<String, String> {
+    let mut shown: HashMap<String, String> = HashMap::new();
+    for (name, value) in headers {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        shown
+            .entry(name.to_string())
+            .and_modify(|joined| {
+                joined.push_str(", ");
+                joined.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    shown
+}
+
+/// The headers to forward after an interceptor returned `rewritten`. The headers it left as
+/// `shown` keep their original values, repeated and non UTF-8 ones included.
+fn forwarded_headers(
+    original: &HeaderMap,
+    shown: &HashMap<String, String>,
+    rewritten: &HashMap<String, String>,
+) -> HeaderMap {
+    let rewritten: HashMap<String, &String> = rewritten
+        .iter()
+        .map(|(name, value)| (name.to_ascii_lowercase(), value))
+        .collect();
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in original {
+        let kept = match rewritten.get(name.as_str()) {
+            Some(value) => shown.get(name.as_str()) == Some(*value),
+            // never shown to the interceptor, so it can't have removed it
+            None => !shown.contains_key(name.as_str()),
+        };
+        if kept {
+            headers.append(name.clone(), value.clone());
+        }
+    }
+    for (name, value) in rewritten {
+        if shown.get(&name) == Some(value) {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::try_from(name),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    headers
+}
+
+fn body_to_bytes(body: &Value) -> Vec<u8> {
+    match body {
+        Value::Null => Vec::new(),
+        Value::String(raw) => raw.clone().into_bytes(),
+        json => json.to_string().into_bytes(),
+    }
+}
+
+fn into_response(response: InterceptResponse) -> Response {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut builder = Response::builder().status(status);
+    if !matches!(response.body, Value::String(_) | Value::Null)
+        && !response
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("content-type"))
+    {
+        builder = builder.header(header::CONTENT_TYPE, "application/json");
+    }
+    for (name, value) in &response.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+
+    builder
+        .body(Body::from(body_to_bytes(&response.body)))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
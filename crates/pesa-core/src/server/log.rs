@@ -11,7 +11,14 @@ use serde_json::json;
 use std::collections::HashMap;
 use tokio::time::Instant;
 
-use crate::{api_logs::ApiLog, server::ApiState};
+use crate::{
+    api_logs::ApiLog,
+    inbox::INBOX_ROUTE,
+    recording::SessionEvent,
+    server::{
+        ApiState, async_handler::ConversationId, metrics::METRICS_ROUTE, openapi::OPENAPI_ROUTE,
+    },
+};
 
 use rand::{Rng, thread_rng as rng};
 
@@ -80,7 +87,8 @@ pub async fn logging_middleware(
     let headers_map = extract_headers(&headers);
     let (request, request_body) = extract_request_body(request).await;
     let response = next.run(request).await;
-    if path == "/" {
+    // inbox requests are stored by the inbox itself, scrapes and specs aren't worth a log
+    if path == "/" || path == INBOX_ROUTE || path == METRICS_ROUTE || path == OPENAPI_ROUTE {
         return Ok(response);
     }
 
@@ -88,6 +96,10 @@ pub async fn logging_middleware(
         .extensions()
         .get::<ApiError>()
         .map(|api_error| api_error.internal_description.clone());
+    let conversation_id = response
+        .extensions()
+        .get::<ConversationId>()
+        .map(|conversation| conversation.0.clone());
 
     let response_headers = response.headers().clone();
     let response_headers_map = extract_headers(&response_headers);
@@ -97,6 +109,20 @@ pub async fn logging_middleware(
     let status_code = response.status();
     let (response, response_body) = extract_response_body(response).await;
 
+    if state.context.recordings.is_recording(state.project_id) {
+        state.context.recordings.record(
+            state.project_id,
+            SessionEvent::request(
+                method.as_str(),
+                &uri,
+                &headers_map,
+                request_body.clone(),
+                status_code.as_u16(),
+                response_body.clone(),
+            ),
+        );
+    }
+
     let mut builder = ApiLog::builder()
         .project_id(state.project_id)
         .path(path)
@@ -118,9 +144,15 @@ pub async fn logging_middleware(
             .to_string(),
         );
 
+    if let Some(query) = uri.query() {
+        builder = builder.query(query);
+    }
     if let Some(error) = error_desc {
         builder = builder.error_desc(error);
     }
+    if let Some(conversation_id) = conversation_id {
+        builder = builder.conversation_id(conversation_id);
+    }
     builder.save(&state.context.db).await.map_err(|err| {
         println!("{}", err);
 
@@ -0,0 +1,47 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::time::Instant;
+
+use crate::{metrics::CONTENT_TYPE, server::ApiState};
+
+pub const METRICS_ROUTE: &str = "/metrics";
+
+/// Counts the requests a sandbox answers, by the route they matched.
+pub async fn metrics_middleware(
+    State(state): State<ApiState>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let start_time = Instant::now();
+    let method = request.method().clone();
+    // unmatched paths are left out of the labels, any path would make a new series
+    let route = matched_path
+        .as_ref()
+        .map_or("unmatched", |mp| mp.as_str())
+        .to_string();
+
+    let response = next.run(request).await;
+    if route != METRICS_ROUTE {
+        state.context.metrics.observe_request(
+            state.project_id,
+            method.as_str(),
+            &route,
+            response.status().as_u16(),
+            start_time.elapsed(),
+        );
+    }
+    response
+}
+
+/// The sandbox's own series, in the Prometheus text format.
+pub async fn metrics(State(state): State<ApiState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, CONTENT_TYPE)],
+        state.context.metrics.render(Some(state.project_id)),
+    )
+}
@@ -1,6 +1,7 @@
 use crate::{
     AppContext,
     accounts::user_profiles::User,
+    inbox::INBOX_ROUTE,
     projects::{self},
     server::{
         api::{
@@ -15,16 +16,23 @@ use axum::{
     Router,
     extract::State,
     http::HeaderValue,
-    routing::{get, post},
+    routing::{any, get, post},
 };
+use strum::EnumIter;
 use tokio::{net::TcpListener, sync::oneshot};
 
 pub mod access_token;
 pub mod api;
 pub mod async_handler;
+pub mod inbox;
+pub mod intercept;
 pub mod log;
+pub mod metrics;
+pub mod openapi;
+pub mod trace_context;
+pub mod validation;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, EnumIter)]
 pub enum MpesaError {
     // ==== Auth / OAuth ====
     InvalidCredentials,          // 401.001.01
@@ -35,12 +43,14 @@ pub enum MpesaError {
 
     // ==== Common Input Validation ====
     InvalidPhoneNumber,       // 400.002.02
+    InvalidTimestamp,         // 400.002.03
     InvalidAmount,            // 400.002.05
     InvalidShortcode,         // 400.002.07
     InvalidCommandId,         // 400.002.10
     InvalidCallbackUrl,       // 400.002.12
     InvalidIdentifierType,    // 400.002.13
     MissingRequiredParameter, // 400.002.14
+    InvalidParameterValue,    // 400.002.15
 
     // ==== STK Push Specific ====
     AlreadyProcessingRequest, // 409.002.01
@@ -60,7 +70,8 @@ pub enum MpesaError {
     InternalError,        // 500.001.01
     RateLimitExceeded,    // 429.001.01
     InvalidRequestFormat, // 400.001.01
-    Unknown(StatusCode),  // fallback
+    #[strum(disabled)]
+    Unknown(StatusCode), // fallback
 
     // ==== C2B ======
     UrlsAlreadyRegistered,
@@ -95,6 +106,7 @@ impl MpesaError {
                 "400.002.02",
                 "Invalid Phone Number",
             ),
+            InvalidTimestamp => (StatusCode::BAD_REQUEST, "400.002.03", "Invalid Timestamp"),
             InvalidAmount => (StatusCode::BAD_REQUEST, "400.002.05", "Invalid Amount"),
             InvalidShortcode => (StatusCode::BAD_REQUEST, "400.002.07", "Invalid Shortcode"),
             InvalidCommandId => (StatusCode::BAD_REQUEST, "400.002.10", "Invalid Command ID"),
@@ -113,6 +125,11 @@ impl MpesaError {
                 "400.002.14",
                 "Missing required parameter",
             ),
+            InvalidParameterValue => (
+                StatusCode::BAD_REQUEST,
+                "400.002.15",
+                "Invalid parameter value",
+            ),
 
             // --- STK Push ---
             AlreadyProcessingRequest => (
@@ -233,8 +250,23 @@ ______              ______ _                                             _
     .route("/mpesa/accountbalance/v1/query", post(handle_async_request::<BalanceQuery>))
     .route("/debug/config", get(get_api_keys))
     .route("/debug/users", get(get_users))
+    .route(INBOX_ROUTE, any(inbox::receive))
+    .route(metrics::METRICS_ROUTE, get(metrics::metrics))
+    .route(openapi::OPENAPI_ROUTE, get(openapi::openapi))
     .with_state(state.clone());
 
+    if state.context.interceptors.is_installed() {
+        router = router.route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            intercept::intercept_middleware,
+        ));
+    }
+
+    router = router.layer(axum::middleware::from_fn_with_state(
+        state.clone(),
+        metrics::metrics_middleware,
+    ));
+
     if log {
         router = router.layer(axum::middleware::from_fn_with_state(
             state.clone(),
@@ -0,0 +1,390 @@
+//! The OpenAPI document of a sandbox's Daraja endpoints, served at [`OPENAPI_ROUTE`].
+//!
+//! The schemas are derived from the request, response and callback types. utoipa has no model
+//! of callbacks, so they are added to the generated document along with the error bodies of
+//! [`MpesaError::to_response`].
+use std::collections::BTreeMap;
+
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, header},
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+use strum::IntoEnumIterator;
+use utoipa::{
+    Modify, OpenApi, ToSchema,
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+};
+
+use crate::{
+    projects, sandboxes,
+    server::{
+        ApiState, MpesaError,
+        api::{
+            auth::AuthResponse,
+            b2c::{B2CCallbackResponse, B2CRequest, B2CRequestResponse},
+            balance_query::{
+                BalanceQueryCallbackResponse, BalanceQueryRequest, BalanceQueryRequestResponse,
+            },
+            c2b::{
+                ResultResponse, ValidationRequest, ValidationResponse,
+                register::{RegisterUrlRequest, RegisterUrlResponse},
+            },
+            stkpush::{StkCallbackBodyWrapper, StkPushRequest, StkPushResponse},
+        },
+    },
+};
+
+pub const OPENAPI_ROUTE: &str = "/openapi.json";
+
+/// The body of every error response, see [`crate::server::ApiError`].
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    pub error_code: String,
+    pub error_message: String,
+}
+
+/// Generates an access token from the project's consumer key and secret.
+#[utoipa::path(
+    get,
+    path = "/oauth/v1/generate",
+    tag = "Authorization",
+    params(("grant_type" = String, Query, description = "Must be `client_credentials`.")),
+    security(("basic" = [])),
+    responses((status = 200, description = "A token valid for an hour.", body = AuthResponse))
+)]
+#[allow(dead_code)]
+fn oauth() {}
+
+/// Prompts the customer to enter their PIN to pay the business (Lipa na M-PESA Online).
+#[utoipa::path(
+    post,
+    path = "/mpesa/stkpush/v1/processrequest",
+    tag = "M-PESA Express",
+    request_body = StkPushRequest,
+    security(("bearer" = [])),
+    responses((status = 200, description = "The push was accepted.", body = StkPushResponse))
+)]
+#[allow(dead_code)]
+fn stk_push() {}
+
+/// Registers the URLs C2B payments to a shortcode are validated and confirmed at.
+#[utoipa::path(
+    post,
+    path = "/mpesa/c2b/v2/registerurl",
+    tag = "Customer To Business",
+    request_body = RegisterUrlRequest,
+    security(("bearer" = [])),
+    responses((status = 200, description = "The URLs were registered.", body = RegisterUrlResponse))
+)]
+#[allow(dead_code)]
+fn register_url() {}
+
+/// Pays a customer from the business' shortcode.
+#[utoipa::path(
+    post,
+    path = "/mpesa/b2c/v3/paymentrequest",
+    tag = "Business To Customer",
+    request_body = B2CRequest,
+    security(("bearer" = [])),
+    responses((status = 200, description = "The payment was accepted.", body = B2CRequestResponse))
+)]
+#[allow(dead_code)]
+fn b2c_payment() {}
+
+/// Queries the balances of the business' shortcode.
+#[utoipa::path(
+    post,
+    path = "/mpesa/accountbalance/v1/query",
+    tag = "Account Balance",
+    request_body = BalanceQueryRequest,
+    security(("bearer" = [])),
+    responses((status = 200, description = "The query was accepted.", body = BalanceQueryRequestResponse))
+)]
+#[allow(dead_code)]
+fn balance_query() {}
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "basic",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Basic)),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Pesa Playground sandbox",
+        description = "The Daraja APIs simulated by a Pesa Playground sandbox.",
+        license(name = "BSD-2-Clause")
+    ),
+    paths(oauth, stk_push, register_url, b2c_payment, balance_query),
+    components(schemas(
+        StkCallbackBodyWrapper,
+        B2CCallbackResponse,
+        BalanceQueryCallbackResponse,
+        ValidationRequest,
+        ValidationResponse,
+        ResultResponse,
+        ErrorResponse
+    )),
+    modifiers(&SecuritySchemes)
+)]
+struct SandboxApi;
+
+/// A request the sandbox makes to a URL given in the request that caused it.
+struct Callback {
+    name: &'static str,
+    /// The request body field holding the URL.
+    url_field: &'static str,
+    schema: &'static str,
+    /// The schema of the response the sandbox reads, if it reads one.
+    response: Option<&'static str>,
+}
+
+struct Operation {
+    path: &'static str,
+    method: &'static str,
+    errors: &'static [MpesaError],
+    callbacks: &'static [Callback],
+}
+
+/// The errors any endpoint taking a bearer token and a JSON body may answer with.
+const BEARER_ERRORS: &[MpesaError] = &[
+    MpesaError::InvalidAccessToken,
+    MpesaError::InternalError,
+    MpesaError::InvalidRequestFormat,
+    MpesaError::MissingRequiredParameter,
+];
+
+const OPERATIONS: &[Operation] = &[
+    Operation {
+        path: "/oauth/v1/generate",
+        method: "get",
+        errors: &[
+            MpesaError::InvalidGrantType,
+            MpesaError::InvalidAuthenticationPassed,
+            MpesaError::InvalidCredentials,
+            MpesaError::InternalError,
+        ],
+        callbacks: &[],
+    },
+    Operation {
+        path: "/mpesa/stkpush/v1/processrequest",
+        method: "post",
+        errors: &[
+            MpesaError::InvalidShortcode,
+            MpesaError::InvalidPhoneNumber,
+            MpesaError::InvalidAmount,
+            MpesaError::InvalidTimestamp,
+            MpesaError::InvalidCallbackUrl,
+            MpesaError::InvalidParameterValue,
+            MpesaError::InvalidCredentials,
+        ],
+        callbacks: &[Callback {
+            name: "result",
+            url_field: "CallBackURL",
+            schema: "StkCallbackBodyWrapper",
+            response: None,
+        }],
+    },
+    Operation {
+        path: "/mpesa/c2b/v2/registerurl",
+        method: "post",
+        errors: &[
+            MpesaError::InvalidShortcode,
+            MpesaError::InvalidCallbackUrl,
+            MpesaError::UrlsAlreadyRegistered,
+            MpesaError::C2BInvalidAccessToken,
+            MpesaError::C2BServerFailure,
+        ],
+        callbacks: &[
+            Callback {
+                name: "validation",
+                url_field: "ValidationURL",
+                schema: "ValidationRequest",
+                response: Some("ValidationResponse"),
+            },
+            Callback {
+                name: "confirmation",
+                url_field: "ConfirmationURL",
+                schema: "ValidationRequest",
+                response: Some("ResultResponse"),
+            },
+        ],
+    },
+    Operation {
+        path: "/mpesa/b2c/v3/paymentrequest",
+        method: "post",
+        errors: &[
+            MpesaError::InvalidShortcode,
+            MpesaError::InvalidPhoneNumber,
+            MpesaError::InvalidAmount,
+            MpesaError::InvalidCallbackUrl,
+            MpesaError::InvalidParameterValue,
+            MpesaError::InvalidCredentials,
+        ],
+        callbacks: &[Callback {
+            name: "result",
+            url_field: "ResultURL",
+            schema: "B2CCallbackResponse",
+            response: None,
+        }],
+    },
+    Operation {
+        path: "/mpesa/accountbalance/v1/query",
+        method: "post",
+        errors: &[
+            MpesaError::InvalidShortcode,
+            MpesaError::InvalidCallbackUrl,
+            MpesaError::InvalidParameterValue,
+            MpesaError::InvalidCredentials,
+        ],
+        callbacks: &[Callback {
+            name: "result",
+            url_field: "ResultURL",
+            schema: "BalanceQueryCallbackResponse",
+            response: None,
+        }],
+    },
+];
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// The error responses of an operation, one per status with an example per error.
+fn error_responses<'a>(errors: impl Iterator<Item = &'a MpesaError>) -> BTreeMap<String, Value> {
+    let mut examples: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+    for error in errors {
+        let (status, code, message) = error.to_response();
+        examples
+            .entry(status.as_u16().to_string())
+            .or_default()
+            .insert(
+                format!("{error:?}"),
+                json!({
+                    "summary": message,
+                    "value": { "errorCode": code, "errorMessage": message },
+                }),
+            );
+    }
+
+    examples
+        .into_iter()
+        .map(|(status, examples)| {
+            let description = status
+                .parse()
+                .ok()
+                .and_then(|status| axum::http::StatusCode::from_u16(status).ok())
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or("Error");
+            let response = json!({
+                "description": description,
+                "content": {
+                    "application/json": {
+                        "schema": schema_ref("ErrorResponse"),
+                        "examples": examples,
+                    }
+                }
+            });
+            (status, response)
+        })
+        .collect()
+}
+
+fn callbacks(callbacks: &[Callback]) -> Value {
+    let callbacks: serde_json::Map<String, Value> = callbacks
+        .iter()
+        .map(|callback| {
+            let response = match callback.response {
+                Some(schema) => json!({
+                    "description": "The receiver's answer.",
+                    "content": { "application/json": { "schema": schema_ref(schema) } },
+                }),
+                None => json!({ "description": "Any 2xx acknowledges the callback." }),
+            };
+            let expression = format!("{{$request.body#/{}}}", callback.url_field);
+            let operation = json!({
+                "post": {
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": schema_ref(callback.schema) }
+                        },
+                    },
+                    "responses": { "200": response },
+                }
+            });
+            (callback.name.to_string(), json!({ expression: operation }))
+        })
+        .collect();
+    Value::Object(callbacks)
+}
+
+/// The OpenAPI document, with `base_url` as its server if one is known.
+pub fn document(title: Option<&str>, base_url: Option<&str>) -> Value {
+    let mut document = serde_json::to_value(SandboxApi::openapi())
+        .expect("the generated OpenAPI document serializes");
+
+    if let Some(title) = title {
+        document["info"]["title"] = json!(title);
+    }
+    if let Some(base_url) = base_url {
+        document["servers"] = json!([{ "url": base_url }]);
+    }
+
+    for operation in OPERATIONS {
+        let target = &mut document["paths"][operation.path][operation.method];
+        let bearer_errors = if operation.method == "post" {
+            BEARER_ERRORS
+        } else {
+            &[]
+        };
+        let errors = operation.errors.iter().chain(bearer_errors);
+        for (status, response) in error_responses(errors) {
+            target["responses"][status] = response;
+        }
+        if !operation.callbacks.is_empty() {
+            target["callbacks"] = callbacks(operation.callbacks);
+        }
+    }
+
+    // every code the sandbox can answer with
+    let codes: Vec<&str> = MpesaError::iter()
+        .map(|error| error.to_response().1)
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    document["components"]["schemas"]["ErrorResponse"]["properties"]["errorCode"]["enum"] =
+        json!(codes);
+
+    document
+}
+
+pub async fn openapi(State(state): State<ApiState>, headers: HeaderMap) -> Json<Value> {
+    let title = projects::ui::get_project(&state.context, state.project_id)
+        .await
+        .ok()
+        .map(|project| format!("Pesa Playground: {}", project.name));
+    // a sandbox that isn't running is being driven in process, its caller knows where it is
+    let base_url = sandboxes::ui::sandbox_url(&state.context, state.project_id).or_else(|| {
+        headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .map(|host| format!("http://{host}"))
+    });
+    Json(document(title.as_deref(), base_url.as_deref()))
+}
@@ -0,0 +1,143 @@
+//! W3C trace context, carried from a request to the callbacks it causes.
+//!
+//! With an OpenTelemetry layer installed the sandbox's spans join the caller's trace and the
+//! callbacks name the dispatch span as their parent. Without one, or without the `otel` feature,
+//! the caller's trace id is still passed on, so their backend sees the callback as part of the
+//! request that caused it.
+use axum::http::HeaderMap;
+#[cfg(feature = "otel")]
+use opentelemetry::{
+    Context,
+    trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+};
+#[cfg(feature = "otel")]
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+pub const TRACEPARENT: &str = "traceparent";
+pub const TRACESTATE: &str = "tracestate";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceContext {
+    pub trace_id: u128,
+    pub span_id: u64,
+    pub sampled: bool,
+    /// The vendor specific `tracestate`, passed on untouched.
+    pub state: Option<String>,
+}
+
+impl TraceContext {
+    /// Parses the `traceparent` and `tracestate` headers. A missing or malformed `traceparent`
+    /// starts no trace.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let traceparent = headers.get(TRACEPARENT)?.to_str().ok()?;
+        let mut context = Self::parse(traceparent)?;
+        context.state = headers
+            .get(TRACESTATE)
+            .and_then(|state| state.to_str().ok())
+            .map(str::to_string);
+        Some(context)
+    }
+
+    /// Parses a `traceparent` of the form `00-<trace id>-<parent id>-<flags>`.
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+        // later versions may append fields, version 00 may not
+        if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        if trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+            return None;
+        }
+        let is_hex = |part: &str| part.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+        if ![version, trace_id, span_id, flags].into_iter().all(is_hex) {
+            return None;
+        }
+
+        let trace_id = u128::from_str_radix(trace_id, 16).ok()?;
+        let span_id = u64::from_str_radix(span_id, 16).ok()?;
+        if trace_id == 0 || span_id == 0 {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(Self {
+            trace_id,
+            span_id,
+            sampled: flags & 1 == 1,
+            state: None,
+        })
+    }
+
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id,
+            self.span_id,
+            u8::from(self.sampled)
+        )
+    }
+
+    /// The headers that carry this context on an outgoing request.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = vec![(TRACEPARENT, self.traceparent())];
+        if let Some(state) = &self.state {
+            headers.push((TRACESTATE, state.clone()));
+        }
+        headers
+    }
+
+    /// Makes `span` a child of the caller's span, in their `tracestate`. A malformed
+    /// `tracestate` is dropped. Does nothing without an OpenTelemetry layer.
+    #[cfg(feature = "otel")]
+    pub fn attach(&self, span: &tracing::Span) {
+        let flags = if self.sampled {
+            TraceFlags::SAMPLED
+        } else {
+            TraceFlags::default()
+        };
+        let state = self
+            .state
+            .as_deref()
+            .and_then(|state| state.parse::<TraceState>().ok())
+            .unwrap_or_default();
+        let remote = SpanContext::new(
+            TraceId::from(self.trace_id),
+            SpanId::from(self.span_id),
+            flags,
+            true,
+            state,
+        );
+        let _ = span.set_parent(Context::new().with_remote_span_context(remote));
+    }
+
+    #[cfg(not(feature = "otel"))]
+    pub fn attach(&self, _span: &tracing::Span) {}
+
+    /// The context to send on from within `span`: the span's own when OpenTelemetry tracks it,
+    /// otherwise `inbound`'s trace under a new span id.
+    pub fn outgoing(span: &tracing::Span, inbound: Option<&TraceContext>) -> Option<Self> {
+        #[cfg(feature = "otel")]
+        {
+            let context = span.context();
+            let current = context.span().span_context().clone();
+            if current.is_valid() {
+                return Some(Self {
+                    trace_id: u128::from_be_bytes(current.trace_id().to_bytes()),
+                    span_id: u64::from_be_bytes(current.span_id().to_bytes()),
+                    sampled: current.is_sampled(),
+                    state: inbound.and_then(|inbound| inbound.state.clone()),
+                });
+            }
+        }
+        #[cfg(not(feature = "otel"))]
+        let _ = span;
+
+        inbound.map(|inbound| Self {
+            span_id: rand::random::<u64>().max(1),
+            ..inbound.clone()
+        })
+    }
+}
@@ -0,0 +1,254 @@
+//! Daraja's rules for the requests a sandbox takes.
+//!
+//! Every project gets the checks a request can't be served without: a body that parses, a
+//! shortcode that is a number, an amount that is one. A project in [`ValidationMode::Strict`]
+//! also turns away what Daraja would, such as local phone numbers, fractional amounts, stale
+//! timestamps and account references that are too long.
+use axum::{
+    Json,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeDelta, Utc};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    projects::{Project, ValidationMode},
+    server::{
+        ApiError, ApiState, MpesaError,
+        api::{
+            b2c::B2CRequest, balance_query::BalanceQueryRequest, c2b::register::RegisterUrlRequest,
+            stkpush::StkPushRequest,
+        },
+    },
+};
+
+/// The format of an STK push `Timestamp`, in Nairobi time.
+pub const TIMESTAMP_FORMAT: &str = "%Y%m%d%H%M%S";
+/// How old a `Timestamp` may be. Clients sending UTC instead of Nairobi time are 3 hours behind.
+const TIMESTAMP_MAX_AGE: TimeDelta = TimeDelta::hours(24);
+/// How far ahead of the sandbox's clock a `Timestamp` may be.
+const TIMESTAMP_MAX_SKEW: TimeDelta = TimeDelta::minutes(5);
+/// Words Daraja refuses in C2B URLs, in any case.
+const FORBIDDEN_URL_WORDS: [&str; 7] =
+    ["m-pesa", "mpesa", "safaricom", "exe", "cmd", "sql", "query"];
+
+/// A JSON body that answers with a Daraja error, rather than axum's, when it doesn't parse.
+pub struct DarajaJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for DarajaJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(Self(value)),
+            Err(rejection) => {
+                let error = match &rejection {
+                    JsonRejection::JsonDataError(err)
+                        if err.body_text().contains("missing field") =>
+                    {
+                        MpesaError::MissingRequiredParameter
+                    }
+                    _ => MpesaError::InvalidRequestFormat,
+                };
+                Err(ApiError::new(error, rejection.body_text()))
+            }
+        }
+    }
+}
+
+/// Daraja's rules for a request, checked for projects in strict mode.
+pub trait Validate {
+    fn validate(&self) -> Result<(), ApiError>;
+}
+
+/// Checks `req` against Daraja's rules if the sandbox's project asks for it.
+pub async fn validate<T: Validate>(state: &ApiState, req: &T) -> Result<(), ApiError> {
+    let project = Project::get_by_id(&state.context.db, state.project_id)
+        .await
+        .map_err(|err| ApiError::new(MpesaError::InternalError, err.to_string()))?;
+    match project.map(|project| project.validation_mode) {
+        Some(ValidationMode::Strict) => req.validate(),
+        _ => Ok(()),
+    }
+}
+
+/// Parses a shortcode, which must be a number whatever the mode.
+pub fn shortcode(field: &str, value: &str) -> Result<u32, ApiError> {
+    value.trim().parse().map_err(|_| {
+        ApiError::new(
+            MpesaError::InvalidShortcode,
+            format!("{field} must be a number, you provided: {value:?}"),
+        )
+    })
+}
+
+/// Parses an amount into cents. Any positive number is taken, strict mode only takes whole ones.
+pub fn amount(value: &str) -> Result<i64, ApiError> {
+    match value.trim().parse::<f64>() {
+        Ok(amount) if amount.is_finite() && amount > 0.0 => Ok((amount * 100.0).round() as i64),
+        _ => Err(ApiError::new(
+            MpesaError::InvalidAmount,
+            format!("Amount must be a positive number, you provided: {value:?}"),
+        )),
+    }
+}
+
+fn strict_shortcode(field: &str, value: &str) -> Result<(), ApiError> {
+    if !(5..=7).contains(&value.len()) || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ApiError::new(
+            MpesaError::InvalidShortcode,
+            format!("{field} must be a shortcode of 5 to 7 digits, you provided: {value:?}"),
+        ));
+    }
+    Ok(())
+}
+
+fn whole_amount(value: &str) -> Result<(), ApiError> {
+    match value.parse::<u64>() {
+        Ok(amount) if amount > 0 => Ok(()),
+        _ => Err(ApiError::new(
+            MpesaError::InvalidAmount,
+            format!("Amount must be a whole number of shillings, you provided: {value:?}"),
+        )),
+    }
+}
+
+/// A Safaricom number in international format, `2547XXXXXXXX` or `2541XXXXXXXX`.
+pub fn msisdn(field: &str, value: &str) -> Result<(), ApiError> {
+    let valid = value.len() == 12
+        && value.bytes().all(|b| b.is_ascii_digit())
+        && (value.starts_with("2547") || value.starts_with("2541"));
+    if !valid {
+        return Err(ApiError::new(
+            MpesaError::InvalidPhoneNumber,
+            format!("{field} must be of the form 2547XXXXXXXX, you provided: {value:?}"),
+        ));
+    }
+    Ok(())
+}
+
+/// A `YYYYMMDDHHmmss` timestamp in Nairobi time, no older than a day and not ahead of the clock.
+pub fn timestamp(value: &str, now: DateTime<Utc>) -> Result<(), ApiError> {
+    let nairobi = FixedOffset::east_opt(3 * 3600).expect("UTC+3 is a valid offset");
+    let timestamp = NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+        .ok()
+        .and_then(|timestamp| timestamp.and_local_timezone(nairobi).single())
+        .ok_or_else(|| {
+            ApiError::new(
+                MpesaError::InvalidTimestamp,
+                format!("Timestamp must be of the form YYYYMMDDHHmmss, you provided: {value:?}"),
+            )
+        })?;
+
+    let age = now.signed_duration_since(timestamp);
+    if age > TIMESTAMP_MAX_AGE || age < -TIMESTAMP_MAX_SKEW {
+        return Err(ApiError::new(
+            MpesaError::InvalidTimestamp,
+            format!("Timestamp {value} is too far from the current time in Nairobi"),
+        ));
+    }
+    Ok(())
+}
+
+fn url(field: &str, value: &str) -> Result<(), ApiError> {
+    match reqwest::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.has_host() => Ok(()),
+        _ => Err(ApiError::new(
+            MpesaError::InvalidCallbackUrl,
+            format!("{field} must be an http or https URL, you provided: {value:?}"),
+        )),
+    }
+}
+
+fn c2b_url(field: &str, value: &str) -> Result<(), ApiError> {
+    url(field, value)?;
+    let lowercase = value.to_lowercase();
+    if let Some(word) = FORBIDDEN_URL_WORDS
+        .iter()
+        .find(|word| lowercase.contains(*word))
+    {
+        return Err(ApiError::new(
+            MpesaError::InvalidCallbackUrl,
+            format!("{field} must not contain {word:?}"),
+        ));
+    }
+    Ok(())
+}
+
+fn required(field: &str, value: &str) -> Result<(), ApiError> {
+    if value.trim().is_empty() {
+        return Err(ApiError::new(
+            MpesaError::MissingRequiredParameter,
+            format!("{field} is required"),
+        ));
+    }
+    Ok(())
+}
+
+/// Free text of `min` to `max` characters.
+fn text(field: &str, value: &str, min: usize, max: usize) -> Result<(), ApiError> {
+    if min > 0 {
+        required(field, value)?;
+    }
+    let length = value.chars().count();
+    if length < min || length > max {
+        return Err(ApiError::new(
+            MpesaError::InvalidParameterValue,
+            format!("{field} must be {min} to {max} characters long, it is {length}"),
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for StkPushRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        strict_shortcode("BusinessShortCode", &self.business_short_code)?;
+        required("Password", &self.password)?;
+        timestamp(&self.timestamp, Utc::now())?;
+        whole_amount(&self.amount)?;
+        msisdn("PartyA", &self.party_a)?;
+        strict_shortcode("PartyB", &self.party_b)?;
+        msisdn("PhoneNumber", &self.phone_number)?;
+        url("CallBackURL", &self.call_back_u_r_l)?;
+        text("AccountReference", &self.account_reference, 1, 12)?;
+        text("TransactionDesc", &self.transaction_desc, 1, 13)
+    }
+}
+
+impl Validate for B2CRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        required("OriginatorConversationID", &self.originator_conversation_id)?;
+        required("InitiatorName", &self.initiator_name)?;
+        required("SecurityCredential", &self.security_credential)?;
+        whole_amount(&self.amount)?;
+        strict_shortcode("PartyA", &self.party_a)?;
+        msisdn("PartyB", &self.party_b)?;
+        text("Remarks", &self.remarks, 2, 100)?;
+        url("QueueTimeOutURL", &self.queue_time_out_url)?;
+        url("ResultURL", &self.result_url)?;
+        text("Occassion", &self.occassion, 0, 100)
+    }
+}
+
+impl Validate for BalanceQueryRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        required("Initiator", &self.initiator)?;
+        required("SecurityCredential", &self.security_credential)?;
+        strict_shortcode("PartyA", &self.party_a)?;
+        text("Remarks", &self.remarks, 1, 100)?;
+        url("QueueTimeOutURL", &self.queue_time_out_url)?;
+        url("ResultURL", &self.result_url)
+    }
+}
+
+impl Validate for RegisterUrlRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        strict_shortcode("ShortCode", &self.short_code.to_string())?;
+        c2b_url("ConfirmationURL", &self.confirmation_url)?;
+        c2b_url("ValidationURL", &self.validation_url)
+    }
+}
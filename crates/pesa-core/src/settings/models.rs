@@ -18,12 +18,20 @@ pub struct AppSettings {
     pub server_log_level: LogLevel,
     pub encryption_keys: Option<EncryptionKeys>,
     pub custom_keymaps: Option<HashMap<String, String>>,
+    /// Hosts Lua scripts may reach with `pesa.http`: exact names, `*.example.com` for
+    /// subdomains or `*` for any host.
+    #[serde(default = "default_script_http_allowlist")]
+    pub script_http_allowlist: Vec<String>,
 }
 
 fn default_theme() -> Theme {
     Theme::Dark
 }
 
+fn default_script_http_allowlist() -> Vec<String> {
+    ["localhost", "127.0.0.1", "::1"].map(String::from).to_vec()
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -31,25 +39,44 @@ impl Default for AppSettings {
             server_log_level: LogLevel::Info,
             encryption_keys: None,
             custom_keymaps: None,
+            script_http_allowlist: default_script_http_allowlist(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+impl AppSettings {
+    /// Whether `host` matches an entry of the script HTTP allowlist.
+    pub fn allows_script_host(&self, host: &str) -> bool {
+        // IPv6 hosts come bracketed from URLs
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+        self.script_http_allowlist.iter().any(|allowed| {
+            let allowed = allowed.trim().to_ascii_lowercase();
+            if allowed == "*" {
+                return true;
+            }
+            match allowed.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+                None => host == allowed,
+            }
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub enum LogLevel {
     Trace,
     Debug,
+    #[default]
     Info,
     Warn,
     Error,
 }
 
-impl Default for LogLevel {
-    fn default() -> Self {
-        Self::Info
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum Theme {
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "request_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u32,
+    pub project_id: u32,
+    #[sea_orm(indexed)]
+    pub conversation_id: String,
+    /// A [`super::RequestEventKind`].
+    pub kind: String,
+    /// What happened, as JSON.
+    pub detail: Option<String>,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::projects::db::Entity",
+        from = "Column::ProjectId",
+        to = "crate::projects::db::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Project,
+}
+
+impl Related<crate::projects::db::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Project.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
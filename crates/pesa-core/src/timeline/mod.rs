@@ -0,0 +1,307 @@
+//! The story of one API request. An STK push leaves an API log, a prompt on the user's phone,
+//! their answer, a transaction with its journal entries and a callback, all sharing the
+//! conversation id the sandbox gave the request.
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set, prelude::DateTimeUtc,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::{Display, EnumString};
+
+use crate::{
+    api_logs,
+    callbacks::{self, CallbackLog, CallbackStatus, CallbackType},
+    journal::{EntrySide, Journal},
+    transactions::{self, Transaction},
+};
+
+pub mod db;
+pub mod ui;
+
+/// Steps of a request that happen outside the API logs, callbacks and ledger.
+#[derive(Debug, Clone, PartialEq, Display, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RequestEventKind {
+    /// The STK prompt was shown on the user's phone.
+    PromptShown,
+    /// The user answered the STK prompt, or failed to.
+    UserResponded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestEvent {
+    pub id: u32,
+    pub project_id: u32,
+    pub conversation_id: String,
+    pub kind: RequestEventKind,
+    pub detail: Value,
+    pub created_at: DateTimeUtc,
+}
+
+impl From<db::Model> for RequestEvent {
+    fn from(model: db::Model) -> Self {
+        Self {
+            id: model.id,
+            project_id: model.project_id,
+            conversation_id: model.conversation_id,
+            kind: model.kind.parse().unwrap_or(RequestEventKind::PromptShown),
+            detail: model
+                .detail
+                .and_then(|detail| serde_json::from_str(&detail).ok())
+                .unwrap_or_default(),
+            created_at: model.created_at,
+        }
+    }
+}
+
+impl RequestEvent {
+    pub async fn record<C: ConnectionTrait>(
+        db: &C,
+        project_id: u32,
+        conversation_id: &str,
+        kind: RequestEventKind,
+        detail: Value,
+    ) -> Result<Self, DbErr> {
+        let model = db::ActiveModel {
+            project_id: Set(project_id),
+            conversation_id: Set(conversation_id.to_string()),
+            kind: Set(kind.to_string()),
+            detail: Set(Some(detail.to_string())),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        Ok(model.into())
+    }
+
+    pub async fn for_conversation<C: ConnectionTrait>(
+        db: &C,
+        conversation_id: &str,
+    ) -> Result<Vec<Self>, DbErr> {
+        Ok(db::Entity::find()
+            .filter(db::Column::ConversationId.eq(conversation_id))
+            .order_by_asc(db::Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimelineEvent {
+    RequestReceived {
+        log_id: String,
+        method: String,
+        path: String,
+        status_code: u16,
+        error: Option<String>,
+    },
+    PromptShown {
+        detail: Value,
+    },
+    UserResponded {
+        detail: Value,
+    },
+    TransactionPosted {
+        transaction_id: String,
+        transaction_type: String,
+        amount: i64,
+        fee: i64,
+        status: String,
+    },
+    LedgerEntry {
+        transaction_id: String,
+        account_id: u32,
+        side: EntrySide,
+        amount: i64,
+    },
+    CallbackSent {
+        callback_id: u32,
+        callback_type: CallbackType,
+        url: String,
+        payload: Value,
+    },
+    CallbackResponse {
+        callback_id: u32,
+        status: CallbackStatus,
+        response_status: Option<i32>,
+        response_body: Option<String>,
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEntry {
+    pub at: DateTimeUtc,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+}
+
+/// Everything that happened for one conversation, oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestTimeline {
+    pub conversation_id: String,
+    pub project_id: Option<u32>,
+    pub entries: Vec<TimelineEntry>,
+}
+
+/// Finds the conversation `id` belongs to. Besides the conversation id itself this takes an API
+/// log id, a transaction receipt, or an id the client chose or was given, like a
+/// `CheckoutRequestID` or `OriginatorConversationID`.
+pub async fn resolve_conversation<C: ConnectionTrait>(
+    db: &C,
+    id: &str,
+) -> Result<Option<String>, DbErr> {
+    if let Some(log) = api_logs::db::Entity::find_by_id(id).one(db).await? {
+        return Ok(log.conversation_id);
+    }
+    if let Some(transaction) = transactions::db::Entity::find_by_id(id).one(db).await? {
+        return Ok(transaction.conversation_id);
+    }
+
+    let callback = callbacks::db::Entity::find()
+        .filter(
+            Condition::any()
+                .add(callbacks::db::Column::ConversationId.eq(id))
+                .add(callbacks::db::Column::OriginatorId.eq(id))
+                .add(callbacks::db::Column::TransactionId.eq(id)),
+        )
+        .one(db)
+        .await?;
+    if let Some(callback) = callback {
+        return Ok(Some(callback.conversation_id));
+    }
+
+    let logged = api_logs::db::Entity::find()
+        .filter(api_logs::db::Column::ConversationId.eq(id))
+        .one(db)
+        .await?
+        .is_some();
+    let recorded = db::Entity::find()
+        .filter(db::Column::ConversationId.eq(id))
+        .one(db)
+        .await?
+        .is_some();
+    Ok((logged || recorded).then(|| id.to_string()))
+}
+
+/// Gathers the API logs, request events, transactions, journal entries and callbacks of a
+/// conversation into one timeline.
+pub async fn timeline<C: ConnectionTrait>(
+    db: &C,
+    conversation_id: &str,
+) -> Result<RequestTimeline, DbErr> {
+    let mut project_id = None;
+    let mut entries = Vec::new();
+
+    let logs = api_logs::db::Entity::find()
+        .filter(api_logs::db::Column::ConversationId.eq(conversation_id))
+        .order_by_asc(api_logs::db::Column::CreatedAt)
+        .all(db)
+        .await?;
+    for log in logs {
+        project_id.get_or_insert(log.project_id);
+        // logs are written once the response is out, the request came in `duration` earlier
+        let at = log.created_at - chrono::Duration::milliseconds(log.duration as i64);
+        entries.push(TimelineEntry {
+            at,
+            event: TimelineEvent::RequestReceived {
+                log_id: log.id,
+                method: log.method,
+                path: log.path,
+                status_code: log.status_code,
+                error: log.error_desc,
+            },
+        });
+    }
+
+    for event in RequestEvent::for_conversation(db, conversation_id).await? {
+        project_id.get_or_insert(event.project_id);
+        let detail = event.detail;
+        entries.push(TimelineEntry {
+            at: event.created_at,
+            event: match event.kind {
+                RequestEventKind::PromptShown => TimelineEvent::PromptShown { detail },
+                RequestEventKind::UserResponded => TimelineEvent::UserResponded { detail },
+            },
+        });
+    }
+
+    let transactions = transactions::db::Entity::find()
+        .filter(transactions::db::Column::ConversationId.eq(conversation_id))
+        .order_by_asc(transactions::db::Column::CreatedAt)
+        .all(db)
+        .await?;
+    for transaction in transactions {
+        let transaction: Transaction = transaction.into();
+        entries.push(TimelineEntry {
+            at: transaction.created_at,
+            event: TimelineEvent::TransactionPosted {
+                transaction_id: transaction.id.clone(),
+                transaction_type: transaction.transaction_type.to_string(),
+                amount: transaction.amount,
+                fee: transaction.fee,
+                status: transaction.status.to_string(),
+            },
+        });
+        for entry in Journal::entries_for_transaction(db, &transaction.id).await? {
+            entries.push(TimelineEntry {
+                at: entry.created_at,
+                event: TimelineEvent::LedgerEntry {
+                    transaction_id: entry.transaction_id,
+                    account_id: entry.account_id,
+                    side: entry.side,
+                    amount: entry.amount,
+                },
+            });
+        }
+    }
+
+    let callbacks = callbacks::db::Entity::find()
+        .filter(callbacks::db::Column::ConversationId.eq(conversation_id))
+        .order_by_asc(callbacks::db::Column::Id)
+        .all(db)
+        .await?;
+    for callback in callbacks {
+        let callback: CallbackLog = callback.into();
+        project_id.get_or_insert(callback.project_id);
+        entries.push(TimelineEntry {
+            at: callback.created_at,
+            event: TimelineEvent::CallbackSent {
+                callback_id: callback.id,
+                callback_type: callback.callback_type,
+                url: callback.callback_url,
+                payload: callback.payload,
+            },
+        });
+        if callback.status != CallbackStatus::Pending {
+            entries.push(TimelineEntry {
+                at: callback.updated_at.unwrap_or(callback.created_at),
+                event: TimelineEvent::CallbackResponse {
+                    callback_id: callback.id,
+                    status: callback.status,
+                    response_status: callback.response_status,
+                    response_body: callback.response_body,
+                    error: callback.error,
+                },
+            });
+        }
+    }
+
+    // stable, so a transaction stays ahead of its journal entries and a callback ahead of its
+    // response when they share a timestamp
+    entries.sort_by_key(|entry| entry.at);
+
+    Ok(RequestTimeline {
+        conversation_id: conversation_id.to_string(),
+        project_id,
+        entries,
+    })
+}
@@ -0,0 +1,19 @@
+use anyhow::{Context, Result};
+
+use super::{RequestTimeline, resolve_conversation, timeline};
+use crate::AppContext;
+
+/// The timeline of the request `id` belongs to, see [`resolve_conversation`] for the ids taken.
+pub async fn get_request_timeline(ctx: &AppContext, id: String) -> Result<Option<RequestTimeline>> {
+    let Some(conversation_id) = resolve_conversation(&ctx.db, &id)
+        .await
+        .context("Failed to find the request")?
+    else {
+        return Ok(None);
+    };
+
+    let timeline = timeline(&ctx.db, &conversation_id)
+        .await
+        .context("Failed to build the request timeline")?;
+    Ok(Some(timeline))
+}
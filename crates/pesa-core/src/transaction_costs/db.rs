@@ -6,11 +6,33 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
+    /// The product this band applies to, e.g. `paybill` or `send_money`.
     pub transaction_type: String,
     pub min_amount: i64,
     pub max_amount: i64,
     pub fee_fixed: Option<i64>,
     pub fee_percentage: Option<f64>,
+    /// Limits the band to a single business. `None` applies to everyone.
+    pub business_id: Option<u32>,
+    pub paid_by: FeePayer,
+    /// When this version of the bands takes effect. `None` belongs to the base schedule.
+    pub effective_from: Option<DateTimeUtc>,
+}
+
+/// Who is charged the fee of a transaction.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "fee_payer")]
+#[serde(rename_all = "snake_case")]
+pub enum FeePayer {
+    /// The sender pays the fee on top of the amount.
+    #[default]
+    #[sea_orm(string_value = "customer")]
+    Customer,
+    /// The business on the transaction accrues the fee on its charges account.
+    #[sea_orm(string_value = "business")]
+    Business,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -1,23 +1,30 @@
 pub mod db;
+pub mod schedule;
 pub mod ui;
 
+use chrono::{DateTime, Utc};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::transactions::TransactionType;
 
-use self::db::{ActiveModel as TransactionCostActiveModel, Entity};
+use self::db::Entity;
+pub use self::db::FeePayer;
 
-pub async fn get_fee<C>(
-    db: &C,
-    transaction_type: &TransactionType,
-    amount: i64,
-) -> Result<i64, DbErr>
-where
-    C: ConnectionTrait,
-{
-    let txn_type = match transaction_type {
+/// A resolved fee and who is charged for it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AppliedFee {
+    pub fee: i64,
+    pub paid_by: FeePayer,
+}
+
+/// The product whose tariff applies when `transaction_type` has no bands of its own.
+fn base_product(transaction_type: &TransactionType) -> Option<String> {
+    let product = match transaction_type {
         TransactionType::Paybill
         | TransactionType::BuyGoods
         | TransactionType::SendMoney
@@ -30,286 +37,144 @@ where
         // these should not incur a fee
         TransactionType::ChargeSettlement
         | TransactionType::RevenueSweep
-        | TransactionType::TopupUtility => {
-            return Ok(0);
+        | TransactionType::TopupUtility
+        | TransactionType::OpeningBalance => {
+            return None;
         }
     };
 
+    Some(product)
+}
+
+/// Fee of a transaction made now under the global tariff.
+pub async fn get_fee<C>(
+    db: &C,
+    transaction_type: &TransactionType,
+    amount: i64,
+) -> Result<i64, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(resolve_fee(db, transaction_type, amount, None, Utc::now())
+        .await?
+        .fee)
+}
+
+/// Resolves the fee of a transaction made at `at` and who pays it.
+///
+/// Tariff bands are scoped. The first band covering `amount` wins, looked up in order:
+///
+/// 1. bands of `business_id` for the exact product,
+/// 2. global bands for the exact product,
+/// 3. global bands of the base product, e.g. `send_money` for paybill payments.
+///
+/// Within each scope only the latest version of the bands in effect at `at` is considered.
+pub async fn resolve_fee<C>(
+    db: &C,
+    transaction_type: &TransactionType,
+    amount: i64,
+    business_id: Option<u32>,
+    at: DateTime<Utc>,
+) -> Result<AppliedFee, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let free = AppliedFee {
+        fee: 0,
+        paid_by: FeePayer::Customer,
+    };
+    let Some(base) = base_product(transaction_type) else {
+        return Ok(free);
+    };
+
+    let product = transaction_type.to_string();
     let amount_in_kes = amount / 100;
-    let rule = Entity::find()
-        .filter(db::Column::TransactionType.eq(txn_type))
-        .filter(db::Column::MinAmount.lte(amount_in_kes))
-        .filter(db::Column::MaxAmount.gte(amount_in_kes))
-        .one(db)
-        .await?;
 
-    if let Some(rule) = rule {
-        let mut fee = 0;
-        if let Some(fixed) = rule.fee_fixed {
-            fee += fixed * 100; // Convert fixed fee to cents
-        }
-        if let Some(percentage) = rule.fee_percentage {
-            fee += (amount as f64 * percentage / 100.0).round() as i64;
-        }
-        return Ok(fee);
+    let mut rule = None;
+    if let Some(business_id) = business_id {
+        rule = find_band(db, &product, Some(business_id), amount_in_kes, at).await?;
+    }
+    if rule.is_none() {
+        rule = find_band(db, &product, None, amount_in_kes, at).await?;
+    }
+    if rule.is_none() && base != product {
+        rule = find_band(db, &base, None, amount_in_kes, at).await?;
+    }
+
+    let Some(rule) = rule else {
+        return Ok(free);
+    };
+
+    let mut fee = 0;
+    if let Some(fixed) = rule.fee_fixed {
+        fee += fixed * 100; // Convert fixed fee to cents
     }
+    if let Some(percentage) = rule.fee_percentage {
+        fee += (amount as f64 * percentage / 100.0).round() as i64;
+    }
+
+    Ok(AppliedFee {
+        fee,
+        paid_by: rule.paid_by,
+    })
+}
+
+/// The band covering `amount_in_kes` in the latest version of a scope in effect at `at`.
+async fn find_band<C>(
+    db: &C,
+    product: &str,
+    business_id: Option<u32>,
+    amount_in_kes: i64,
+    at: DateTime<Utc>,
+) -> Result<Option<db::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let scope = Condition::all()
+        .add(db::Column::TransactionType.eq(product))
+        .add(match business_id {
+            Some(id) => db::Column::BusinessId.eq(id),
+            None => db::Column::BusinessId.is_null(),
+        });
+
+    // NULLs sort last when descending, so dated versions win over the base schedule
+    let Some(latest) = Entity::find()
+        .filter(scope.clone())
+        .filter(
+            Condition::any()
+                .add(db::Column::EffectiveFrom.is_null())
+                .add(db::Column::EffectiveFrom.lte(at)),
+        )
+        .order_by_desc(db::Column::EffectiveFrom)
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
 
-    Ok(0)
+    Entity::find()
+        .filter(scope)
+        .filter(match latest.effective_from {
+            Some(effective_from) => db::Column::EffectiveFrom.eq(effective_from),
+            None => db::Column::EffectiveFrom.is_null(),
+        })
+        .filter(db::Column::MinAmount.lte(amount_in_kes))
+        .filter(db::Column::MaxAmount.gte(amount_in_kes))
+        .one(db)
+        .await
 }
 
+/// Seeds the Safaricom tariff bundled in `default_tariff.csv` into an empty tariff table.
 pub async fn init_default_costs<C>(db: &C) -> Result<(), DbErr>
 where
     C: ConnectionTrait,
 {
     if Entity::find().count(db).await? == 0 {
-        let default_costs = vec![
-            // Withdraw From M-PESA Agent
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(1),
-                max_amount: sea_orm::ActiveValue::Set(49),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(0)), // Free
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(50),
-                max_amount: sea_orm::ActiveValue::Set(100),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(11)), // 11.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(101),
-                max_amount: sea_orm::ActiveValue::Set(500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(29)), // 29.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(501),
-                max_amount: sea_orm::ActiveValue::Set(1000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(29)), // 29.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(1001),
-                max_amount: sea_orm::ActiveValue::Set(1500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(29)), // 29.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(1501),
-                max_amount: sea_orm::ActiveValue::Set(2500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(29)), // 29.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(2501),
-                max_amount: sea_orm::ActiveValue::Set(3500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(52)), // 52.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(3501),
-                max_amount: sea_orm::ActiveValue::Set(5000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(69)), // 69.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(5001),
-                max_amount: sea_orm::ActiveValue::Set(7500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(87)), // 87.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(7501),
-                max_amount: sea_orm::ActiveValue::Set(10000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(115)), // 115.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(10001),
-                max_amount: sea_orm::ActiveValue::Set(15000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(167)), // 167.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(15001),
-                max_amount: sea_orm::ActiveValue::Set(20000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(185)), // 185.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(20001),
-                max_amount: sea_orm::ActiveValue::Set(35000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(197)), // 197.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(35001),
-                max_amount: sea_orm::ActiveValue::Set(50000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(278)), // 278.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Withdraw.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(50001),
-                max_amount: sea_orm::ActiveValue::Set(150000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(309)), // 309.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            // Send to other M-PESA Users, Pochi La Biashara and Business Till To customer
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(1),
-                max_amount: sea_orm::ActiveValue::Set(100),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(0)), // Free
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(101),
-                max_amount: sea_orm::ActiveValue::Set(500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(7)), // 7.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(501),
-                max_amount: sea_orm::ActiveValue::Set(1000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(13)), // 13.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(1001),
-                max_amount: sea_orm::ActiveValue::Set(1500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(23)), // 23.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(1501),
-                max_amount: sea_orm::ActiveValue::Set(2500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(33)), // 33.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(2501),
-                max_amount: sea_orm::ActiveValue::Set(3500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(53)), // 53.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(3501),
-                max_amount: sea_orm::ActiveValue::Set(5000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(57)), // 57.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(5001),
-                max_amount: sea_orm::ActiveValue::Set(7500),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(78)), // 78.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(7501),
-                max_amount: sea_orm::ActiveValue::Set(10000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(90)), // 90.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(10001),
-                max_amount: sea_orm::ActiveValue::Set(15000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(100)), // 100.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(15001),
-                max_amount: sea_orm::ActiveValue::Set(20000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(105)), // 105.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(20001),
-                max_amount: sea_orm::ActiveValue::Set(35000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(108)), // 108.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(35001),
-                max_amount: sea_orm::ActiveValue::Set(50000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(108)), // 108.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::SendMoney.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(50001),
-                max_amount: sea_orm::ActiveValue::Set(150000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(108)), // 108.00 KES
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-            // Deposit (usually free)
-            TransactionCostActiveModel {
-                transaction_type: sea_orm::ActiveValue::Set(TransactionType::Deposit.to_string()),
-                min_amount: sea_orm::ActiveValue::Set(1),
-                max_amount: sea_orm::ActiveValue::Set(150000),
-                fee_fixed: sea_orm::ActiveValue::Set(Some(0)), // KES 0.00
-                fee_percentage: sea_orm::ActiveValue::Set(None),
-                ..Default::default()
-            },
-        ];
+        let bands = schedule::parse_csv(include_str!("default_tariff.csv"))
+            .map_err(|err| DbErr::Custom(format!("Invalid default tariff: {err:#}")))?;
 
-        for cost in default_costs {
-            TransactionCostActiveModel::insert(cost, db).await?;
+        for band in bands {
+            band.into_active_model(None).insert(db).await?;
         }
     }
     Ok(())
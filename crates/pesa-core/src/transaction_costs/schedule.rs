@@ -0,0 +1,394 @@
+//! Tariff schedules: whole tariff tables moved in and out as CSV or JSON, and versioned by the
+//! date they take effect.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{AppliedFee, FeePayer, db, resolve_fee, ui::TransactionCostData};
+use crate::transactions::TransactionType;
+
+const CSV_HEADER: &str =
+    "transaction_type,min_amount,max_amount,fee_fixed,fee_percentage,business_id,paid_by";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TariffFormat {
+    Csv,
+    Json,
+}
+
+/// A complete set of tariff bands taking effect together.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TariffSchedule {
+    /// When the schedule takes effect. `None` is the base schedule, in effect from the start.
+    #[serde(default)]
+    pub effective_from: Option<DateTime<Utc>>,
+    pub bands: Vec<TransactionCostData>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TariffVersion {
+    pub effective_from: Option<DateTime<Utc>>,
+    pub bands: usize,
+}
+
+/// The fee of one probe amount under the current tariff and under a candidate schedule.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TariffPreview {
+    pub transaction_type: String,
+    pub business_id: Option<u32>,
+    /// Probed amount in cents.
+    pub amount: i64,
+    pub current: AppliedFee,
+    pub candidate: AppliedFee,
+}
+
+impl TariffSchedule {
+    /// Reads a schedule. CSV only carries bands, `effective_from` then dates the schedule.
+    pub fn parse(
+        format: TariffFormat,
+        content: &str,
+        effective_from: Option<DateTime<Utc>>,
+    ) -> Result<Self> {
+        let mut schedule = match format {
+            TariffFormat::Csv => TariffSchedule {
+                effective_from: None,
+                bands: parse_csv(content)?,
+            },
+            TariffFormat::Json => {
+                serde_json::from_str(content).context("Invalid tariff schedule JSON")?
+            }
+        };
+
+        if effective_from.is_some() {
+            schedule.effective_from = effective_from;
+        }
+        validate(&schedule.bands)?;
+
+        Ok(schedule)
+    }
+
+    pub fn render(&self, format: TariffFormat) -> Result<String> {
+        match format {
+            TariffFormat::Csv => Ok(to_csv(&self.bands)),
+            TariffFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    /// Stores the schedule, replacing the bands previously imported with the same date for the
+    /// products and scopes it covers. Other products and business overrides are kept.
+    pub async fn import<C>(&self, conn: &C) -> Result<()>
+    where
+        C: ConnectionTrait,
+    {
+        validate(&self.bands)?;
+
+        let scopes: BTreeSet<_> = self
+            .bands
+            .iter()
+            .map(|band| (band.transaction_type.as_str(), band.business_id))
+            .collect();
+        let mut replaced = Condition::any();
+        for (product, business_id) in scopes {
+            replaced = replaced.add(
+                Condition::all()
+                    .add(db::Column::TransactionType.eq(product))
+                    .add(match business_id {
+                        Some(business_id) => db::Column::BusinessId.eq(business_id),
+                        None => db::Column::BusinessId.is_null(),
+                    }),
+            );
+        }
+        if !replaced.is_empty() {
+            db::Entity::delete_many()
+                .filter(match self.effective_from {
+                    Some(effective_from) => db::Column::EffectiveFrom.eq(effective_from),
+                    None => db::Column::EffectiveFrom.is_null(),
+                })
+                .filter(replaced)
+                .exec(conn)
+                .await?;
+        }
+
+        for band in &self.bands {
+            band.clone()
+                .into_active_model(self.effective_from)
+                .insert(conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// The bands a transaction made at `at` would be charged by.
+    pub async fn in_effect<C>(conn: &C, at: DateTime<Utc>) -> Result<Self>
+    where
+        C: ConnectionTrait,
+    {
+        let rows = db::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(db::Column::EffectiveFrom.is_null())
+                    .add(db::Column::EffectiveFrom.lte(at)),
+            )
+            .order_by_asc(db::Column::TransactionType)
+            .order_by_asc(db::Column::MinAmount)
+            .all(conn)
+            .await?;
+
+        // only the latest version of every product and scope applies
+        let mut latest = BTreeMap::new();
+        for row in &rows {
+            let version = latest
+                .entry((row.transaction_type.clone(), row.business_id))
+                .or_insert(row.effective_from);
+            if row.effective_from > *version {
+                *version = row.effective_from;
+            }
+        }
+
+        let bands = rows
+            .into_iter()
+            .filter(|row| {
+                latest.get(&(row.transaction_type.clone(), row.business_id))
+                    == Some(&row.effective_from)
+            })
+            .map(TransactionCostData::from)
+            .collect();
+
+        Ok(TariffSchedule {
+            effective_from: None,
+            bands,
+        })
+    }
+
+    pub async fn versions<C>(conn: &C) -> Result<Vec<TariffVersion>>
+    where
+        C: ConnectionTrait,
+    {
+        let rows = db::Entity::find()
+            .order_by_asc(db::Column::EffectiveFrom)
+            .all(conn)
+            .await?;
+
+        let mut versions: Vec<TariffVersion> = Vec::new();
+        for row in rows {
+            match versions.last_mut() {
+                Some(version) if version.effective_from == row.effective_from => version.bands += 1,
+                _ => versions.push(TariffVersion {
+                    effective_from: row.effective_from,
+                    bands: 1,
+                }),
+            }
+        }
+
+        Ok(versions)
+    }
+
+    /// Compares the fees at the edges of every band of this schedule against the current
+    /// tariff. The schedule is imported in a transaction that is always rolled back.
+    pub async fn preview<C>(&self, conn: &C) -> Result<Vec<TariffPreview>>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let at = self.effective_from.unwrap_or_else(Utc::now);
+        let probes = self
+            .bands
+            .iter()
+            .flat_map(|band| {
+                [band.min_amount, band.max_amount]
+                    .map(|kes| (band.transaction_type.clone(), band.business_id, kes * 100))
+            })
+            .collect::<Vec<_>>();
+
+        let txn = conn.begin().await?;
+
+        let mut current = Vec::with_capacity(probes.len());
+        for (product, business_id, amount) in &probes {
+            let product = TransactionType::from_str(product)?;
+            current.push(resolve_fee(&txn, &product, *amount, *business_id, at).await?);
+        }
+
+        self.import(&txn).await?;
+
+        let mut previews = Vec::with_capacity(probes.len());
+        for ((product, business_id, amount), current) in probes.into_iter().zip(current) {
+            let candidate = resolve_fee(
+                &txn,
+                &TransactionType::from_str(&product)?,
+                amount,
+                business_id,
+                at,
+            )
+            .await?;
+
+            previews.push(TariffPreview {
+                transaction_type: product,
+                business_id,
+                amount,
+                current,
+                candidate,
+            });
+        }
+
+        txn.rollback().await?;
+
+        Ok(previews)
+    }
+}
+
+/// Checks every band names a known product and that bands of one product and scope don't
+/// overlap.
+pub fn validate(bands: &[TransactionCostData]) -> Result<()> {
+    let mut scopes: BTreeMap<(&str, Option<u32>), Vec<&TransactionCostData>> = BTreeMap::new();
+
+    for band in bands {
+        if matches!(
+            TransactionType::from_str(&band.transaction_type),
+            Err(_) | Ok(TransactionType::Unknown(_))
+        ) {
+            bail!("Unknown transaction type `{}`", band.transaction_type);
+        }
+        if band.min_amount < 0 || band.min_amount > band.max_amount {
+            bail!(
+                "Invalid {} band {}-{}",
+                band.transaction_type,
+                band.min_amount,
+                band.max_amount
+            );
+        }
+
+        scopes
+            .entry((&band.transaction_type, band.business_id))
+            .or_default()
+            .push(band);
+    }
+
+    for ((product, _), mut bands) in scopes {
+        bands.sort_by_key(|band| band.min_amount);
+        for pair in bands.windows(2) {
+            if pair[1].min_amount <= pair[0].max_amount {
+                bail!(
+                    "Overlapping {product} bands {}-{} and {}-{}",
+                    pair[0].min_amount,
+                    pair[0].max_amount,
+                    pair[1].min_amount,
+                    pair[1].max_amount
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses tariff bands from CSV. Lines starting with `#` are comments and the header decides
+/// the column order. Empty optional cells are left unset.
+pub fn parse_csv(content: &str) -> Result<Vec<TransactionCostData>> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (_, header) = lines.next().context("Tariff CSV is empty")?;
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    for required in ["transaction_type", "min_amount", "max_amount"] {
+        if !columns.contains(&required) {
+            bail!("Tariff CSV is missing the `{required}` column");
+        }
+    }
+    if let Some(unknown) = columns
+        .iter()
+        .find(|column| !CSV_HEADER.split(',').any(|known| known == **column))
+    {
+        bail!("Unknown tariff CSV column `{unknown}`");
+    }
+
+    let mut bands = Vec::new();
+    for (line_number, line) in lines {
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        if cells.len() != columns.len() {
+            bail!(
+                "Line {line_number}: expected {} cells, found {}",
+                columns.len(),
+                cells.len()
+            );
+        }
+
+        let cell = |name: &str| {
+            columns
+                .iter()
+                .position(|column| *column == name)
+                .map(|index| cells[index])
+                .filter(|value| !value.is_empty())
+        };
+        let number = |name: &str| -> Result<Option<i64>> {
+            cell(name)
+                .map(|value| value.parse::<i64>())
+                .transpose()
+                .with_context(|| format!("Line {line_number}: invalid {name}"))
+        };
+
+        bands.push(TransactionCostData {
+            transaction_type: cell("transaction_type")
+                .with_context(|| format!("Line {line_number}: missing transaction_type"))?
+                .to_string(),
+            min_amount: number("min_amount")?
+                .with_context(|| format!("Line {line_number}: missing min_amount"))?,
+            max_amount: number("max_amount")?
+                .with_context(|| format!("Line {line_number}: missing max_amount"))?,
+            fee_fixed: number("fee_fixed")?,
+            fee_percentage: cell("fee_percentage")
+                .map(|value| value.parse::<f64>())
+                .transpose()
+                .with_context(|| format!("Line {line_number}: invalid fee_percentage"))?,
+            business_id: number("business_id")?
+                .map(u32::try_from)
+                .transpose()
+                .with_context(|| format!("Line {line_number}: invalid business_id"))?,
+            paid_by: match cell("paid_by") {
+                None | Some("customer") => FeePayer::Customer,
+                Some("business") => FeePayer::Business,
+                Some(other) => {
+                    return Err(anyhow!("Line {line_number}: unknown payer `{other}`"));
+                }
+            },
+        });
+    }
+
+    Ok(bands)
+}
+
+pub fn to_csv(bands: &[TransactionCostData]) -> String {
+    let optional = |value: Option<String>| value.unwrap_or_default();
+
+    let mut csv = format!("{CSV_HEADER}\n");
+    for band in bands {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            band.transaction_type,
+            band.min_amount,
+            band.max_amount,
+            optional(band.fee_fixed.map(|fee| fee.to_string())),
+            optional(band.fee_percentage.map(|fee| fee.to_string())),
+            optional(band.business_id.map(|id| id.to_string())),
+            match band.paid_by {
+                FeePayer::Customer => "customer",
+                FeePayer::Business => "business",
+            }
+        ));
+    }
+
+    csv
+}
@@ -1,33 +1,110 @@
 use super::{
+    AppliedFee, FeePayer,
     db::{self, Entity, Model},
-    get_fee,
+    resolve_fee,
+    schedule::{self, TariffFormat, TariffPreview, TariffSchedule, TariffVersion},
 };
 use anyhow::{Context, Result, bail};
-use sea_orm::entity::*;
+use chrono::{DateTime, Utc};
+use sea_orm::{QueryFilter, TransactionTrait, entity::*};
 use serde::{Deserialize, Serialize};
 
 use crate::{AppContext, transactions::TransactionType};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TransactionCostData {
     pub transaction_type: String,
     pub min_amount: i64,
     pub max_amount: i64,
     pub fee_fixed: Option<i64>,
     pub fee_percentage: Option<f64>,
+    /// Scope the band to one business, leave empty for the global tariff.
+    #[serde(default)]
+    pub business_id: Option<u32>,
+    #[serde(default)]
+    pub paid_by: FeePayer,
+}
+
+impl TransactionCostData {
+    pub(crate) fn into_active_model(
+        self,
+        effective_from: Option<DateTime<Utc>>,
+    ) -> db::ActiveModel {
+        db::ActiveModel {
+            transaction_type: Set(self.transaction_type),
+            min_amount: Set(self.min_amount),
+            max_amount: Set(self.max_amount),
+            fee_fixed: Set(self.fee_fixed),
+            fee_percentage: Set(self.fee_percentage),
+            business_id: Set(self.business_id),
+            paid_by: Set(self.paid_by),
+            effective_from: Set(effective_from),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Model> for TransactionCostData {
+    fn from(value: Model) -> Self {
+        Self {
+            transaction_type: value.transaction_type,
+            min_amount: value.min_amount,
+            max_amount: value.max_amount,
+            fee_fixed: value.fee_fixed,
+            fee_percentage: value.fee_percentage,
+            business_id: value.business_id,
+            paid_by: value.paid_by,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TariffImport {
+    pub format: TariffFormat,
+    pub content: String,
+    /// Dates the schedule, overriding the date in a JSON document.
+    #[serde(default)]
+    pub effective_from: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TariffExport {
+    pub format: TariffFormat,
+    /// Export the tariff in effect at this time instead of now.
+    #[serde(default)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+/// Checks `band` against the other bands of the tariff version it goes into, leaving out the
+/// band `replacing` when it is an update.
+async fn validate_band(
+    ctx: &AppContext,
+    band: &TransactionCostData,
+    effective_from: Option<DateTime<Utc>>,
+    replacing: Option<i32>,
+) -> Result<()> {
+    let mut query = Entity::find().filter(match effective_from {
+        Some(effective_from) => db::Column::EffectiveFrom.eq(effective_from),
+        None => db::Column::EffectiveFrom.is_null(),
+    });
+    if let Some(id) = replacing {
+        query = query.filter(db::Column::Id.ne(id));
+    }
+
+    let mut bands: Vec<TransactionCostData> = query
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(TransactionCostData::from)
+        .collect();
+    bands.push(band.clone());
+    schedule::validate(&bands)
 }
 
 pub async fn create_transaction_cost(ctx: &AppContext, data: TransactionCostData) -> Result<Model> {
     let db = &ctx.db;
-    let new_cost = db::ActiveModel {
-        transaction_type: Set(data.transaction_type),
-        min_amount: Set(data.min_amount),
-        max_amount: Set(data.max_amount),
-        fee_fixed: Set(data.fee_fixed),
-        fee_percentage: Set(data.fee_percentage),
-        ..Default::default()
-    };
-    Ok(new_cost.insert(db).await?)
+    validate_band(ctx, &data, None, None).await?;
+    Ok(data.into_active_model(None).insert(db).await?)
 }
 
 pub async fn list_transaction_costs(ctx: &AppContext) -> Result<Vec<Model>> {
@@ -43,12 +120,15 @@ pub async fn update_transaction_cost(
     let db = &ctx.db;
     let cost = Entity::find_by_id(id).one(db).await?;
     if let Some(cost) = cost {
+        validate_band(ctx, &data, cost.effective_from, Some(id)).await?;
         let mut cost: db::ActiveModel = cost.into();
         cost.transaction_type = Set(data.transaction_type);
         cost.min_amount = Set(data.min_amount);
         cost.max_amount = Set(data.max_amount);
         cost.fee_fixed = Set(data.fee_fixed);
         cost.fee_percentage = Set(data.fee_percentage);
+        cost.business_id = Set(data.business_id);
+        cost.paid_by = Set(data.paid_by);
         Ok(cost.update(db).await?)
     } else {
         bail!("Transaction cost not found")
@@ -70,8 +150,52 @@ pub async fn calculate_transaction_fee(
     ctx: &AppContext,
     txn_type: TransactionType,
     amount: i64,
-) -> Result<i64> {
-    get_fee(&ctx.db, &txn_type, amount)
+    business_id: Option<u32>,
+) -> Result<AppliedFee> {
+    resolve_fee(&ctx.db, &txn_type, amount, business_id, Utc::now())
         .await
         .context("Failed to calculate transaction cost")
 }
+
+pub async fn import_tariff_schedule(
+    ctx: &AppContext,
+    input: TariffImport,
+) -> Result<TariffSchedule> {
+    let schedule = TariffSchedule::parse(input.format, &input.content, input.effective_from)?;
+
+    let txn = ctx.db.begin().await?;
+    schedule
+        .import(&txn)
+        .await
+        .context("Failed to import tariff schedule")?;
+    txn.commit().await?;
+
+    Ok(schedule)
+}
+
+pub async fn export_tariff_schedule(ctx: &AppContext, input: TariffExport) -> Result<String> {
+    let mut schedule = TariffSchedule::in_effect(&ctx.db, input.at.unwrap_or_else(Utc::now))
+        .await
+        .context("Failed to load tariff schedule")?;
+    schedule.effective_from = input.at;
+
+    schedule.render(input.format)
+}
+
+pub async fn preview_tariff_schedule(
+    ctx: &AppContext,
+    input: TariffImport,
+) -> Result<Vec<TariffPreview>> {
+    let schedule = TariffSchedule::parse(input.format, &input.content, input.effective_from)?;
+
+    schedule
+        .preview(&ctx.db)
+        .await
+        .context("Failed to preview tariff schedule")
+}
+
+pub async fn list_tariff_versions(ctx: &AppContext) -> Result<Vec<TariffVersion>> {
+    TariffSchedule::versions(&ctx.db)
+        .await
+        .context("Failed to list tariff versions")
+}
@@ -19,6 +19,9 @@ pub struct Model {
     pub reversal_of: Option<String>,
     pub created_at: DateTimeUtc,
     pub updated_at: Option<DateTimeUtc>,
+    /// The API conversation that posted the transaction, if any.
+    #[sea_orm(indexed)]
+    pub conversation_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -53,6 +56,7 @@ impl From<Model> for Transaction {
             created_at: value.created_at,
             updated_at: value.updated_at,
             notes,
+            conversation_id: value.conversation_id,
         }
     }
 }
@@ -4,18 +4,22 @@ use chrono::{DateTime, Utc};
 use once_cell::sync::Lazy;
 use rand::{Rng, distributions::Alphanumeric};
 use sea_orm::{
-    ActiveModelTrait,
-    ActiveValue::{Set, Unchanged},
-    ConnectionTrait, DbErr, EntityTrait,
-    prelude::DateTimeUtc,
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
+    QueryFilter, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString};
 use thiserror::Error;
 use tokio::sync::Mutex;
 
+use crate::business::Business;
+use crate::journal::{EntrySide, Journal, JournalLine};
+use crate::transaction_costs::{FeePayer, resolve_fee};
 use crate::transactions_log::{TransactionLog, db::Direction};
-use crate::{accounts::Account, server::api::b2c};
+use crate::{
+    accounts::{Account, SYSTEM_ACCOUNT_ID},
+    server::api::b2c,
+};
 use serde_json;
 
 pub mod db;
@@ -37,6 +41,18 @@ pub enum TransactionEngineError {
 
     #[error("Transaction not found")]
     TransactionNotFound,
+
+    #[error("Transaction has already been reversed")]
+    AlreadyReversed,
+
+    #[error("Transaction cannot be reversed: {0}")]
+    NotReversible(String),
+
+    #[error("Invalid reversal amount {amount}, {remaining} can still be reversed")]
+    InvalidReversalAmount { amount: i64, remaining: i64 },
+
+    #[error("Unbalanced journal entry: debits {debits} != credits {credits}")]
+    UnbalancedEntry { debits: i64, credits: i64 },
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -62,6 +78,43 @@ pub enum TransactionNote {
     Disbursment {
         kind: b2c::CommandID,
     },
+    Reversal {
+        fee_refund: i64,
+    },
+}
+
+/// What happens to the fee charged on a transaction when it is reversed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeRefundPolicy {
+    /// The fee is kept by the system.
+    #[default]
+    Keep,
+    /// The fee is refunded to the sender in proportion to the amount reversed.
+    Proportional,
+}
+
+/// What happens when the recipient no longer has the funds being reversed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsufficientFundsPolicy {
+    /// The reversal is refused.
+    #[default]
+    Reject,
+    /// The reversal goes through and the recipient's balance goes negative.
+    AllowNegative,
+    /// The reversal is recorded as pending, see [`Ledger::complete_reversal`] and
+    /// [`Ledger::cancel_reversal`].
+    Pending,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReversalOptions {
+    /// Amount to reverse in cents. Defaults to everything not reversed yet.
+    pub amount: Option<i64>,
+    pub fee_refund: FeeRefundPolicy,
+    pub insufficient_funds: InsufficientFundsPolicy,
 }
 
 static GLOBAL_LEDGER_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
@@ -81,6 +134,7 @@ pub enum TransactionType {
     RevenueSweep,
     TopupUtility,
     Disbursment,
+    OpeningBalance,
     Unknown(String),
 }
 
@@ -92,6 +146,8 @@ pub enum TransactionStatus {
     Failed,
     Completed,
     Reversed,
+    PartiallyReversed,
+    PendingReversal,
     Unknown(String),
 }
 
@@ -109,11 +165,17 @@ pub struct Transaction {
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub notes: Option<TransactionNote>,
+    pub conversation_id: Option<String>,
 }
 
 pub struct Ledger {}
 
 impl Ledger {
+    /// Moves `amount` from `source` to `destination`, charging the tariff fee to the source.
+    ///
+    /// Every write happens inside a single database transaction (a savepoint when `conn` is
+    /// already a transaction), so a failure part way leaves no trace. The returned events must
+    /// only be dispatched once the caller's outermost transaction has committed.
     pub async fn transfer<C>(
         conn: &C,
         source: Option<u32>,
@@ -123,12 +185,114 @@ impl Ledger {
         notes: Option<&TransactionNote>,
     ) -> Result<(Transaction, Vec<crate::events::DomainEvent>), TransactionEngineError>
     where
-        C: ConnectionTrait,
+        C: ConnectionTrait + TransactionTrait,
+    {
+        Self::transfer_in_conversation(conn, None, source, destination, amount, txn_type, notes)
+            .await
+    }
+
+    /// [`Ledger::transfer`] on behalf of an API request, the transaction keeping the request's
+    /// `conversation_id` so it shows up on the request's timeline.
+    pub async fn transfer_in_conversation<C>(
+        conn: &C,
+        conversation_id: Option<&str>,
+        source: Option<u32>,
+        destination: u32,
+        amount: i64,
+        txn_type: &TransactionType,
+        notes: Option<&TransactionNote>,
+    ) -> Result<(Transaction, Vec<crate::events::DomainEvent>), TransactionEngineError>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let _guard = GLOBAL_LEDGER_LOCK.lock().await;
+        let txn = conn.begin().await?;
+        let result = Self::apply_transfer(
+            &txn,
+            conversation_id,
+            source,
+            destination,
+            amount,
+            txn_type,
+            notes,
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(result)
+    }
+
+    /// Reverses the transaction `id`, moving the amount back from its destination.
+    ///
+    /// Returns the new reversal transaction, linked to the original through `reversal_of`. When
+    /// the recipient has already spent the money, `options` decide whether the reversal is
+    /// refused, drives their balance negative or is left pending. Atomic in the same way as
+    /// [`Ledger::transfer`].
+    pub async fn reverse<C>(
+        conn: &C,
+        id: &str,
+        options: &ReversalOptions,
+    ) -> Result<(Transaction, Vec<crate::events::DomainEvent>), TransactionEngineError>
+    where
+        C: ConnectionTrait + TransactionTrait,
     {
         let _guard = GLOBAL_LEDGER_LOCK.lock().await;
+        let txn = conn.begin().await?;
+        let result = Self::apply_reverse(&txn, id, options).await?;
+        txn.commit().await?;
+
+        Ok(result)
+    }
+
+    /// Completes the pending reversal `id` once the recipient has the funds for it.
+    pub async fn complete_reversal<C>(
+        conn: &C,
+        id: &str,
+    ) -> Result<(Transaction, Vec<crate::events::DomainEvent>), TransactionEngineError>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let _guard = GLOBAL_LEDGER_LOCK.lock().await;
+        let txn = conn.begin().await?;
+        let result = Self::apply_complete_reversal(&txn, id).await?;
+        txn.commit().await?;
+
+        Ok(result)
+    }
+
+    /// Cancels the pending reversal `id`. No money moves, the reversal is marked failed and the
+    /// original transaction can be reversed again.
+    pub async fn cancel_reversal<C>(
+        conn: &C,
+        id: &str,
+    ) -> Result<Transaction, TransactionEngineError>
+    where
+        C: ConnectionTrait + TransactionTrait,
+    {
+        let _guard = GLOBAL_LEDGER_LOCK.lock().await;
+        let txn = conn.begin().await?;
+        let result = Self::apply_cancel_reversal(&txn, id).await?;
+        txn.commit().await?;
+
+        Ok(result)
+    }
+
+    async fn apply_transfer<C>(
+        conn: &C,
+        conversation_id: Option<&str>,
+        source: Option<u32>,
+        destination: u32,
+        amount: i64,
+        txn_type: &TransactionType,
+        notes: Option<&TransactionNote>,
+    ) -> Result<(Transaction, Vec<crate::events::DomainEvent>), TransactionEngineError>
+    where
+        C: ConnectionTrait,
+    {
         let mut events = Vec::new();
+        let created_at = Utc::now().to_utc();
 
-        let mut source_account = if let Some(source) = source {
+        let source_account = if let Some(source) = source {
             let source_account = Account::get_account(conn, source).await?;
             if let Some(account) = source_account {
                 if matches!(account.account_type, crate::accounts::AccountType::System) {
@@ -149,44 +313,57 @@ impl Ledger {
             return Err(TransactionEngineError::AccountNotFound(destination));
         }
 
-        let mut destination_account = destination_account.unwrap();
+        let destination_account = destination_account.unwrap();
 
-        let fee = crate::transaction_costs::get_fee(conn, txn_type, amount).await?;
+        // Tariffs are scoped to the business being paid, or failing that the one paying.
+        let business_id = match Business::find_by_account(conn, destination_account.id).await? {
+            Some(business_id) => Some(business_id),
+            None => match &source_account {
+                Some(source) => Business::find_by_account(conn, source.id).await?,
+                None => None,
+            },
+        };
+        let applied = resolve_fee(conn, txn_type, amount, business_id, created_at).await?;
+
+        // Business paid fees are accrued on the business charges account and collected at
+        // revenue settlement. Otherwise only a real source pays fees.
+        let (fee, charged_business) = match (applied.paid_by, business_id, &source_account) {
+            (FeePayer::Business, Some(business_id), _) => (applied.fee, Some(business_id)),
+            (_, _, Some(_)) => (applied.fee, None),
+            _ => (0, None),
+        };
+        let source_fee = if charged_business.is_some() { 0 } else { fee };
 
         // check if source has enough funds
-        if let Some(source) = &mut source_account {
+        if let Some(source) = &source_account {
             if source.id == destination_account.id {
                 return Err(TransactionEngineError::SelfTransact);
             }
 
-            if source.balance < amount + fee {
+            if source.balance < amount + source_fee {
                 return Err(TransactionEngineError::InsufficientFunds);
             }
+        }
 
-            // fees should be added to business charges account
-            if matches!(txn_type, TransactionType::Disbursment) {
-                source.balance -= amount;
-            } else {
-                source.balance -= amount + fee;
-            }
+        // Make sure the system account exists, it backs every issuance and collects fees.
+        Account::get_account(conn, SYSTEM_ACCOUNT_ID).await?;
 
-            let acc = crate::accounts::db::ActiveModel {
-                id: Unchanged(source.id),
-                balance: Set(source.balance),
-                ..Default::default()
-            };
+        let mut lines = vec![
+            JournalLine::debit(
+                source_account
+                    .as_ref()
+                    .map(|source| source.id)
+                    .unwrap_or(SYSTEM_ACCOUNT_ID),
+                amount,
+            ),
+            JournalLine::credit(destination_account.id, amount),
+        ];
 
-            acc.update(conn).await?;
+        if let Some(source) = &source_account {
+            lines.push(JournalLine::debit(source.id, source_fee));
+            lines.push(JournalLine::credit(SYSTEM_ACCOUNT_ID, source_fee));
         }
 
-        destination_account.balance += amount;
-        let acc = crate::accounts::db::ActiveModel {
-            id: Unchanged(destination_account.id),
-            balance: Set(destination_account.balance),
-            ..Default::default()
-        };
-        acc.update(conn).await?;
-
         let notes_string = notes.map(|n| serde_json::to_string(n).unwrap_or_default());
 
         let txn = db::ActiveModel {
@@ -198,12 +375,18 @@ impl Ledger {
             currency: Set("KES".to_string()),
             transaction_type: Set(txn_type.to_string()),
             status: Set(TransactionStatus::Completed.to_string()),
-            created_at: Set(Utc::now().to_utc()),
+            created_at: Set(created_at),
             notes: Set(notes_string),
+            conversation_id: Set(conversation_id.map(str::to_string)),
             ..Default::default()
         };
 
         let txn: Transaction = txn.insert(conn).await?.into();
+        let balances = Journal::post(conn, &txn.id, &lines).await?;
+
+        if let Some(business_id) = charged_business {
+            Business::increment_charges_amount(conn, business_id, -fee).await?;
+        }
 
         if let Some(source) = &source_account {
             let (_log, event) = TransactionLog::create(
@@ -211,7 +394,7 @@ impl Ledger {
                 txn.id.clone(),
                 source.id,
                 Direction::Outflow,
-                source.balance,
+                balances.get(&source.id).copied().unwrap_or(source.balance),
             )
             .await?;
             events.push(event);
@@ -222,108 +405,316 @@ impl Ledger {
             txn.id.clone(),
             destination_account.id,
             Direction::Inflow,
-            destination_account.balance,
+            balances
+                .get(&destination_account.id)
+                .copied()
+                .unwrap_or(destination_account.balance),
         )
         .await?;
         events.push(event);
 
-        drop(_guard);
-
         Ok((txn, events))
     }
 
-    pub async fn reverse<C>(
+    async fn apply_reverse<C>(
         conn: &C,
         id: &str,
+        options: &ReversalOptions,
     ) -> Result<(Transaction, Vec<crate::events::DomainEvent>), TransactionEngineError>
     where
         C: ConnectionTrait,
     {
-        let _guard = GLOBAL_LEDGER_LOCK.lock().await;
-        let mut events = Vec::new();
+        let transaction = db::Entity::find_by_id(id)
+            .one(conn)
+            .await?
+            .ok_or(TransactionEngineError::TransactionNotFound)?;
 
-        let transaction = db::Entity::find_by_id(id).one(conn).await?;
-        if transaction.is_none() {
-            return Err(TransactionEngineError::TransactionNotFound);
+        if transaction.transaction_type == TransactionType::Reversal.to_string() {
+            return Err(TransactionEngineError::NotReversible(
+                "a reversal cannot itself be reversed".to_string(),
+            ));
         }
 
-        let transaction = transaction.unwrap();
-        let source_id = transaction.from;
-        let dest_id = transaction.to;
-        let amount = transaction.amount;
+        match transaction.status.parse() {
+            Ok(TransactionStatus::Completed | TransactionStatus::PartiallyReversed) => {}
+            Ok(TransactionStatus::Reversed) => return Err(TransactionEngineError::AlreadyReversed),
+            Ok(TransactionStatus::PendingReversal) => {
+                return Err(TransactionEngineError::NotReversible(
+                    "a reversal of this transaction is still pending".to_string(),
+                ));
+            }
+            _ => {
+                return Err(TransactionEngineError::NotReversible(format!(
+                    "transaction is {}",
+                    transaction.status
+                )));
+            }
+        }
+
+        let already_reversed = Self::reversed_amount(conn, &transaction.id).await?;
+        let remaining = transaction.amount - already_reversed;
+        let amount = options.amount.unwrap_or(remaining);
+        if amount <= 0 || amount > remaining {
+            return Err(TransactionEngineError::InvalidReversalAmount { amount, remaining });
+        }
+
+        // Only fees that actually reached the system account can be refunded. Business paid
+        // fees are settled with the business charges instead.
+        let system_credits: i64 = Journal::entries_for_transaction(conn, &transaction.id)
+            .await?
+            .iter()
+            .filter(|entry| {
+                entry.account_id == SYSTEM_ACCOUNT_ID && entry.side == EntrySide::Credit
+            })
+            .map(|entry| entry.amount)
+            .sum();
+        let collected_fee = if transaction.to == SYSTEM_ACCOUNT_ID {
+            system_credits - transaction.amount
+        } else {
+            system_credits
+        };
+        let fee_refund = match options.fee_refund {
+            FeeRefundPolicy::Keep => 0,
+            // refund the share of the fee the reversed amounts have covered so far, so that
+            // several partial reversals add up to the whole fee
+            FeeRefundPolicy::Proportional => {
+                collected_fee * (already_reversed + amount) / transaction.amount
+                    - collected_fee * already_reversed / transaction.amount
+            }
+        };
 
-        // look through the accounts and restore balances
-        if let Some(dest) = crate::accounts::db::Entity::find_by_id(dest_id)
+        let dest = crate::accounts::db::Entity::find_by_id(transaction.to)
             .one(conn)
             .await?
-        {
-            if dest.balance < amount {
-                // we are trying to reverse but target has insufficient funds to do so
-                // TODO implement correct real world logic for this scenario
-                return Err(TransactionEngineError::InsufficientFunds);
+            .ok_or(TransactionEngineError::AccountNotFound(transaction.to))?;
+
+        // credit back the funds to source, or to the system account if it was an issuance
+        let refund_to = transaction.from.unwrap_or(SYSTEM_ACCOUNT_ID);
+        if Account::get_account(conn, refund_to).await?.is_none() {
+            return Err(TransactionEngineError::AccountNotFound(refund_to));
+        }
+
+        let pending = dest.balance < amount
+            && match options.insufficient_funds {
+                InsufficientFundsPolicy::Reject => {
+                    return Err(TransactionEngineError::InsufficientFunds);
+                }
+                InsufficientFundsPolicy::AllowNegative => false,
+                InsufficientFundsPolicy::Pending => true,
+            };
+
+        let notes = TransactionNote::Reversal { fee_refund };
+        let reversal = db::ActiveModel {
+            id: Set(Ledger::generate_receipt()),
+            to: Set(refund_to),
+            from: Set(Some(transaction.to)),
+            amount: Set(amount),
+            fee: Set(0),
+            currency: Set(transaction.currency.clone()),
+            status: Set(if pending {
+                TransactionStatus::Pending
+            } else {
+                TransactionStatus::Completed
             }
-            let balance = dest.balance - amount;
-            let mut dest_model: crate::accounts::db::ActiveModel = dest.into();
-            dest_model.balance = Set(balance);
-            dest_model.update(conn).await?;
+            .to_string()),
+            reversal_of: Set(Some(transaction.id.clone())),
+            transaction_type: Set(TransactionType::Reversal.to_string()),
+            created_at: Set(Utc::now().to_utc()),
+            notes: Set(serde_json::to_string(&notes).ok()),
+            // a reversal belongs on the timeline of the request that made the original
+            conversation_id: Set(transaction.conversation_id.clone()),
+            ..Default::default()
+        };
+        let reversal: Transaction = reversal.insert(conn).await?.into();
 
-            let (_log, event) = TransactionLog::create(
-                conn,
-                transaction.id.clone(),
-                dest_id,
-                Direction::Outflow,
-                balance,
-            )
-            .await?;
-            events.push(event);
+        if pending {
+            Self::set_status(conn, transaction, TransactionStatus::PendingReversal).await?;
+            return Ok((reversal, Vec::new()));
+        }
+
+        let events = Self::post_reversal(conn, &reversal, fee_refund).await?;
+        let status = if amount == remaining {
+            TransactionStatus::Reversed
+        } else {
+            TransactionStatus::PartiallyReversed
+        };
+        Self::set_status(conn, transaction, status).await?;
+
+        Ok((reversal, events))
+    }
+
+    async fn apply_complete_reversal<C>(
+        conn: &C,
+        id: &str,
+    ) -> Result<(Transaction, Vec<crate::events::DomainEvent>), TransactionEngineError>
+    where
+        C: ConnectionTrait,
+    {
+        let reversal = db::Entity::find_by_id(id)
+            .one(conn)
+            .await?
+            .ok_or(TransactionEngineError::TransactionNotFound)?;
+
+        if reversal.transaction_type != TransactionType::Reversal.to_string()
+            || reversal.status != TransactionStatus::Pending.to_string()
+        {
+            return Err(TransactionEngineError::NotReversible(
+                "only pending reversals can be completed".to_string(),
+            ));
+        }
+
+        let original = db::Entity::find_by_id(reversal.reversal_of.clone().unwrap_or_default())
+            .one(conn)
+            .await?
+            .ok_or(TransactionEngineError::TransactionNotFound)?;
+
+        let dest = Account::get_account(conn, original.to)
+            .await?
+            .ok_or(TransactionEngineError::AccountNotFound(original.to))?;
+        if dest.balance < reversal.amount {
+            return Err(TransactionEngineError::InsufficientFunds);
+        }
+
+        let mut completed: db::ActiveModel = reversal.into();
+        completed.status = Set(TransactionStatus::Completed.to_string());
+        completed.updated_at = Set(Some(Utc::now().to_utc()));
+        let reversal: Transaction = completed.update(conn).await?.into();
+
+        let fee_refund = match &reversal.notes {
+            Some(TransactionNote::Reversal { fee_refund }) => *fee_refund,
+            _ => 0,
+        };
+        let events = Self::post_reversal(conn, &reversal, fee_refund).await?;
+
+        let status = if Self::reversed_amount(conn, &original.id).await? >= original.amount {
+            TransactionStatus::Reversed
+        } else {
+            TransactionStatus::PartiallyReversed
+        };
+        Self::set_status(conn, original, status).await?;
+
+        Ok((reversal, events))
+    }
+
+    async fn apply_cancel_reversal<C>(
+        conn: &C,
+        id: &str,
+    ) -> Result<Transaction, TransactionEngineError>
+    where
+        C: ConnectionTrait,
+    {
+        let reversal = db::Entity::find_by_id(id)
+            .one(conn)
+            .await?
+            .ok_or(TransactionEngineError::TransactionNotFound)?;
+
+        if reversal.transaction_type != TransactionType::Reversal.to_string()
+            || reversal.status != TransactionStatus::Pending.to_string()
+        {
+            return Err(TransactionEngineError::NotReversible(
+                "only pending reversals can be cancelled".to_string(),
+            ));
+        }
+
+        let original = db::Entity::find_by_id(reversal.reversal_of.clone().unwrap_or_default())
+            .one(conn)
+            .await?
+            .ok_or(TransactionEngineError::TransactionNotFound)?;
+
+        let mut cancelled: db::ActiveModel = reversal.into();
+        cancelled.status = Set(TransactionStatus::Failed.to_string());
+        cancelled.updated_at = Set(Some(Utc::now().to_utc()));
+        let reversal: Transaction = cancelled.update(conn).await?.into();
+
+        // earlier reversals may have gone through
+        let status = if Self::reversed_amount(conn, &original.id).await? > 0 {
+            TransactionStatus::PartiallyReversed
         } else {
-            return Err(TransactionEngineError::AccountNotFound(dest_id));
+            TransactionStatus::Completed
+        };
+        Self::set_status(conn, original, status).await?;
+
+        Ok(reversal)
+    }
+
+    /// Moves the funds of a reversal back and writes the transaction logs for it.
+    async fn post_reversal<C>(
+        conn: &C,
+        reversal: &Transaction,
+        fee_refund: i64,
+    ) -> Result<Vec<crate::events::DomainEvent>, TransactionEngineError>
+    where
+        C: ConnectionTrait,
+    {
+        let mut events = Vec::new();
+        let recipient = reversal.from.unwrap_or(SYSTEM_ACCOUNT_ID);
+
+        let mut lines = vec![
+            JournalLine::debit(recipient, reversal.amount),
+            JournalLine::credit(reversal.to, reversal.amount),
+        ];
+        if fee_refund > 0 {
+            lines.push(JournalLine::debit(SYSTEM_ACCOUNT_ID, fee_refund));
+            lines.push(JournalLine::credit(reversal.to, fee_refund));
         }
 
-        // credit back the funds to source
-        if let Some(source_id) = source_id {
-            if let Some(source) = crate::accounts::db::Entity::find_by_id(source_id)
-                .one(conn)
-                .await?
-            {
-                let balance = source.balance + amount;
-                let mut source_model: crate::accounts::db::ActiveModel = source.into();
-                source_model.balance = Set(balance);
-                source_model.update(conn).await?;
+        let balances = Journal::post(conn, &reversal.id, &lines).await?;
+
+        for (account_id, direction) in [
+            (recipient, Direction::Outflow),
+            (reversal.to, Direction::Inflow),
+        ] {
+            if account_id == SYSTEM_ACCOUNT_ID {
+                continue;
+            }
 
+            if let Some(balance) = balances.get(&account_id) {
                 let (_log, event) = TransactionLog::create(
                     conn,
-                    transaction.id.clone(),
-                    source_id,
-                    Direction::Inflow,
-                    balance,
+                    reversal.id.clone(),
+                    account_id,
+                    direction,
+                    *balance,
                 )
                 .await?;
                 events.push(event);
-            } else {
-                return Err(TransactionEngineError::AccountNotFound(source_id));
             }
         }
 
+        Ok(events)
+    }
+
+    /// Total amount already reversed, or pending reversal, against `transaction_id`.
+    async fn reversed_amount<C>(conn: &C, transaction_id: &str) -> Result<i64, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let reversals = db::Entity::find()
+            .filter(db::Column::ReversalOf.eq(transaction_id))
+            .filter(db::Column::Status.is_in([
+                TransactionStatus::Completed.to_string(),
+                TransactionStatus::Pending.to_string(),
+            ]))
+            .all(conn)
+            .await?;
+
+        Ok(reversals.iter().map(|reversal| reversal.amount).sum())
+    }
+
+    async fn set_status<C>(
+        conn: &C,
+        transaction: db::Model,
+        status: TransactionStatus,
+    ) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+    {
         let mut txn: db::ActiveModel = transaction.into();
-        txn.status = Set(TransactionStatus::Reversed.to_string());
-        txn.updated_at = Set(Some(DateTimeUtc::UNIX_EPOCH));
+        txn.status = Set(status.to_string());
+        txn.updated_at = Set(Some(Utc::now().to_utc()));
         txn.update(conn).await?;
 
-        let txn = db::ActiveModel {
-            id: Set(Ledger::generate_receipt()),
-            to: Set(source_id.unwrap_or(dest_id)),
-            from: Set(Some(dest_id)),
-            amount: Set(amount),
-            currency: Set("KES".to_string()),
-            transaction_type: Set(TransactionType::Reversal.to_string()),
-            status: Set(TransactionStatus::Completed.to_string()),
-            ..Default::default()
-        };
-        let txn: Transaction = txn.insert(conn).await?.into();
-
-        drop(_guard);
-        Ok((txn, events))
+        Ok(())
     }
 
     pub fn generate_receipt() -> String {
@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use super::Ledger;
+use super::ReversalOptions;
 use super::Transaction;
 use super::TransactionEngineError;
 use super::TransactionNote;
@@ -16,6 +17,7 @@ use sea_orm::EntityTrait;
 use sea_orm::PaginatorTrait;
 use sea_orm::QueryFilter;
 use sea_orm::QuerySelect;
+use sea_orm::TransactionTrait;
 use sea_query::ExprTrait;
 use serde::{Deserialize, Serialize};
 
@@ -34,7 +36,7 @@ use crate::server::api::c2b::C2bTransactionType;
 use crate::server::api::c2b::ResponseType;
 use crate::server::api::c2b::ValidationRequest;
 use crate::server::api::c2b::ValidationResponse;
-use crate::transaction_costs::get_fee;
+use crate::transaction_costs::{FeePayer, resolve_fee};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct TransactionFilter {
@@ -318,16 +320,36 @@ pub async fn transfer(
     Ok(txn)
 }
 
-pub async fn reverse(ctx: &AppContext, id: String) -> Result<Transaction> {
-    let (txn, events) = Ledger::reverse(&ctx.db, &id)
+pub async fn reverse(
+    ctx: &AppContext,
+    id: String,
+    options: Option<ReversalOptions>,
+) -> Result<Transaction> {
+    let (txn, events) = Ledger::reverse(&ctx.db, &id, &options.unwrap_or_default())
         .await
-        .context("Transfer Error")?;
+        .context("Reversal Error")?;
 
     DomainEventDispatcher::dispatch_events(ctx, events)?;
 
     Ok(txn)
 }
 
+pub async fn complete_reversal(ctx: &AppContext, id: String) -> Result<Transaction> {
+    let (txn, events) = Ledger::complete_reversal(&ctx.db, &id)
+        .await
+        .context("Reversal Error")?;
+
+    DomainEventDispatcher::dispatch_events(ctx, events)?;
+
+    Ok(txn)
+}
+
+pub async fn cancel_reversal(ctx: &AppContext, id: String) -> Result<Transaction> {
+    Ledger::cancel_reversal(&ctx.db, &id)
+        .await
+        .context("Reversal Error")
+}
+
 #[derive(serde::Serialize)]
 pub struct TransactionStats {
     pub total_count: u64,
@@ -462,18 +484,23 @@ pub async fn c2b_lipa_logic(ctx: &AppContext, args: LipaArgs) -> Result<()> {
     let source = user_account.unwrap();
 
     // pre calculate amount and balance
-    let fee = get_fee(
+    let applied = resolve_fee(
         conn,
         match args.payment_type {
             LipaPaymentType::Paybill => &TransactionType::Paybill,
             LipaPaymentType::Till => &TransactionType::BuyGoods,
         },
         args.amount,
+        Some(business_id),
+        chrono::Utc::now(),
     )
     .await
     .context("Failed to compute transaction fee")?;
 
-    let total = args.amount + fee;
+    let total = match applied.paid_by {
+        FeePayer::Customer => args.amount + applied.fee,
+        FeePayer::Business => args.amount,
+    };
     if source.balance < total {
         bail!(TransactionEngineError::InsufficientFunds);
     }
@@ -520,7 +547,11 @@ struct ProcessLipaArgs {
     business: Business,
 }
 
-async fn process_lipa<C: ConnectionTrait>(conn: C, args: ProcessLipaArgs, ctx: AppContext) {
+async fn process_lipa<C: ConnectionTrait + TransactionTrait>(
+    conn: C,
+    args: ProcessLipaArgs,
+    ctx: AppContext,
+) {
     let trasaction_id = Ledger::generate_receipt();
 
     let parts: Vec<&str> = args.user.name.split_whitespace().collect();
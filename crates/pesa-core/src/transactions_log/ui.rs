@@ -139,13 +139,7 @@ pub async fn get_transaction_history(
 
     // 1. Determine account IDs from scope
     let account_ids: Option<Vec<u32>> = match filter.scope.r#type {
-        HistoryScopeType::User => {
-            if let Some(user_id) = filter.scope.id {
-                Some(vec![user_id])
-            } else {
-                None
-            }
-        }
+        HistoryScopeType::User => filter.scope.id.map(|user_id| vec![user_id]),
         HistoryScopeType::Business => {
             if let Some(business_id) = filter.scope.id {
                 let mut ids = Vec::new();
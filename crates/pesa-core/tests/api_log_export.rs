@@ -0,0 +1,149 @@
+mod common;
+
+use axum::{body::Body, http::Request};
+use pesa_core::{
+    api_logs::{
+        ApiLog, db,
+        export::{Har, HarHeader, to_curl},
+    },
+    server::create_router,
+};
+use sea_orm::EntityTrait;
+use serde_json::json;
+use tower::ServiceExt;
+
+fn stk_log() -> ApiLog {
+    ApiLog {
+        id: "a1".to_string(),
+        project_id: 1,
+        method: "POST".to_string(),
+        path: "/mpesa/stkpush/v1/processrequest".to_string(),
+        status_code: 400,
+        request_body: Some(
+            json!({
+                "headers": {
+                    "content-type": "application/json",
+                    "content-length": "29",
+                    "authorization": "Bearer token",
+                },
+                "body": r#"{"AccountReference":"Joe's"}"#,
+            })
+            .to_string(),
+        ),
+        response_body: Some(
+            json!({
+                "headers": { "content-type": "application/json" },
+                "body": r#"{"errorCode":"400.002.02"}"#,
+            })
+            .to_string(),
+        ),
+        error_desc: Some("Invalid phone".to_string()),
+        duration: 12,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn har_carries_headers_bodies_and_timing() {
+    let har = Har::from_logs(&[stk_log()], "http://127.0.0.1:8001/");
+    let value = serde_json::to_value(&har).unwrap();
+
+    assert_eq!(value["log"]["version"], "1.2");
+    let entry = &har.log.entries[0];
+    assert_eq!(
+        entry.request.url,
+        "http://127.0.0.1:8001/mpesa/stkpush/v1/processrequest"
+    );
+    assert!(entry.request.headers.contains(&HarHeader {
+        name: "authorization".to_string(),
+        value: "Bearer token".to_string(),
+    }));
+    assert_eq!(
+        entry.request.post_data.as_ref().unwrap().text,
+        r#"{"AccountReference":"Joe's"}"#
+    );
+    assert_eq!(entry.response.status_text, "Bad Request");
+    assert_eq!(
+        entry.response.content.text.as_deref(),
+        Some(r#"{"errorCode":"400.002.02"}"#)
+    );
+    assert_eq!(entry.time, 12);
+    assert_eq!(entry.comment.as_deref(), Some("Invalid phone"));
+    assert_eq!(value["log"]["entries"][0]["response"]["redirectURL"], "");
+}
+
+#[test]
+fn callback_logs_keep_their_url_and_json_payload() {
+    let log = ApiLog {
+        path: "https://example.com/callback".to_string(),
+        request_body: Some(json!({ "headers": {}, "body": { "ResultCode": 0 } }).to_string()),
+        ..stk_log()
+    };
+
+    let har = Har::from_logs(&[log], "http://127.0.0.1:8001");
+
+    let request = &har.log.entries[0].request;
+    assert_eq!(request.url, "https://example.com/callback");
+    assert_eq!(
+        request.post_data.as_ref().unwrap().text,
+        r#"{"ResultCode":0}"#
+    );
+}
+
+#[test]
+fn curl_command_is_quoted_for_the_shell() {
+    let curl = to_curl(&stk_log(), "http://127.0.0.1:8001");
+
+    assert_eq!(
+        curl,
+        [
+            "curl -X POST 'http://127.0.0.1:8001/mpesa/stkpush/v1/processrequest'",
+            "-H 'authorization: Bearer token'",
+            "-H 'content-type: application/json'",
+            r#"--data-raw '{"AccountReference":"Joe'\''s"}'"#,
+        ]
+        .join(" \\\n  ")
+    );
+}
+
+#[tokio::test]
+async fn exports_keep_the_query_string() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600100").await?;
+    let router = create_router(ctx.clone(), project_id, true);
+
+    router
+        .oneshot(
+            Request::get("/oauth/v1/generate?grant_type=client_credentials")
+                .header("host", "127.0.0.1:8001")
+                .body(Body::empty())?,
+        )
+        .await?;
+
+    let logs: Vec<ApiLog> = db::Entity::find()
+        .all(&ctx.db)
+        .await?
+        .into_iter()
+        .map(ApiLog::from)
+        .collect();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].path, "/oauth/v1/generate");
+
+    let url = "http://127.0.0.1:8001/oauth/v1/generate?grant_type=client_credentials";
+    let request = &Har::from_logs(&logs, "http://localhost").log.entries[0].request;
+    assert_eq!(request.url, url);
+    assert_eq!(
+        request.query_string,
+        vec![HarHeader {
+            name: "grant_type".to_string(),
+            value: "client_credentials".to_string(),
+        }]
+    );
+    assert!(
+        to_curl(&logs[0], "http://localhost").starts_with(&format!("curl -X GET '{url}'")),
+        "{}",
+        to_curl(&logs[0], "http://localhost")
+    );
+    Ok(())
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use axum::{Router, extract::State, routing::post};
+use futures::future::BoxFuture;
+use pesa_core::callbacks::{
+    CallbackLog, CallbackStatus, CallbackType, CreateCallbackParams,
+    hooks::{CallbackDecision, CallbackHook, HookOutcome},
+    orchestrator::CallbackOrchestrator,
+};
+use serde_json::{Value, json};
+
+/// Rewrites, drops or garbles callbacks depending on their conversation id.
+struct ScriptedHook;
+
+impl CallbackHook for ScriptedHook {
+    fn before_dispatch(
+        &self,
+        callback: CreateCallbackParams,
+    ) -> BoxFuture<'static, anyhow::Result<HookOutcome>> {
+        Box::pin(async move {
+            let mut outcome = HookOutcome::from(callback);
+            match outcome.callback.conversation_id.as_str() {
+                "rewrite" => outcome.callback.payload["ResultCode"] = json!(1032),
+                "drop" => outcome.drop = true,
+                "raw" => outcome.raw_body = Some(r#"{"ResultCode": 0,"#.to_string()),
+                "delay" => outcome.delay_ms = Some(10),
+                "delay_rewrite" => {
+                    outcome.delay_ms = Some(10);
+                    outcome.callback.payload["ResultCode"] = json!(1032);
+                }
+                _ => {}
+            }
+            Ok(outcome)
+        })
+    }
+}
+
+type Received = Arc<Mutex<Vec<String>>>;
+
+/// A callback receiver recording the raw bodies it gets.
+async fn receiver() -> anyhow::Result<(String, Received)> {
+    let received = Received::default();
+    let app = Router::new()
+        .route(
+            "/callback",
+            post(
+                |State(received): State<Received>, body: String| async move {
+                    received.lock().unwrap().push(body);
+                    "ok"
+                },
+            ),
+        )
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/callback", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok((url, received))
+}
+
+async fn send(conversation_id: &str) -> anyhow::Result<(CallbackLog, Vec<String>)> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    ctx.callback_hooks.set(Arc::new(ScriptedHook));
+    let project_id = common::create_project(&ctx, "600100").await?;
+    let (url, received) = receiver().await?;
+
+    CallbackOrchestrator::handle_callback(
+        &ctx,
+        CreateCallbackParams {
+            project_id,
+            callback_type: CallbackType::StkPush,
+            url,
+            conversation_id: conversation_id.to_string(),
+            originator_id: "originator".to_string(),
+            payload: json!({ "ResultCode": 0 }),
+            transaction_id: None,
+        },
+        None,
+    )
+    .await;
+
+    let log = CallbackLog::find_by_project(&ctx.db, project_id)
+        .await?
+        .pop()
+        .expect("callback is logged");
+    let received = received.lock().unwrap().clone();
+    Ok((log, received))
+}
+
+#[tokio::test]
+async fn untouched_callbacks_are_sent_as_is() -> anyhow::Result<()> {
+    let (log, received) = send("keep").await?;
+
+    assert_eq!(received, vec![r#"{"ResultCode":0}"#.to_string()]);
+    assert_eq!(log.status, CallbackStatus::Delivered);
+    assert_eq!(log.hook_decision, Some(CallbackDecision::Unchanged));
+    assert_eq!(log.original_payload, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rewritten_callbacks_keep_the_original_payload() -> anyhow::Result<()> {
+    let (log, received) = send("rewrite").await?;
+
+    assert_eq!(received, vec![r#"{"ResultCode":1032}"#.to_string()]);
+    assert_eq!(log.hook_decision, Some(CallbackDecision::Modified));
+    assert_eq!(log.payload, json!({ "ResultCode": 1032 }));
+    assert_eq!(log.original_payload, Some(json!({ "ResultCode": 0 })));
+    Ok(())
+}
+
+#[tokio::test]
+async fn dropped_callbacks_are_logged_but_never_sent() -> anyhow::Result<()> {
+    let (log, received) = send("drop").await?;
+
+    assert!(received.is_empty());
+    assert_eq!(log.status, CallbackStatus::Dropped);
+    assert_eq!(log.hook_decision, Some(CallbackDecision::Dropped));
+    Ok(())
+}
+
+#[tokio::test]
+async fn raw_bodies_are_sent_verbatim() -> anyhow::Result<()> {
+    let (log, received) = send("raw").await?;
+
+    assert_eq!(received, vec![r#"{"ResultCode": 0,"#.to_string()]);
+    assert_eq!(log.hook_decision, Some(CallbackDecision::Modified));
+    assert_eq!(
+        log.payload,
+        Value::String(r#"{"ResultCode": 0,"#.to_string())
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn delayed_callbacks_are_only_delayed_when_otherwise_untouched() -> anyhow::Result<()> {
+    let (log, received) = send("delay").await?;
+    assert_eq!(received, vec![r#"{"ResultCode":0}"#.to_string()]);
+    assert_eq!(log.hook_decision, Some(CallbackDecision::Delayed));
+
+    let (log, received) = send("delay_rewrite").await?;
+    assert_eq!(received, vec![r#"{"ResultCode":1032}"#.to_string()]);
+    assert_eq!(log.hook_decision, Some(CallbackDecision::Modified));
+    assert_eq!(log.original_payload, Some(json!({ "ResultCode": 0 })));
+    Ok(())
+}
@@ -0,0 +1,80 @@
+#![allow(dead_code)]
+
+use pesa_core::migrations::Migrator;
+use sea_orm::{Database, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
+
+/// Fresh in-memory database with the full schema and the default tariff.
+pub async fn setup_db() -> anyhow::Result<DatabaseConnection> {
+    let db = Database::connect("sqlite::memory:").await?;
+    Migrator::up(&db, None).await?;
+    pesa_core::transaction_costs::init_default_costs(&db).await?;
+    Ok(db)
+}
+
+/// A business with an empty MMF account and `utility_balance` cents in its utility account.
+pub async fn create_business(
+    db: &DatabaseConnection,
+    short_code: &str,
+    utility_balance: i64,
+) -> anyhow::Result<pesa_core::business::Business> {
+    pesa_core::business::Business::create(
+        db,
+        pesa_core::business::CreateBusiness {
+            name: format!("Business {short_code}"),
+            short_code: short_code.to_string(),
+            initial_working_balance: 0.0,
+            initial_utility_balance: utility_balance as f64 / 100.0,
+        },
+    )
+    .await
+}
+
+/// Discards the events emitted by the core.
+pub struct NoopEvents;
+
+impl pesa_core::AppEventManager for NoopEvents {
+    fn emit_all(&self, _event: &str, _payload: serde_json::Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// An [`pesa_core::AppContext`] over `db`, with its settings kept in `dir`.
+pub async fn app_context(
+    db: DatabaseConnection,
+    dir: &tempfile::TempDir,
+) -> anyhow::Result<pesa_core::AppContext> {
+    let settings =
+        pesa_core::settings::SettingsManager::new(dir.path().join("settings.json")).await?;
+
+    Ok(pesa_core::AppContext {
+        db,
+        settings,
+        event_manager: std::sync::Arc::new(NoopEvents),
+        running: Default::default(),
+        app_root: dir.path().to_path_buf(),
+        interceptors: Default::default(),
+        callback_hooks: Default::default(),
+        recordings: Default::default(),
+        metrics: Default::default(),
+    })
+}
+
+/// A project of a new business, with its API keys.
+pub async fn create_project(ctx: &pesa_core::AppContext, short_code: &str) -> anyhow::Result<u32> {
+    let business = create_business(&ctx.db, short_code, 0).await?;
+    let project = pesa_core::projects::ui::create_project(
+        ctx,
+        pesa_core::projects::CreateProject {
+            business_id: business.id,
+            name: format!("Project {short_code}"),
+            callback_url: None,
+            simulation_mode: pesa_core::projects::SimulationMode::AlwaysSuccess,
+            validation_mode: Default::default(),
+            stk_delay: 0,
+            prefix: None,
+        },
+    )
+    .await?;
+    Ok(project.id)
+}
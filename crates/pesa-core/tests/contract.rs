@@ -0,0 +1,135 @@
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{Json, Router, http::StatusCode, routing::post};
+use pesa_core::self_test::{
+    context::TestMode,
+    contract::{ContractTarget, ContractTests},
+    emitter::TestStatus,
+    report::TestCaseReport,
+    runner::TestRunner,
+};
+use serde_json::{Value, json};
+
+fn accepted() -> Json<Value> {
+    Json(json!({ "ResultCode": "0", "ResultDesc": "Accepted" }))
+}
+
+/// Accepts what parses as JSON and rejects the rest, as M-Pesa expects.
+async fn conforming(body: String) -> Result<Json<Value>, StatusCode> {
+    serde_json::from_str::<Value>(&body)
+        .map(|_| accepted())
+        .map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+/// Breaks on STK callbacks it can't read.
+async fn crashes_on_bad_callbacks(body: String) -> Result<Json<Value>, StatusCode> {
+    let callback: Value = serde_json::from_str(&body).unwrap_or_default();
+    if callback["Body"]["stkCallback"]["ResultCode"].is_i64() {
+        Ok(accepted())
+    } else {
+        Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+/// Answers validation requests with something that isn't a validation response.
+async fn answers_validation_with_text() -> &'static str {
+    "OK"
+}
+
+async fn serve(app: Router) -> anyhow::Result<ContractTarget> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let target = ContractTarget::new(format!("http://{}", listener.local_addr()?));
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok(target)
+}
+
+async fn run(target: ContractTarget) -> anyhow::Result<Vec<TestCaseReport>> {
+    let dir = tempfile::tempdir()?;
+    let report = TestRunner::new(Duration::from_secs(60), Duration::from_secs(1))
+        .continue_on_failure(true)
+        .with_target(target)
+        .run_suite::<ContractTests>(
+            TestMode::Fresh,
+            Arc::new(common::NoopEvents),
+            dir.path().to_path_buf(),
+        )
+        .await?;
+    Ok(report
+        .suites
+        .into_iter()
+        .flat_map(|suite| suite.cases)
+        .collect())
+}
+
+fn case<'a>(cases: &'a [TestCaseReport], name: &str) -> &'a TestCaseReport {
+    cases
+        .iter()
+        .find(|case| case.name == name)
+        .unwrap_or_else(|| panic!("{name} missing from the report"))
+}
+
+#[tokio::test]
+async fn conforming_backend_passes_every_contract() -> anyhow::Result<()> {
+    let target = serve(
+        Router::new()
+            .route("/mpesa/stk/callback", post(conforming))
+            .route("/mpesa/b2c/result", post(conforming))
+            .route("/mpesa/c2b/validation", post(conforming))
+            .route("/mpesa/c2b/confirmation", post(conforming)),
+    )
+    .await?;
+
+    let cases = run(target).await?;
+    assert_eq!(cases.len(), 4);
+    for case in &cases {
+        assert_eq!(
+            case.status,
+            TestStatus::Passed,
+            "{}: {}",
+            case.name,
+            case.message
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn wrong_answers_fail_the_contract_they_break() -> anyhow::Result<()> {
+    let target = serve(
+        Router::new()
+            .route("/mpesa/stk/callback", post(crashes_on_bad_callbacks))
+            .route("/mpesa/b2c/result", post(conforming))
+            .route("/mpesa/c2b/validation", post(answers_validation_with_text))
+            .route("/mpesa/c2b/confirmation", post(conforming)),
+    )
+    .await?;
+
+    let cases = run(target).await?;
+
+    let stk = case(&cases, "stk_callback");
+    assert_eq!(stk.status, TestStatus::Failed);
+    assert!(
+        stk.message
+            .contains("malformed: truncated JSON: server error 500"),
+        "{}",
+        stk.message
+    );
+    // the well formed callbacks were answered correctly
+    assert!(!stk.message.contains("success:"), "{}", stk.message);
+
+    let validation = case(&cases, "c2b_validation");
+    assert_eq!(validation.status, TestStatus::Failed);
+    assert!(
+        validation
+            .message
+            .contains("paybill payment: answer is not a validation response"),
+        "{}",
+        validation.message
+    );
+
+    assert_eq!(case(&cases, "b2c_result").status, TestStatus::Passed);
+    assert_eq!(case(&cases, "c2b_confirmation").status, TestStatus::Passed);
+    Ok(())
+}
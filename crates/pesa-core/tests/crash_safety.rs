@@ -0,0 +1,195 @@
+//! Failures injected part way through a ledger operation must leave no partial writes behind.
+//!
+//! Failures are injected with SQLite triggers that abort a specific write, which is the closest
+//! we can get to a crash between two steps without hooks in the ledger itself.
+
+mod common;
+
+use pesa_core::{
+    accounts::{Account, AccountType, SYSTEM_ACCOUNT_ID},
+    journal,
+    transactions::{self, Ledger, ReversalOptions, TransactionType},
+};
+use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait};
+
+struct Snapshot {
+    balances: Vec<i64>,
+    transactions: u64,
+}
+
+async fn snapshot(db: &DatabaseConnection, accounts: &[u32]) -> anyhow::Result<Snapshot> {
+    let mut balances = Vec::new();
+    for id in accounts {
+        balances.push(
+            Account::get_account(db, *id)
+                .await?
+                .expect("account")
+                .balance,
+        );
+    }
+
+    Ok(Snapshot {
+        balances,
+        transactions: transactions::db::Entity::find().count(db).await?,
+    })
+}
+
+async fn inject_failure(db: &DatabaseConnection, trigger: &str) -> anyhow::Result<()> {
+    db.execute_unprepared(&format!(
+        "CREATE TRIGGER injected_failure {trigger} BEGIN SELECT RAISE(ABORT, 'injected failure'); END;"
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn clear_failure(db: &DatabaseConnection) -> anyhow::Result<()> {
+    db.execute_unprepared("DROP TRIGGER injected_failure")
+        .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_transfer_steps_roll_back() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 10_000).await?;
+    let accounts = [alice.id, bob.id, SYSTEM_ACCOUNT_ID];
+
+    let failures = [
+        // after the transaction row is written, before any journal entry
+        "BEFORE INSERT ON journal_entries".to_string(),
+        // after the source is debited, before the destination is credited
+        format!("BEFORE UPDATE ON accounts WHEN NEW.id = {}", bob.id),
+        // after both balances moved, before the transaction log is written
+        "BEFORE INSERT ON transactions_log".to_string(),
+    ];
+
+    for failure in failures {
+        let before = snapshot(&db, &accounts).await?;
+        inject_failure(&db, &failure).await?;
+
+        let result = Ledger::transfer(
+            &db,
+            Some(alice.id),
+            bob.id,
+            25_000,
+            &TransactionType::SendMoney,
+            None,
+        )
+        .await;
+        assert!(result.is_err(), "transfer should fail on `{failure}`");
+
+        clear_failure(&db).await?;
+        let after = snapshot(&db, &accounts).await?;
+        assert_eq!(
+            before.balances, after.balances,
+            "balances moved on `{failure}`"
+        );
+        assert_eq!(before.transactions, after.transactions);
+        assert!(journal::audit_ledger(&db).await?.balanced);
+    }
+
+    // with the failures cleared the same transfer goes through
+    Ledger::transfer(
+        &db,
+        Some(alice.id),
+        bob.id,
+        25_000,
+        &TransactionType::SendMoney,
+        None,
+    )
+    .await?;
+    assert_eq!(
+        Account::get_account(&db, bob.id).await?.unwrap().balance,
+        35_000
+    );
+    assert!(journal::audit_ledger(&db).await?.balanced);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_reversal_rolls_back() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 0).await?;
+    let accounts = [alice.id, bob.id, SYSTEM_ACCOUNT_ID];
+
+    let (txn, _) = Ledger::transfer(
+        &db,
+        Some(alice.id),
+        bob.id,
+        25_000,
+        &TransactionType::SendMoney,
+        None,
+    )
+    .await?;
+
+    // everything moved, only marking the original as reversed fails
+    let before = snapshot(&db, &accounts).await?;
+    inject_failure(&db, "BEFORE UPDATE ON transactions").await?;
+    assert!(
+        Ledger::reverse(&db, &txn.id, &ReversalOptions::default())
+            .await
+            .is_err()
+    );
+    clear_failure(&db).await?;
+
+    let after = snapshot(&db, &accounts).await?;
+    assert_eq!(before.balances, after.balances);
+    assert_eq!(before.transactions, after.transactions);
+    assert!(journal::audit_ledger(&db).await?.balanced);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn failed_transfer_inside_outer_transaction_keeps_earlier_writes() -> anyhow::Result<()> {
+    use sea_orm::TransactionTrait;
+
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 0).await?;
+
+    inject_failure(
+        &db,
+        &format!(
+            "BEFORE UPDATE ON accounts WHEN NEW.id = {} AND NEW.balance > 10000",
+            bob.id
+        ),
+    )
+    .await?;
+
+    let outer = db.begin().await?;
+    Ledger::transfer(
+        &outer,
+        Some(alice.id),
+        bob.id,
+        10_000,
+        &TransactionType::SendMoney,
+        None,
+    )
+    .await?;
+    assert!(
+        Ledger::transfer(
+            &outer,
+            Some(alice.id),
+            bob.id,
+            5_000,
+            &TransactionType::SendMoney,
+            None
+        )
+        .await
+        .is_err()
+    );
+    outer.commit().await?;
+    clear_failure(&db).await?;
+
+    assert_eq!(
+        Account::get_account(&db, bob.id).await?.unwrap().balance,
+        10_000
+    );
+    assert!(journal::audit_ledger(&db).await?.balanced);
+
+    Ok(())
+}
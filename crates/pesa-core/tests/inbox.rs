@@ -0,0 +1,114 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use http_body_util::BodyExt;
+use pesa_core::{
+    inbox::{CannedResponse, InboxRequest, ui},
+    server::{api::c2b::ResultCode, create_router},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn post(
+    router: &axum::Router,
+    uri: &str,
+    body: Value,
+) -> anyhow::Result<(StatusCode, Value)> {
+    let request = Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-trace", "abc")
+        .body(Body::from(body.to_string()))?;
+    let response = router.clone().oneshot(request).await?;
+    let status = response.status();
+    let bytes = response.into_body().collect().await?.to_bytes();
+    Ok((status, serde_json::from_slice(&bytes)?))
+}
+
+#[tokio::test]
+async fn inbox_stores_requests_and_accepts_by_default() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600100").await?;
+    let router = create_router(ctx.clone(), project_id, true);
+
+    let callback = json!({ "Body": { "stkCallback": { "ResultCode": 0 } } });
+    let (status, body) = post(&router, "/_inbox/stk?attempt=1", callback.clone()).await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "ResultCode": "0", "ResultDesc": "Accepted" }));
+
+    let stored = InboxRequest::list(&ctx.db, project_id, Some("stk"), None, None).await?;
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].method, "POST");
+    assert_eq!(stored[0].query.as_deref(), Some("attempt=1"));
+    assert_eq!(
+        stored[0].headers.get("x-trace").map(String::as_str),
+        Some("abc")
+    );
+    assert_eq!(stored[0].body, callback);
+
+    // the inbox keeps its own record, it is not an API call to the sandbox
+    let logs = pesa_core::api_logs::ui::count_api_logs(&ctx, None, None, None, None).await?;
+    assert_eq!(logs, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn inbox_answers_with_its_canned_response() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600101").await?;
+    let router = create_router(ctx.clone(), project_id, false);
+
+    ui::set_inbox_response(
+        &ctx,
+        project_id,
+        "validation".into(),
+        CannedResponse::Reject {
+            result_code: ResultCode::C2B00012,
+            result_desc: Some("Invalid Account Number".into()),
+        },
+    )
+    .await?;
+
+    let (status, body) = post(&router, "/_inbox/validation", json!({ "TransID": "X1" })).await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({ "ResultCode": "C2B00012", "ResultDesc": "Invalid Account Number" })
+    );
+    post(&router, "/_inbox/confirmation", json!({ "TransID": "X1" })).await?;
+
+    let inboxes = ui::list_inboxes(&ctx, project_id).await?;
+    let names: Vec<_> = inboxes
+        .iter()
+        .map(|inbox| (inbox.name.as_str(), inbox.requests))
+        .collect();
+    assert_eq!(names, [("confirmation", 1), ("validation", 1)]);
+    assert_eq!(inboxes[0].response, CannedResponse::Accept);
+
+    assert_eq!(
+        ui::clear_inbox(&ctx, project_id, Some("validation".into())).await?,
+        1
+    );
+    let left = InboxRequest::list(&ctx.db, project_id, None, None, None).await?;
+    assert_eq!(left.len(), 1);
+    assert_eq!(left[0].inbox, "confirmation");
+    Ok(())
+}
+
+#[tokio::test]
+async fn inbox_names_are_restricted() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600102").await?;
+
+    let result =
+        ui::set_inbox_response(&ctx, project_id, "a b".into(), CannedResponse::Accept).await;
+
+    assert!(result.is_err());
+    Ok(())
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use futures::future::BoxFuture;
+use http_body_util::BodyExt;
+use pesa_core::server::{
+    create_router,
+    intercept::{InterceptResponse, InterceptedRequest, Interception, RequestInterceptor},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+const STK_ROUTE: &str = "/mpesa/stkpush/v1/processrequest";
+
+/// Answers STK pushes for one phone number and fixes up the body of the others.
+struct StkInterceptor;
+
+impl RequestInterceptor for StkInterceptor {
+    fn handles(&self, _project_id: u32, path: &str) -> bool {
+        path == STK_ROUTE
+    }
+
+    fn intercept(
+        &self,
+        mut request: InterceptedRequest,
+    ) -> BoxFuture<'static, anyhow::Result<Interception>> {
+        Box::pin(async move {
+            if request.body["PhoneNumber"] == json!("254700000001") {
+                return Ok(Interception::Respond(InterceptResponse {
+                    status: 500,
+                    headers: Default::default(),
+                    body: json!({ "errorCode": "500.001.1001", "project": request.project_id }),
+                }));
+            }
+
+            request.body = json!({ "PhoneNumber": "254700000002" });
+            request
+                .headers
+                .insert("content-type".into(), "application/json".into());
+            Ok(Interception::Forward(request))
+        })
+    }
+}
+
+async fn router() -> anyhow::Result<(axum::Router, tempfile::TempDir)> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    ctx.interceptors.set(Arc::new(StkInterceptor));
+    Ok((create_router(ctx, 7, false), dir))
+}
+
+async fn post(router: &axum::Router, body: &str) -> anyhow::Result<(StatusCode, Value)> {
+    let request = Request::post(STK_ROUTE)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))?;
+    let response = router.clone().oneshot(request).await?;
+    let status = response.status();
+    let bytes = response.into_body().collect().await?.to_bytes();
+    Ok((
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    ))
+}
+
+#[tokio::test]
+async fn interceptor_answers_without_reaching_the_handler() -> anyhow::Result<()> {
+    let (router, _dir) = router().await?;
+
+    let (status, body) = post(&router, r#"{"PhoneNumber": "254700000001"}"#).await?;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body, json!({ "errorCode": "500.001.1001", "project": 7 }));
+    Ok(())
+}
+
+#[tokio::test]
+async fn rewritten_requests_reach_the_handler() -> anyhow::Result<()> {
+    let (router, _dir) = router().await?;
+
+    // not JSON, which would be rejected as malformed
+    let (status, body) = post(&router, "PhoneNumber=254700000002").await?;
+
+    // the rewritten body parses, the handler only rejects its missing fields
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["errorCode"], "400.002.14");
+    Ok(())
+}
+
+#[tokio::test]
+async fn routes_without_interceptors_are_untouched() -> anyhow::Result<()> {
+    let (router, _dir) = router().await?;
+
+    let response = router
+        .oneshot(Request::get("/oauth/v1/generate").body(Body::empty())?)
+        .await?;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    Ok(())
+}
+
+/// Forwards requests with one header changed and one added.
+struct HeaderInterceptor;
+
+impl RequestInterceptor for HeaderInterceptor {
+    fn handles(&self, _project_id: u32, _path: &str) -> bool {
+        true
+    }
+
+    fn intercept(
+        &self,
+        mut request: InterceptedRequest,
+    ) -> BoxFuture<'static, anyhow::Result<Interception>> {
+        Box::pin(async move {
+            request.headers.insert("x-changed".into(), "after".into());
+            request.headers.insert("X-Added".into(), "yes".into());
+            Ok(Interception::Forward(request))
+        })
+    }
+}
+
+/// The headers that reached the handler, values as bytes.
+async fn echo_headers(headers: axum::http::HeaderMap) -> axum::Json<Vec<(String, Vec<u8>)>> {
+    axum::Json(
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+            .collect(),
+    )
+}
+
+#[tokio::test]
+async fn forwarded_requests_keep_untouched_headers_as_they_came() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    ctx.interceptors.set(Arc::new(HeaderInterceptor));
+    let state = pesa_core::server::ApiState {
+        context: ctx,
+        project_id: 7,
+    };
+    let router = axum::Router::new()
+        .route("/echo", axum::routing::post(echo_headers))
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            pesa_core::server::intercept::intercept_middleware,
+        ));
+
+    let request = Request::post("/echo")
+        .header("x-repeated", "one")
+        .header("x-repeated", "two")
+        .header("x-binary", header::HeaderValue::from_bytes(b"caf\xe9")?)
+        .header("x-changed", "before")
+        .body(Body::empty())?;
+    let response = router.oneshot(request).await?;
+    let bytes = response.into_body().collect().await?.to_bytes();
+    let headers: Vec<(String, Vec<u8>)> = serde_json::from_slice(&bytes)?;
+    let values = |name: &str| -> Vec<&[u8]> {
+        headers
+            .iter()
+            .filter(|(header, _)| header == name)
+            .map(|(_, value)| value.as_slice())
+            .collect()
+    };
+
+    assert_eq!(values("x-repeated"), [b"one".as_slice(), b"two"]);
+    assert_eq!(values("x-binary"), [b"caf\xe9".as_slice()]);
+    assert_eq!(values("x-changed"), [b"after".as_slice()]);
+    assert_eq!(values("x-added"), [b"yes".as_slice()]);
+    Ok(())
+}
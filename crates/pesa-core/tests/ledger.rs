@@ -0,0 +1,136 @@
+mod common;
+
+use pesa_core::{
+    accounts::{Account, AccountType, SYSTEM_ACCOUNT_ID},
+    journal::{self, Journal, JournalLine, LedgerDiscrepancy},
+    transactions::{Ledger, TransactionEngineError, TransactionType},
+};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
+
+async fn balance(db: &sea_orm::DatabaseConnection, id: u32) -> anyhow::Result<i64> {
+    Ok(Account::get_account(db, id)
+        .await?
+        .expect("account exists")
+        .balance)
+}
+
+#[tokio::test]
+async fn transfer_posts_balanced_entries_and_collects_fee() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 0).await?;
+
+    let fee =
+        pesa_core::transaction_costs::get_fee(&db, &TransactionType::SendMoney, 50_000).await?;
+    let (txn, _) = Ledger::transfer(
+        &db,
+        Some(alice.id),
+        bob.id,
+        50_000,
+        &TransactionType::SendMoney,
+        None,
+    )
+    .await?;
+
+    assert_eq!(txn.fee, fee);
+    assert_eq!(balance(&db, alice.id).await?, 100_000 - 50_000 - fee);
+    assert_eq!(balance(&db, bob.id).await?, 50_000);
+    assert_eq!(balance(&db, SYSTEM_ACCOUNT_ID).await?, fee - 100_000);
+
+    let entries = Journal::entries_for_transaction(&db, &txn.id).await?;
+    assert_eq!(entries.len(), if fee > 0 { 4 } else { 2 });
+
+    let audit = journal::audit_ledger(&db).await?;
+    assert!(audit.balanced, "{:?}", audit.discrepancies);
+    Ok(())
+}
+
+#[tokio::test]
+async fn unbalanced_lines_are_rejected() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 1_000).await?;
+
+    let err = Journal::post(
+        &db,
+        "UNBALANCED",
+        &[
+            JournalLine::debit(alice.id, 500),
+            JournalLine::credit(SYSTEM_ACCOUNT_ID, 400),
+        ],
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(
+        err,
+        TransactionEngineError::UnbalancedEntry {
+            debits: 500,
+            credits: 400
+        }
+    ));
+    assert_eq!(balance(&db, alice.id).await?, 1_000);
+    Ok(())
+}
+
+#[tokio::test]
+async fn audit_detects_tampered_balance() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 1_000).await?;
+
+    let model = pesa_core::accounts::db::Entity::find_by_id(alice.id)
+        .one(&db)
+        .await?
+        .expect("account exists");
+    let mut model: pesa_core::accounts::db::ActiveModel = model.into();
+    model.balance = Set(5_000);
+    model.update(&db).await?;
+
+    let audit = journal::audit_ledger(&db).await?;
+    assert!(!audit.balanced);
+    assert!(audit.discrepancies.iter().any(|discrepancy| matches!(
+        discrepancy,
+        LedgerDiscrepancy::BalanceMismatch {
+            account_id,
+            stored_balance: 5_000,
+            journal_balance: 1_000,
+        } if *account_id == alice.id
+    )));
+    Ok(())
+}
+
+#[tokio::test]
+async fn upgrade_opens_existing_balances_in_the_journal() -> anyhow::Result<()> {
+    use pesa_core::migrations::Migrator;
+    use sea_orm::ConnectionTrait;
+    use sea_orm_migration::MigratorTrait;
+
+    // a database from before the journal, with balances already built up
+    let db = sea_orm::Database::connect("sqlite::memory:").await?;
+    Migrator::up(&db, Some(2)).await?;
+    db.execute_unprepared(
+        "INSERT INTO accounts (id, balance, account_type, created_at, disabled) VALUES \
+         (1, 150000, 'user', '2026-01-01 00:00:00+00:00', false), \
+         (2, -2500, 'user', '2026-01-01 00:00:00+00:00', false), \
+         (3, 0, 'user', '2026-01-01 00:00:00+00:00', false)",
+    )
+    .await?;
+
+    Migrator::up(&db, None).await?;
+
+    assert_eq!(balance(&db, 1).await?, 150_000);
+    assert_eq!(balance(&db, 2).await?, -2_500);
+    assert_eq!(balance(&db, SYSTEM_ACCOUNT_ID).await?, -147_500);
+    let audit = journal::audit_ledger(&db).await?;
+    assert!(audit.balanced, "{:?}", audit.discrepancies);
+    assert_eq!(audit.transactions_checked, 2);
+
+    // the opening transactions read back through today's entity
+    let openings = pesa_core::transactions::db::Entity::find().all(&db).await?;
+    assert_eq!(openings.len(), 2);
+    assert!(
+        openings
+            .iter()
+            .all(|txn| txn.transaction_type == TransactionType::OpeningBalance.to_string())
+    );
+    Ok(())
+}
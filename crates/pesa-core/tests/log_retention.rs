@@ -0,0 +1,215 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use pesa_core::{
+    api_logs::ApiLog,
+    callbacks::{CallbackLog, CallbackType, CreateCallbackParams},
+    logs::{PruneReport, RetentionPolicy, ui},
+};
+use sea_orm::ConnectionTrait;
+use serde_json::json;
+
+async fn log(
+    ctx: &pesa_core::AppContext,
+    project_id: u32,
+    age_days: i64,
+    request_body: &str,
+) -> anyhow::Result<ApiLog> {
+    ApiLog::builder()
+        .project_id(project_id)
+        .method("POST")
+        .path("/mpesa/stkpush/v1/processrequest")
+        .status_code(200)
+        .request_body(request_body)
+        .created_at(Utc::now() - Duration::days(age_days))
+        .duration(5)
+        .save(&ctx.db)
+        .await
+}
+
+async fn callback(
+    ctx: &pesa_core::AppContext,
+    project_id: u32,
+    checkout_id: &str,
+) -> anyhow::Result<CallbackLog> {
+    Ok(CallbackLog::create(
+        &ctx.db,
+        CreateCallbackParams {
+            project_id,
+            callback_type: CallbackType::StkPush,
+            url: "https://example.com/stk".to_string(),
+            conversation_id: checkout_id.to_string(),
+            originator_id: checkout_id.to_string(),
+            payload: json!({ "Body": { "stkCallback": { "CheckoutRequestID": checkout_id } } }),
+            transaction_id: None,
+        },
+    )
+    .await?)
+}
+
+#[tokio::test]
+async fn prune_applies_age_and_count_limits_per_project() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600300").await?;
+    let other_project = common::create_project(&ctx, "600301").await?;
+
+    for age in [40, 3, 2, 1, 0] {
+        log(&ctx, project_id, age, "{}").await?;
+    }
+    log(&ctx, other_project, 40, "{}").await?;
+    for id in ["ws_CO_1", "ws_CO_2", "ws_CO_3"] {
+        callback(&ctx, project_id, id).await?;
+    }
+
+    let report = ui::set_retention_policy(
+        &ctx,
+        RetentionPolicy {
+            project_id,
+            max_age_days: Some(30),
+            max_count: Some(2),
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        report,
+        PruneReport {
+            api_logs: 3,
+            callback_logs: 1,
+        }
+    );
+    let remaining = ui::prune_logs(&ctx, None).await?;
+    assert_eq!(remaining, PruneReport::default());
+    // projects without a policy keep everything
+    let kept =
+        pesa_core::api_logs::ui::count_api_logs(&ctx, Some(other_project as i64), None, None, None)
+            .await?;
+    assert_eq!(kept, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_finds_phone_numbers_and_ids_in_bodies() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600302").await?;
+
+    let stk = log(
+        &ctx,
+        project_id,
+        0,
+        r#"{"PhoneNumber":"254712345678","AccountReference":"INV-1"}"#,
+    )
+    .await?;
+    log(&ctx, project_id, 0, r#"{"PhoneNumber":"254799999999"}"#).await?;
+    callback(&ctx, project_id, "ws_CO_191220191020363925").await?;
+
+    // part of a number matches too
+    let results = ui::search_logs(
+        &ctx,
+        serde_json::from_value(json!({ "project_id": project_id, "query": "712345678" }))?,
+    )
+    .await?;
+    assert_eq!(results.api_logs.len(), 1);
+    assert_eq!(results.api_logs[0].id, stk.id);
+    assert!(results.callback_logs.is_empty());
+
+    let results = ui::search_logs(
+        &ctx,
+        serde_json::from_value(json!({ "query": "ws_CO_191220191020363925" }))?,
+    )
+    .await?;
+    assert!(results.api_logs.is_empty());
+    assert_eq!(results.callback_logs.len(), 1);
+
+    // the API log list takes the same search
+    let logs = pesa_core::api_logs::ui::list_api_logs(
+        &ctx,
+        serde_json::from_value(json!({ "project_id": project_id, "search": "inv-1" }))?,
+    )
+    .await?;
+    assert_eq!(logs.len(), 1);
+
+    // deleted logs leave the index
+    pesa_core::api_logs::ui::delete_api_log(&ctx, stk.id).await?;
+    let results = ui::search_logs(
+        &ctx,
+        serde_json::from_value(json!({ "query": "712345678" }))?,
+    )
+    .await?;
+    assert!(results.api_logs.is_empty());
+
+    assert!(
+        ui::search_logs(&ctx, serde_json::from_value(json!({ "query": "ws" }))?)
+            .await
+            .is_err()
+    );
+    assert!(
+        pesa_core::api_logs::ui::list_api_logs(
+            &ctx,
+            serde_json::from_value(json!({ "search": "ws" }))?,
+        )
+        .await
+        .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_survives_rebuilding_the_log_table() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600303").await?;
+
+    let first = log(&ctx, project_id, 0, r#"{"PhoneNumber":"254711111111"}"#).await?;
+    log(&ctx, project_id, 0, r#"{"PhoneNumber":"254722222222"}"#).await?;
+    let last = log(&ctx, project_id, 0, r#"{"PhoneNumber":"254733333333"}"#).await?;
+    pesa_core::api_logs::ui::delete_api_log(&ctx, first.id).await?;
+
+    // copied into a new table, as SQLite does when it can't alter one in place, the logs are
+    // renumbered
+    ctx.db
+        .execute_unprepared(
+            "PRAGMA foreign_keys = OFF;
+             CREATE TABLE api_logs_rebuilt AS SELECT * FROM api_logs;
+             DROP TABLE api_logs;
+             ALTER TABLE api_logs_rebuilt RENAME TO api_logs;
+             PRAGMA foreign_keys = ON;",
+        )
+        .await?;
+
+    let results = ui::search_logs(
+        &ctx,
+        serde_json::from_value(json!({ "query": "254733333333" }))?,
+    )
+    .await?;
+    assert_eq!(results.api_logs.len(), 1);
+    assert_eq!(results.api_logs[0].id, last.id);
+    Ok(())
+}
+
+#[tokio::test]
+async fn upgrade_indexes_existing_logs() -> anyhow::Result<()> {
+    use pesa_core::migrations::Migrator;
+    use sea_orm_migration::MigratorTrait;
+
+    let db = sea_orm::Database::connect("sqlite::memory:").await?;
+    let before_search_keys = Migrator::migrations().len() as u32 - 1;
+    Migrator::up(&db, Some(before_search_keys)).await?;
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(db, &dir).await?;
+    let project_id = common::create_project(&ctx, "600304").await?;
+    let logged = log(&ctx, project_id, 0, r#"{"PhoneNumber":"254744444444"}"#).await?;
+
+    Migrator::up(&ctx.db, None).await?;
+
+    let results = ui::search_logs(
+        &ctx,
+        serde_json::from_value(json!({ "query": "254744444444" }))?,
+    )
+    .await?;
+    assert_eq!(results.api_logs.len(), 1);
+    assert_eq!(results.api_logs[0].id, logged.id);
+    Ok(())
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use http_body_util::BodyExt;
+use pesa_core::{
+    accounts::{Account, AccountType},
+    server::create_router,
+    transactions::TransactionType,
+};
+use tower::ServiceExt;
+
+async fn get_metrics(router: &axum::Router) -> anyhow::Result<String> {
+    let response = router
+        .clone()
+        .oneshot(Request::get("/metrics").body(Body::empty())?)
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()[header::CONTENT_TYPE]
+            .to_str()?
+            .starts_with("text/plain")
+    );
+    let bytes = response.into_body().collect().await?.to_bytes();
+    Ok(String::from_utf8(bytes.to_vec())?)
+}
+
+#[tokio::test]
+async fn sandbox_exposes_only_its_own_series() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600500").await?;
+    let other_project = common::create_project(&ctx, "600501").await?;
+    let router = create_router(ctx.clone(), project_id, false);
+    let other_router = create_router(ctx.clone(), other_project, false);
+
+    // no access token, the sandbox turns it away
+    let request = Request::post("/mpesa/stkpush/v1/processrequest")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{}"))?;
+    let status = router.clone().oneshot(request).await?.status();
+    other_router
+        .clone()
+        .oneshot(Request::get("/nowhere").body(Body::empty())?)
+        .await?;
+    ctx.metrics
+        .observe_callback(project_id, "stk_push", 2, false, Duration::from_millis(40));
+
+    let metrics = get_metrics(&router).await?;
+    let requests = format!(
+        "pesa_api_requests_total{{project=\"{project_id}\",method=\"POST\",route=\"/mpesa/stkpush/v1/processrequest\",status=\"{}\"}} 1",
+        status.as_u16()
+    );
+    assert!(metrics.contains(&requests), "{metrics}");
+    assert!(metrics.contains(&format!(
+        "pesa_callback_attempts_total{{project=\"{project_id}\",type=\"stk_push\"}} 2"
+    )));
+    assert!(metrics.contains(&format!(
+        "pesa_callback_failures_total{{project=\"{project_id}\",type=\"stk_push\"}} 1"
+    )));
+    assert!(metrics.contains(&format!(
+        "pesa_callback_duration_seconds_bucket{{project=\"{project_id}\",type=\"stk_push\",le=\"0.05\"}} 1"
+    )));
+    assert!(!metrics.contains(&format!("project=\"{other_project}\"")));
+    // scrapes aren't counted
+    assert!(!metrics.contains("route=\"/metrics\""));
+
+    let metrics = get_metrics(&other_router).await?;
+    assert!(metrics.contains(&format!(
+        "pesa_api_requests_total{{project=\"{other_project}\",method=\"GET\",route=\"unmatched\",status=\"404\"}} 1"
+    )));
+    assert!(!metrics.contains(&format!("project=\"{project_id}\"")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn ledger_volumes_are_counted_per_transaction_type() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let alice = Account::create_account(&ctx.db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&ctx.db, AccountType::User, 0).await?;
+
+    for amount in [1_000, 2_500] {
+        pesa_core::transactions::ui::transfer(
+            &ctx,
+            Some(alice.id),
+            bob.id,
+            amount,
+            TransactionType::SendMoney,
+            None,
+        )
+        .await?;
+    }
+
+    let metrics = ctx.metrics.render(None);
+    assert!(metrics.contains("pesa_ledger_transactions_total{type=\"send_money\"} 2"));
+    assert!(metrics.contains("pesa_ledger_volume_total{type=\"send_money\"} 3500"));
+    // the ledger is shared, sandboxes leave it out
+    assert!(!ctx.metrics.render(Some(1)).contains("pesa_ledger"));
+    Ok(())
+}
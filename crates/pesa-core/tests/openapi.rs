@@ -0,0 +1,77 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use http_body_util::BodyExt;
+use pesa_core::server::create_router;
+use serde_json::Value;
+use tower::ServiceExt;
+
+async fn get_document(router: &axum::Router) -> anyhow::Result<Value> {
+    let response = router
+        .clone()
+        .oneshot(
+            Request::get("/openapi.json")
+                .header(header::HOST, "localhost:8009")
+                .body(Body::empty())?,
+        )
+        .await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await?.to_bytes();
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+#[tokio::test]
+async fn sandbox_serves_its_openapi_document() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600700").await?;
+    let document = get_document(&create_router(ctx.clone(), project_id, false)).await?;
+
+    assert!(document["openapi"].as_str().unwrap().starts_with("3."));
+    assert_eq!(document["servers"][0]["url"], "http://localhost:8009");
+    for path in [
+        "/oauth/v1/generate",
+        "/mpesa/stkpush/v1/processrequest",
+        "/mpesa/c2b/v2/registerurl",
+        "/mpesa/b2c/v3/paymentrequest",
+        "/mpesa/accountbalance/v1/query",
+    ] {
+        assert!(document["paths"][path].is_object(), "{path} missing");
+    }
+
+    // the schemas follow the names on the wire
+    let schemas = &document["components"]["schemas"];
+    let stk_push = &schemas["StkPushRequest"]["properties"];
+    assert!(stk_push["CallBackURL"].is_object());
+    assert!(stk_push["BusinessShortCode"].is_object());
+    assert!(schemas["B2CCommandID"].is_object());
+    assert!(schemas["BalanceQueryCommandID"].is_object());
+
+    let operation = &document["paths"]["/mpesa/stkpush/v1/processrequest"]["post"];
+    let callback = &operation["callbacks"]["result"]["{$request.body#/CallBackURL}"]["post"];
+    assert_eq!(
+        callback["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/StkCallbackBodyWrapper"
+    );
+    assert!(schemas["StkCallbackBodyWrapper"]["properties"]["Body"].is_object());
+
+    let register = &document["paths"]["/mpesa/c2b/v2/registerurl"]["post"]["callbacks"];
+    assert!(register["validation"]["{$request.body#/ValidationURL}"].is_object());
+    assert!(register["confirmation"]["{$request.body#/ConfirmationURL}"].is_object());
+
+    // the error bodies are those the sandbox answers with
+    let unauthorized = &operation["responses"]["401"]["content"]["application/json"];
+    assert_eq!(
+        unauthorized["examples"]["InvalidAccessToken"]["value"],
+        serde_json::json!({ "errorCode": "401.001.02", "errorMessage": "Invalid Access Token" })
+    );
+    let codes = schemas["ErrorResponse"]["properties"]["errorCode"]["enum"]
+        .as_array()
+        .unwrap();
+    assert!(codes.contains(&Value::from("500.003.1001")));
+    assert!(!codes.contains(&Value::from("400.000.00")));
+    Ok(())
+}
@@ -0,0 +1,363 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, header},
+};
+use base64::{Engine, engine::general_purpose};
+use chrono::Utc;
+use http_body_util::BodyExt;
+use pesa_core::{
+    accounts::{
+        paybill_accounts::{CreatePaybillAccount, PaybillAccount},
+        user_profiles::User,
+    },
+    projects::{self, SimulationMode, UpdateProject},
+    recording::{
+        SESSIONS_DIR, Session, SessionEvent,
+        replay::{DivergenceKind, ReplayOptions, normalize},
+        ui,
+    },
+    server::{
+        api::stkpush::ui::{PromptDecision, STK_RESPONSE_REGISTRY, UserResponse},
+        create_router, validation,
+    },
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn send_requests(router: &axum::Router) -> anyhow::Result<()> {
+    router
+        .clone()
+        .oneshot(Request::get("/oauth/v1/generate").body(Body::empty())?)
+        .await?;
+    router
+        .clone()
+        .oneshot(
+            Request::post("/mpesa/stkpush/v1/processrequest")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({ "Amount": 1 }).to_string()))?,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Snapshots need a database kept in a file.
+async fn file_context(dir: &tempfile::TempDir) -> anyhow::Result<pesa_core::AppContext> {
+    let db = pesa_core::db::Database::new(&dir.path().join("database.sqlite")).await?;
+    db.init().await?;
+    common::app_context(db.conn, dir).await
+}
+
+/// Enters the right PIN on every STK prompt, as the user would in the app.
+struct AcceptsPrompts;
+
+impl pesa_core::AppEventManager for AcceptsPrompts {
+    fn emit_all(&self, event: &str, payload: Value) -> anyhow::Result<()> {
+        if event == "stk_push"
+            && let Some((_, sender)) = payload["checkout_id"]
+                .as_str()
+                .and_then(|checkout_id| STK_RESPONSE_REGISTRY.remove(checkout_id))
+        {
+            let pin = payload["user"]["pin"].as_str().unwrap_or_default();
+            let _ = sender.send(UserResponse::Accepted {
+                pin: pin.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Accepts callbacks on a local port and returns its URL.
+async fn callback_server() -> anyhow::Result<String> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/callback", listener.local_addr()?);
+    let app = axum::Router::new().route(
+        "/callback",
+        axum::routing::post(|| async { axum::Json(json!({ "ResultCode": 0 })) }),
+    );
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok(url)
+}
+
+/// An STK push of the paybill of the business short coded `short_code`, with a token.
+async fn stk_push(
+    ctx: &pesa_core::AppContext,
+    router: &axum::Router,
+    project_id: u32,
+    short_code: &str,
+    phone: &str,
+    callback_url: &str,
+) -> anyhow::Result<()> {
+    let project = projects::ui::get_project(ctx, project_id).await?;
+    let credentials = general_purpose::STANDARD.encode(format!(
+        "{}:{}",
+        project.consumer_key, project.consumer_secret
+    ));
+    let response = router
+        .clone()
+        .oneshot(
+            Request::get("/oauth/v1/generate?grant_type=client_credentials")
+                .header(header::AUTHORIZATION, format!("Basic {credentials}"))
+                .body(Body::empty())?,
+        )
+        .await?;
+    let bytes = response.into_body().collect().await?.to_bytes();
+    let token = serde_json::from_slice::<Value>(&bytes)?["access_token"]
+        .as_str()
+        .expect("access token issued")
+        .to_string();
+
+    let timestamp = Utc::now()
+        .with_timezone(&chrono::FixedOffset::east_opt(3 * 3600).unwrap())
+        .format(validation::TIMESTAMP_FORMAT)
+        .to_string();
+    let password =
+        general_purpose::STANDARD.encode(format!("{short_code}{}{timestamp}", project.passkey));
+    let response = router
+        .clone()
+        .oneshot(
+            Request::post("/mpesa/stkpush/v1/processrequest")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::from(
+                    json!({
+                        "BusinessShortCode": short_code,
+                        "Password": password,
+                        "Timestamp": timestamp,
+                        "TransactionType": "CustomerPayBillOnline",
+                        "Amount": "10",
+                        "PartyA": phone,
+                        "PartyB": short_code,
+                        "PhoneNumber": phone,
+                        "CallBackURL": callback_url,
+                        "AccountReference": "INV-001",
+                        "TransactionDesc": "Payment"
+                    })
+                    .to_string(),
+                ))?,
+        )
+        .await?;
+    assert_eq!(response.status(), 200);
+    Ok(())
+}
+
+fn replay_options(session: &str) -> ReplayOptions {
+    ReplayOptions {
+        session: session.to_string(),
+        ignore_timing: true,
+        settle_ms: Some(0),
+    }
+}
+
+#[tokio::test]
+async fn recorded_session_replays_without_divergences() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = file_context(&dir).await?;
+    let project_id = common::create_project(&ctx, "600200").await?;
+    let router = create_router(ctx.clone(), project_id, true);
+
+    ui::start_recording(&ctx, project_id, Some("smoke".into())).await?;
+    assert!(ui::start_recording(&ctx, project_id, None).await.is_err());
+    send_requests(&router).await?;
+    let summary = ui::stop_recording(&ctx, project_id).await?;
+
+    assert_eq!(summary.requests, 2);
+    assert!(dir.path().join(SESSIONS_DIR).join("smoke.sqlite").exists());
+    let session = ui::get_session(&ctx, "smoke".into()).await?;
+    let SessionEvent::Request { uri, status, .. } = &session.entries[0].event else {
+        panic!("expected a request, got {:?}", session.entries[0].event);
+    };
+    assert_eq!(uri, "/oauth/v1/generate");
+    assert_eq!(*status, 400);
+
+    let report = ui::replay_session(&ctx, replay_options("smoke")).await?;
+    assert_eq!(report.requests, 2);
+    assert!(report.passed(), "{:#?}", report.divergences);
+
+    assert_eq!(ui::list_sessions(&ctx).await?.len(), 1);
+    assert!(ui::delete_session(&ctx, "smoke".into()).await?);
+    assert!(ui::list_sessions(&ctx).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_reports_changed_responses() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = file_context(&dir).await?;
+    let project_id = common::create_project(&ctx, "600201").await?;
+    let router = create_router(ctx.clone(), project_id, true);
+
+    ui::start_recording(&ctx, project_id, Some("changed".into())).await?;
+    send_requests(&router).await?;
+    ui::stop_recording(&ctx, project_id).await?;
+
+    // pretend the sandbox answered the STK push differently back then
+    let sessions = dir.path().join(SESSIONS_DIR);
+    let mut session = Session::load(&sessions, "changed")?;
+    if let SessionEvent::Request { status, .. } = &mut session.entries[1].event {
+        *status = 200;
+    }
+    session.save(&sessions)?;
+
+    let report = ui::replay_session(&ctx, replay_options("changed")).await?;
+    assert_eq!(report.divergences.len(), 1);
+    let divergence = &report.divergences[0];
+    assert_eq!(divergence.kind, DivergenceKind::Response);
+    assert_eq!(divergence.index, 1);
+    assert_eq!(divergence.target, "/mpesa/stkpush/v1/processrequest");
+    assert_eq!(divergence.expected["status"], 200);
+    assert_ne!(divergence.actual["status"], 200);
+    Ok(())
+}
+
+#[tokio::test]
+async fn interactive_prompts_replay_the_recorded_decision() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = pesa_core::AppContext {
+        event_manager: Arc::new(AcceptsPrompts),
+        ..file_context(&dir).await?
+    };
+    let project_id = common::create_project(&ctx, "600202").await?;
+    let other_project = common::create_project(&ctx, "600203").await?;
+    projects::ui::update_project(
+        &ctx,
+        project_id,
+        UpdateProject {
+            simulation_mode: Some(SimulationMode::Realistic),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let business_id = projects::ui::get_project(&ctx, project_id)
+        .await?
+        .business_id;
+    PaybillAccount::create(
+        &ctx.db,
+        CreatePaybillAccount {
+            business_id,
+            paybill_number: 600202,
+            response_type: None,
+            validation_url: None,
+            confirmation_url: None,
+        },
+    )
+    .await?;
+    User::create_from(
+        &ctx.db,
+        "254708374149".to_string(),
+        "Jane".to_string(),
+        "1234".to_string(),
+        100_000,
+    )
+    .await?;
+    let callback_url = callback_server().await?;
+    let router = create_router(ctx.clone(), project_id, true);
+
+    ui::start_recording(&ctx, project_id, Some("prompt".into())).await?;
+    ui::start_recording(&ctx, other_project, Some("other".into())).await?;
+    stk_push(
+        &ctx,
+        &router,
+        project_id,
+        "600202",
+        "254708374149",
+        &callback_url,
+    )
+    .await?;
+    for _ in 0..100 {
+        let session = ctx.recordings.current(project_id).unwrap();
+        if session.callbacks().count() > 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    ui::stop_recording(&ctx, project_id).await?;
+    let other = ui::stop_recording(&ctx, other_project).await?;
+
+    let session = ui::get_session(&ctx, "prompt".into()).await?;
+    assert_eq!(session.summary().callbacks, 1);
+    assert_eq!(
+        session.prompt_decisions().collect::<Vec<_>>(),
+        vec![&PromptDecision::Accepted { pin_valid: true }]
+    );
+    // the payment moved money of the recorded project only
+    assert!(session.summary().ledger_entries > 0);
+    assert_eq!(other.ledger_entries, 0);
+
+    let started = std::time::Instant::now();
+    let options = ReplayOptions {
+        settle_ms: Some(10_000),
+        ..replay_options("prompt")
+    };
+    let report = ui::replay_session(&ctx, options).await?;
+    assert!(report.passed(), "{:#?}", report.divergences);
+    assert_eq!(report.callbacks, 1);
+    assert_eq!(report.unanswered_prompts, 0);
+    // the prompt was answered rather than left to time out
+    assert!(started.elapsed() < std::time::Duration::from_secs(20));
+
+    // pretend the payment moved another amount back then
+    let sessions = dir.path().join(SESSIONS_DIR);
+    let mut session = Session::load(&sessions, "prompt")?;
+    let transaction = session
+        .entries
+        .iter_mut()
+        .find_map(|entry| match &mut entry.event {
+            SessionEvent::Ledger { transaction } => Some(transaction),
+            _ => None,
+        })
+        .expect("ledger entry recorded");
+    transaction.transaction_amount += 100;
+    let transaction_type = transaction.transaction_type.clone();
+    session.save(&sessions)?;
+
+    let options = ReplayOptions {
+        settle_ms: Some(10_000),
+        ..replay_options("prompt")
+    };
+    let report = ui::replay_session(&ctx, options).await?;
+    assert_eq!(report.divergences.len(), 1, "{:#?}", report.divergences);
+    let divergence = &report.divergences[0];
+    assert_eq!(divergence.kind, DivergenceKind::Ledger);
+    assert_eq!(divergence.index, 0);
+    assert_eq!(divergence.target, transaction_type);
+    assert_eq!(
+        divergence.expected["transaction_amount"].as_i64(),
+        divergence.actual["transaction_amount"]
+            .as_i64()
+            .map(|amount| amount + 100)
+    );
+    Ok(())
+}
+
+#[test]
+fn normalize_blanks_generated_ids_and_metadata_items() {
+    let callback = json!({
+        "CheckoutRequestID": "ws_CO_1",
+        "ResultCode": 0,
+        "CallbackMetadata": { "Item": [
+            { "Name": "MpesaReceiptNumber", "Value": "ABC123" },
+            { "Name": "Amount", "Value": 10 },
+        ]},
+    });
+
+    assert_eq!(
+        normalize(&callback),
+        json!({
+            "CheckoutRequestID": null,
+            "ResultCode": 0,
+            "CallbackMetadata": { "Item": [
+                { "Name": "MpesaReceiptNumber", "Value": null },
+                { "Name": "Amount", "Value": 10 },
+            ]},
+        })
+    );
+    // bodies kept as text are compared as JSON
+    assert_eq!(
+        normalize(&json!(r#"{"ConversationID":"AG_1","ResultCode":0}"#)),
+        json!({ "ConversationID": null, "ResultCode": 0 })
+    );
+}
@@ -0,0 +1,221 @@
+mod common;
+
+use pesa_core::{
+    accounts::{Account, AccountType, SYSTEM_ACCOUNT_ID},
+    journal,
+    transactions::{
+        self, FeeRefundPolicy, InsufficientFundsPolicy, Ledger, ReversalOptions,
+        TransactionEngineError, TransactionStatus, TransactionType,
+    },
+};
+use sea_orm::{DatabaseConnection, EntityTrait};
+
+async fn balance(db: &DatabaseConnection, id: u32) -> anyhow::Result<i64> {
+    Ok(Account::get_account(db, id)
+        .await?
+        .expect("account")
+        .balance)
+}
+
+async fn status(db: &DatabaseConnection, id: &str) -> anyhow::Result<String> {
+    Ok(transactions::db::Entity::find_by_id(id)
+        .one(db)
+        .await?
+        .expect("transaction")
+        .status)
+}
+
+async fn send(
+    db: &DatabaseConnection,
+    from: u32,
+    to: u32,
+    amount: i64,
+) -> anyhow::Result<transactions::Transaction> {
+    let (txn, _) = Ledger::transfer(
+        db,
+        Some(from),
+        to,
+        amount,
+        &TransactionType::SendMoney,
+        None,
+    )
+    .await?;
+    Ok(txn)
+}
+
+#[tokio::test]
+async fn full_reversal_links_back_and_cannot_repeat() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 0).await?;
+
+    let txn = send(&db, alice.id, bob.id, 40_000).await?;
+    let (reversal, _) = Ledger::reverse(&db, &txn.id, &ReversalOptions::default()).await?;
+
+    assert_eq!(reversal.reversal_of.as_deref(), Some(txn.id.as_str()));
+    assert_eq!(reversal.amount, 40_000);
+    assert_eq!(balance(&db, bob.id).await?, 0);
+    // the fee is kept by default
+    assert_eq!(balance(&db, alice.id).await?, 100_000 - txn.fee);
+
+    let original = transactions::db::Entity::find_by_id(txn.id.as_str())
+        .one(&db)
+        .await?
+        .expect("transaction");
+    assert_eq!(original.status, TransactionStatus::Reversed.to_string());
+    assert!(original.updated_at.is_some_and(|at| at > txn.created_at));
+
+    assert!(matches!(
+        Ledger::reverse(&db, &txn.id, &ReversalOptions::default()).await,
+        Err(TransactionEngineError::AlreadyReversed)
+    ));
+    assert!(matches!(
+        Ledger::reverse(&db, &reversal.id, &ReversalOptions::default()).await,
+        Err(TransactionEngineError::NotReversible(_))
+    ));
+    assert!(journal::audit_ledger(&db).await?.balanced);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn partial_reversals_refund_fee_proportionally() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 0).await?;
+
+    let txn = send(&db, alice.id, bob.id, 40_000).await?;
+    let options = |amount| ReversalOptions {
+        amount,
+        fee_refund: FeeRefundPolicy::Proportional,
+        ..Default::default()
+    };
+
+    Ledger::reverse(&db, &txn.id, &options(Some(10_000))).await?;
+    assert_eq!(
+        status(&db, &txn.id).await?,
+        TransactionStatus::PartiallyReversed.to_string()
+    );
+    assert_eq!(balance(&db, bob.id).await?, 30_000);
+
+    assert!(matches!(
+        Ledger::reverse(&db, &txn.id, &options(Some(35_000))).await,
+        Err(TransactionEngineError::InvalidReversalAmount {
+            amount: 35_000,
+            remaining: 30_000
+        })
+    ));
+
+    // the rest, and with it the rest of the fee
+    Ledger::reverse(&db, &txn.id, &options(None)).await?;
+    assert_eq!(
+        status(&db, &txn.id).await?,
+        TransactionStatus::Reversed.to_string()
+    );
+    assert_eq!(balance(&db, bob.id).await?, 0);
+    assert_eq!(balance(&db, alice.id).await?, 100_000);
+    assert!(journal::audit_ledger(&db).await?.balanced);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn spent_funds_follow_the_insufficient_funds_policy() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 0).await?;
+    let carol = Account::create_account(&db, AccountType::User, 0).await?;
+
+    let first = send(&db, alice.id, bob.id, 20_000).await?;
+    let second = send(&db, alice.id, bob.id, 20_000).await?;
+    // bob spends most of it
+    send(&db, bob.id, carol.id, 30_000).await?;
+    let bob_balance = balance(&db, bob.id).await?;
+    assert!(bob_balance < 20_000);
+
+    assert!(matches!(
+        Ledger::reverse(&db, &first.id, &ReversalOptions::default()).await,
+        Err(TransactionEngineError::InsufficientFunds)
+    ));
+
+    Ledger::reverse(
+        &db,
+        &first.id,
+        &ReversalOptions {
+            insufficient_funds: InsufficientFundsPolicy::AllowNegative,
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(balance(&db, bob.id).await?, bob_balance - 20_000);
+    assert!(balance(&db, bob.id).await? < 0);
+
+    let (pending, _) = Ledger::reverse(
+        &db,
+        &second.id,
+        &ReversalOptions {
+            insufficient_funds: InsufficientFundsPolicy::Pending,
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(pending.status, TransactionStatus::Pending);
+    assert_eq!(
+        status(&db, &second.id).await?,
+        TransactionStatus::PendingReversal.to_string()
+    );
+    assert!(matches!(
+        Ledger::complete_reversal(&db, &pending.id).await,
+        Err(TransactionEngineError::InsufficientFunds)
+    ));
+
+    // a cancelled reversal moves nothing and frees the transaction to be reversed again
+    let bob_before = balance(&db, bob.id).await?;
+    let cancelled = Ledger::cancel_reversal(&db, &pending.id).await?;
+    assert_eq!(cancelled.status, TransactionStatus::Failed);
+    assert_eq!(balance(&db, bob.id).await?, bob_before);
+    assert_eq!(
+        status(&db, &second.id).await?,
+        TransactionStatus::Completed.to_string()
+    );
+    assert!(matches!(
+        Ledger::cancel_reversal(&db, &pending.id).await,
+        Err(TransactionEngineError::NotReversible(_))
+    ));
+    assert!(matches!(
+        Ledger::complete_reversal(&db, &pending.id).await,
+        Err(TransactionEngineError::NotReversible(_))
+    ));
+    let (pending, _) = Ledger::reverse(
+        &db,
+        &second.id,
+        &ReversalOptions {
+            insufficient_funds: InsufficientFundsPolicy::Pending,
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(pending.amount, 20_000);
+
+    // once bob is topped up the pending reversal can go through
+    Ledger::transfer(
+        &db,
+        Some(SYSTEM_ACCOUNT_ID),
+        bob.id,
+        50_000,
+        &TransactionType::Deposit,
+        None,
+    )
+    .await?;
+    let before = balance(&db, bob.id).await?;
+    let (completed, _) = Ledger::complete_reversal(&db, &pending.id).await?;
+    assert_eq!(completed.status, TransactionStatus::Completed);
+    assert_eq!(balance(&db, bob.id).await?, before - 20_000);
+    assert_eq!(
+        status(&db, &second.id).await?,
+        TransactionStatus::Reversed.to_string()
+    );
+    assert!(journal::audit_ledger(&db).await?.balanced);
+
+    Ok(())
+}
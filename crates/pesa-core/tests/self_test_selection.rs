@@ -0,0 +1,196 @@
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use pesa_core::self_test::{
+    callback::CallbackManager,
+    context::{TestContext, TestMode},
+    contract::{ContractTarget, ContractTests},
+    emitter::TestStatus,
+    runner::{TestRunner, TestSelection, TestStep},
+    tests::{SelfTests, all_tests},
+};
+
+fn names(plan: &[SelfTests]) -> Vec<&'static str> {
+    plan.iter().map(|t| t.name()).collect()
+}
+
+#[test]
+fn empty_selection_runs_everything() {
+    let plan = TestSelection::default().plan::<SelfTests>().unwrap();
+    assert_eq!(plan, all_tests());
+}
+
+#[test]
+fn selected_test_pulls_in_its_dependencies() {
+    let selection = TestSelection {
+        names: vec!["c2b".to_string()],
+        tags: vec![],
+    };
+    assert_eq!(names(&selection.plan().unwrap()), ["init_project", "c2b"]);
+}
+
+#[test]
+fn tags_and_names_are_combined_in_declaration_order() {
+    let selection = TestSelection {
+        names: vec!["send_money".to_string()],
+        tags: vec!["payouts".to_string()],
+    };
+    assert_eq!(
+        names(&selection.plan().unwrap()),
+        ["init_project", "send_money", "b2c"]
+    );
+}
+
+#[test]
+fn unknown_names_and_tags_are_rejected() {
+    let unknown_name = TestSelection {
+        names: vec!["nope".to_string()],
+        tags: vec![],
+    };
+    assert!(unknown_name.plan::<SelfTests>().is_err());
+
+    let unknown_tag = TestSelection {
+        names: vec![],
+        tags: vec!["nope".to_string()],
+    };
+    assert!(unknown_tag.plan::<SelfTests>().is_err());
+}
+
+#[test]
+fn dependencies_are_declared_before_their_dependents() {
+    let all = all_tests();
+    for (i, test) in all.iter().enumerate() {
+        for dependency in test.dependencies() {
+            let position = all.iter().position(|t| t == dependency).unwrap();
+            assert!(position < i, "{} depends on a later step", test.name());
+        }
+    }
+}
+
+#[test]
+fn contract_selection_uses_the_contract_suite() {
+    let selection = TestSelection {
+        names: vec![],
+        tags: vec!["c2b".to_string()],
+    };
+    let plan = selection.plan::<ContractTests>().unwrap();
+    assert_eq!(
+        plan,
+        [ContractTests::C2BValidation, ContractTests::C2BConfirmation]
+    );
+    assert!(
+        TestSelection {
+            names: vec!["c2b".to_string()],
+            tags: vec![],
+        }
+        .plan::<ContractTests>()
+        .is_err()
+    );
+}
+
+#[test]
+fn contract_target_joins_paths_and_rejects_bad_urls() {
+    let target = ContractTarget::new("http://localhost:8080/");
+    assert!(target.validate().is_ok());
+    assert_eq!(
+        target.url(&target.c2b_validation_path),
+        "http://localhost:8080/mpesa/c2b/validation"
+    );
+    assert!(ContractTarget::new("localhost:8080").validate().is_err());
+    assert!(ContractTarget::new("not a url").validate().is_err());
+}
+
+struct Passes;
+
+impl TestStep for Passes {
+    async fn run(&self, _: &mut TestContext, _: &mut CallbackManager) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+struct Fails;
+
+impl TestStep for Fails {
+    async fn run(&self, _: &mut TestContext, _: &mut CallbackManager) -> anyhow::Result<()> {
+        anyhow::bail!("broken on purpose")
+    }
+}
+
+mod failing_suite {
+    use super::{Fails, Passes};
+
+    pesa_core::define_tests!(FailingSuite as "failing";
+        Setup { name: "setup", description: "Passes", ctor: Passes },
+        Broken { name: "broken", description: "Fails", depends_on: [Setup], ctor: Fails },
+        AfterBroken {
+            name: "after_broken",
+            description: "Needs the failed step",
+            depends_on: [Broken],
+            ctor: Passes
+        },
+        Independent { name: "independent", description: "Passes", ctor: Passes },
+    );
+}
+
+async fn run_failing_suite(
+    continue_on_failure: bool,
+) -> anyhow::Result<Vec<(String, TestStatus, String)>> {
+    let dir = tempfile::tempdir()?;
+    let report = TestRunner::new(Duration::from_secs(10), Duration::from_secs(1))
+        .continue_on_failure(continue_on_failure)
+        .run_suite::<failing_suite::FailingSuite>(
+            TestMode::Fresh,
+            Arc::new(common::NoopEvents),
+            dir.path().to_path_buf(),
+        )
+        .await?;
+    Ok(report.suites[0]
+        .cases
+        .iter()
+        .map(|case| (case.name.clone(), case.status.clone(), case.message.clone()))
+        .collect())
+}
+
+#[tokio::test]
+async fn failures_skip_only_their_dependents_when_continuing() -> anyhow::Result<()> {
+    let cases = run_failing_suite(true).await?;
+
+    let statuses: Vec<_> = cases
+        .iter()
+        .map(|(name, status, _)| (name.as_str(), status.clone()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("setup", TestStatus::Passed),
+            ("broken", TestStatus::Failed),
+            ("after_broken", TestStatus::Pending),
+            ("independent", TestStatus::Passed),
+        ]
+    );
+    assert!(cases[1].2.contains("broken on purpose"), "{}", cases[1].2);
+    assert_eq!(cases[2].2, "Not run, dependency 'broken' did not pass");
+    Ok(())
+}
+
+#[tokio::test]
+async fn failures_stop_the_suite_by_default() -> anyhow::Result<()> {
+    let cases = run_failing_suite(false).await?;
+
+    let statuses: Vec<_> = cases
+        .iter()
+        .map(|(name, status, _)| (name.as_str(), status.clone()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("setup", TestStatus::Passed),
+            ("broken", TestStatus::Failed),
+            ("after_broken", TestStatus::Pending),
+            ("independent", TestStatus::Pending),
+        ]
+    );
+    assert_eq!(cases[3].2, "Not run, step 'broken' failed");
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use pesa_core::settings::models::AppSettings;
+
+#[test]
+fn script_http_allowlist_matches_hosts() {
+    let mut settings: AppSettings = serde_json::from_str(r#"{"theme": "dark"}"#).unwrap();
+    assert!(settings.allows_script_host("localhost"));
+    assert!(settings.allows_script_host("[::1]"));
+    assert!(!settings.allows_script_host("example.com"));
+
+    settings.script_http_allowlist = vec!["api.shop.test".into(), "*.example.com".into()];
+    assert!(settings.allows_script_host("API.shop.test"));
+    assert!(settings.allows_script_host("orders.example.com"));
+    assert!(!settings.allows_script_host("example.com"));
+    assert!(!settings.allows_script_host("badexample.com"));
+    assert!(!settings.allows_script_host("localhost"));
+
+    settings.script_http_allowlist = vec!["*".into()];
+    assert!(settings.allows_script_host("anything.test"));
+}
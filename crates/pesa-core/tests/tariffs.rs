@@ -0,0 +1,366 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use pesa_core::{
+    accounts::{Account, AccountType, utility_accounts::UtilityAccount},
+    business::Business,
+    journal,
+    transaction_costs::{
+        self, FeePayer, db,
+        schedule::{TariffFormat, TariffSchedule},
+    },
+    transactions::{Ledger, TransactionType},
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, DatabaseConnection, EntityTrait, PaginatorTrait,
+};
+
+async fn add_band(
+    db: &DatabaseConnection,
+    product: TransactionType,
+    business_id: Option<u32>,
+    fee_fixed: i64,
+    paid_by: FeePayer,
+) -> anyhow::Result<()> {
+    db::ActiveModel {
+        transaction_type: Set(product.to_string()),
+        min_amount: Set(1),
+        max_amount: Set(150_000),
+        fee_fixed: Set(Some(fee_fixed)),
+        fee_percentage: Set(None),
+        business_id: Set(business_id),
+        paid_by: Set(paid_by),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn business_bands_take_precedence_over_product_and_base_bands() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let business = common::create_business(&db, "600100", 0).await?;
+
+    // without bands of its own, paybill falls back to the send money tariff
+    let base = transaction_costs::get_fee(&db, &TransactionType::SendMoney, 50_000).await?;
+    let paybill = transaction_costs::resolve_fee(
+        &db,
+        &TransactionType::Paybill,
+        50_000,
+        Some(business.id),
+        Utc::now(),
+    )
+    .await?;
+    assert_eq!(paybill.fee, base);
+    assert_eq!(paybill.paid_by, FeePayer::Customer);
+
+    add_band(&db, TransactionType::Paybill, None, 5, FeePayer::Customer).await?;
+    add_band(
+        &db,
+        TransactionType::Paybill,
+        Some(business.id),
+        2,
+        FeePayer::Business,
+    )
+    .await?;
+
+    let scoped = transaction_costs::resolve_fee(
+        &db,
+        &TransactionType::Paybill,
+        50_000,
+        Some(business.id),
+        Utc::now(),
+    )
+    .await?;
+    assert_eq!(scoped.fee, 200);
+    assert_eq!(scoped.paid_by, FeePayer::Business);
+
+    let other = common::create_business(&db, "600200", 0).await?;
+    let global = transaction_costs::resolve_fee(
+        &db,
+        &TransactionType::Paybill,
+        50_000,
+        Some(other.id),
+        Utc::now(),
+    )
+    .await?;
+    assert_eq!(global.fee, 500);
+    assert_eq!(global.paid_by, FeePayer::Customer);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn business_paid_fees_accrue_on_the_business() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let business = common::create_business(&db, "600300", 0).await?;
+    let utility = UtilityAccount::find_by_business_id(&db, business.id)
+        .await?
+        .expect("utility account");
+    let customer = Account::create_account(&db, AccountType::User, 100_000).await?;
+
+    add_band(
+        &db,
+        TransactionType::BuyGoods,
+        Some(business.id),
+        3,
+        FeePayer::Business,
+    )
+    .await?;
+
+    let (txn, _) = Ledger::transfer(
+        &db,
+        Some(customer.id),
+        utility.account_id,
+        40_000,
+        &TransactionType::BuyGoods,
+        None,
+    )
+    .await?;
+
+    assert_eq!(txn.fee, 300);
+    // the customer only pays the amount, the merchant owes the fee
+    let customer = Account::get_account(&db, customer.id).await?.unwrap();
+    assert_eq!(customer.balance, 60_000);
+    let business = Business::get_by_id(&db, business.id).await?.unwrap();
+    assert_eq!(business.charges_amount, -300);
+
+    // settlement collects the accrued charges from the utility account
+    Business::settle_revenue(&db, business.id).await?;
+    let business = Business::get_summary(&db, business.id).await?;
+    assert_eq!(business.mmf_account.balance, 40_000 - 300);
+    assert_eq!(business.charges_amount, 0);
+
+    Business::settle_revenue(&db, business.id).await?;
+    let business = Business::get_summary(&db, business.id).await?;
+    assert_eq!(business.mmf_account.balance, 40_000 - 300);
+    assert!(journal::audit_ledger(&db).await?.balanced);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn disbursment_fees_are_paid_by_the_business() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let business = common::create_business(&db, "600400", 100_000).await?;
+    let utility = UtilityAccount::find_by_business_id(&db, business.id)
+        .await?
+        .expect("utility account");
+    let customer = Account::create_account(&db, AccountType::User, 0).await?;
+
+    let (txn, _) = Ledger::transfer(
+        &db,
+        Some(utility.account_id),
+        customer.id,
+        50_000,
+        &TransactionType::Disbursment,
+        None,
+    )
+    .await?;
+
+    let expected = transaction_costs::get_fee(&db, &TransactionType::SendMoney, 50_000).await?;
+    assert_eq!(txn.fee, expected);
+    let utility = UtilityAccount::find_by_id(&db, utility.account_id)
+        .await?
+        .unwrap();
+    assert_eq!(utility.balance, 50_000);
+    let business = Business::get_by_id(&db, business.id).await?.unwrap();
+    assert_eq!(business.charges_amount, -expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn default_tariff_round_trips_through_csv_and_json() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+
+    let current = TariffSchedule::in_effect(&db, Utc::now()).await?;
+    assert_eq!(current.bands.len(), 44);
+
+    for format in [TariffFormat::Csv, TariffFormat::Json] {
+        let exported = current.render(format)?;
+        let parsed = TariffSchedule::parse(format, &exported, None)?;
+        assert_eq!(parsed.render(format)?, exported);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transactions_use_the_tariff_in_effect_at_their_time() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let alice = Account::create_account(&db, AccountType::User, 100_000).await?;
+    let bob = Account::create_account(&db, AccountType::User, 0).await?;
+
+    let base = transaction_costs::get_fee(&db, &TransactionType::SendMoney, 50_000).await?;
+    let effective_from = Utc::now() - Duration::hours(1);
+    let csv = "transaction_type,min_amount,max_amount,fee_fixed\nsend_money,1,150000,10\n";
+    TariffSchedule::parse(TariffFormat::Csv, csv, Some(effective_from))?
+        .import(&db)
+        .await?;
+
+    let before = transaction_costs::resolve_fee(
+        &db,
+        &TransactionType::SendMoney,
+        50_000,
+        None,
+        effective_from - Duration::minutes(1),
+    )
+    .await?;
+    assert_eq!(before.fee, base);
+
+    let (txn, _) = Ledger::transfer(
+        &db,
+        Some(alice.id),
+        bob.id,
+        50_000,
+        &TransactionType::SendMoney,
+        None,
+    )
+    .await?;
+    assert_eq!(txn.fee, 1_000);
+
+    // the new version only replaces send money, withdrawals keep the base bands
+    let versions = TariffSchedule::versions(&db).await?;
+    assert_eq!(versions.len(), 2);
+    let withdraw = TariffSchedule::in_effect(&db, Utc::now())
+        .await?
+        .bands
+        .into_iter()
+        .filter(|band| band.transaction_type == "withdraw")
+        .count();
+    assert_eq!(withdraw, 15);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn importing_a_base_schedule_keeps_business_overrides() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let business = common::create_business(&db, "600400", 0).await?;
+
+    let overrides = format!(
+        "transaction_type,min_amount,max_amount,fee_fixed,business_id,paid_by\n\
+         paybill,1,150000,2,{},business\n",
+        business.id
+    );
+    TariffSchedule::parse(TariffFormat::Csv, &overrides, None)?
+        .import(&db)
+        .await?;
+    let base = "transaction_type,min_amount,max_amount,fee_fixed\n\
+                paybill,1,150000,5\n";
+    TariffSchedule::parse(TariffFormat::Csv, base, None)?
+        .import(&db)
+        .await?;
+
+    let scoped = transaction_costs::resolve_fee(
+        &db,
+        &TransactionType::Paybill,
+        50_000,
+        Some(business.id),
+        Utc::now(),
+    )
+    .await?;
+    assert_eq!(scoped.fee, 200);
+    assert_eq!(scoped.paid_by, FeePayer::Business);
+    let global =
+        transaction_costs::resolve_fee(&db, &TransactionType::Paybill, 50_000, None, Utc::now())
+            .await?;
+    assert_eq!(global.fee, 500);
+
+    // products the schedule doesn't mention keep their bands too
+    let withdraw = TariffSchedule::in_effect(&db, Utc::now())
+        .await?
+        .bands
+        .into_iter()
+        .filter(|band| band.transaction_type == "withdraw")
+        .count();
+    assert_eq!(withdraw, 15);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn overlapping_bands_are_rejected() -> anyhow::Result<()> {
+    let csv = "transaction_type,min_amount,max_amount,fee_fixed\n\
+               send_money,1,100,0\n\
+               send_money,100,500,7\n";
+
+    let err = TariffSchedule::parse(TariffFormat::Csv, csv, None).unwrap_err();
+    assert!(err.to_string().contains("Overlapping"), "{err}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn preview_compares_without_saving() -> anyhow::Result<()> {
+    let db = common::setup_db().await?;
+    let rows = db::Entity::find().count(&db).await?;
+
+    let candidate = TariffSchedule::parse(
+        TariffFormat::Json,
+        r#"{"bands": [{"transaction_type": "send_money", "min_amount": 1, "max_amount": 1000,
+            "fee_fixed": 5, "fee_percentage": null}]}"#,
+        None,
+    )?;
+    let preview = candidate.preview(&db).await?;
+
+    assert_eq!(preview.len(), 2);
+    let top = &preview[1];
+    assert_eq!(top.amount, 100_000);
+    assert_eq!(
+        top.current.fee,
+        transaction_costs::get_fee(&db, &TransactionType::SendMoney, 100_000).await?
+    );
+    assert_eq!(top.candidate.fee, 500);
+    assert_eq!(db::Entity::find().count(&db).await?, rows);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn edited_bands_are_checked_against_their_tariff() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let business = common::create_business(&ctx.db, "600900", 0).await?;
+    let band = |min_amount, max_amount| transaction_costs::ui::TransactionCostData {
+        transaction_type: TransactionType::SendMoney.to_string(),
+        min_amount,
+        max_amount,
+        fee_fixed: Some(1_000),
+        fee_percentage: None,
+        business_id: Some(business.id),
+        paid_by: FeePayer::Customer,
+    };
+
+    let created = transaction_costs::ui::create_transaction_cost(&ctx, band(1, 50_000)).await?;
+    let err = transaction_costs::ui::create_transaction_cost(&ctx, band(50_000, 100_000))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Overlapping"), "{err}");
+
+    // the default tariff holds global send money bands over the same amounts
+    let global = transaction_costs::ui::TransactionCostData {
+        business_id: None,
+        ..band(1, 50_000)
+    };
+    let err = transaction_costs::ui::create_transaction_cost(&ctx, global)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Overlapping"), "{err}");
+
+    // a band doesn't overlap with what it replaces
+    transaction_costs::ui::update_transaction_cost(&ctx, created.id, band(1, 60_000)).await?;
+    let err = transaction_costs::ui::update_transaction_cost(&ctx, created.id, band(500, 100))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Invalid"), "{err}");
+    let stored = db::Entity::find_by_id(created.id)
+        .one(&ctx.db)
+        .await?
+        .unwrap();
+    assert_eq!(stored.max_amount, 60_000);
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use pesa_core::self_test::{
+    emitter::TestStatus,
+    report::{TestCaseReport, TestReport, TestSuiteReport},
+};
+
+fn case(name: &str, status: TestStatus, message: &str) -> TestCaseReport {
+    TestCaseReport {
+        name: name.to_string(),
+        status,
+        message: message.to_string(),
+        duration_ms: 1500,
+    }
+}
+
+#[test]
+fn junit_xml_counts_and_escapes_results() {
+    let mut suite = TestSuiteReport::new("stk.lua");
+    suite.cases = vec![
+        case("happy path", TestStatus::Passed, ""),
+        case(
+            "amount <= 0",
+            TestStatus::Failed,
+            "stk.lua:4: expected \"0\", got 1\nmore detail",
+        ),
+        case("slow", TestStatus::TimedOut, "Timed out after 60s"),
+        case("never ran", TestStatus::Pending, ""),
+    ];
+    let report = TestReport {
+        name: "ppg-script".to_string(),
+        suites: vec![suite],
+    };
+
+    assert!(!report.passed());
+    assert_eq!(report.failures(), 2);
+
+    let xml = report.to_junit_xml();
+    assert!(xml.contains(
+        r#"<testsuites name="ppg-script" tests="4" failures="1" errors="1" time="6.000">"#
+    ));
+    assert!(xml.contains(
+        r#"<testsuite name="stk.lua" tests="4" failures="1" errors="1" skipped="1" time="6.000">"#
+    ));
+    assert!(xml.contains(r#"<testcase name="happy path" classname="stk.lua" time="1.500"/>"#));
+    assert!(xml.contains(r#"<testcase name="amount &lt;= 0""#));
+    assert!(xml.contains(r#"<failure message="stk.lua:4: expected &quot;0&quot;, got 1">"#));
+    assert!(xml.contains(r#"<error message="Timed out after 60s">"#));
+    assert!(xml.contains(r#"<skipped message="">"#));
+}
+
+#[test]
+fn empty_report_passes() {
+    let report = TestReport::new("self-test");
+    assert!(report.passed());
+    assert!(report.to_junit_xml().contains(r#"tests="0""#));
+}
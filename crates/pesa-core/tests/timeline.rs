@@ -0,0 +1,130 @@
+mod common;
+
+use pesa_core::{
+    accounts::{Account, AccountType},
+    api_logs::ApiLog,
+    callbacks::{CallbackLog, CallbackType, CreateCallbackParams, DispatchOutcome},
+    timeline::{RequestEvent, RequestEventKind, ui::get_request_timeline},
+    transactions::{Ledger, TransactionType},
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn timeline_follows_a_request_from_log_to_callback() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600400").await?;
+    let conversation_id = "AG_20261019_0000000000000001";
+    let checkout_id = "ws_CO_191020261020363925";
+
+    let log = ApiLog::builder()
+        .project_id(project_id)
+        .method("POST")
+        .path("/mpesa/stkpush/v1/processrequest")
+        .status_code(200)
+        .duration(5)
+        .conversation_id(conversation_id)
+        .save(&ctx.db)
+        .await?;
+    RequestEvent::record(
+        &ctx.db,
+        project_id,
+        conversation_id,
+        RequestEventKind::PromptShown,
+        json!({ "phone": "254712345678", "amount": 100 }),
+    )
+    .await?;
+    RequestEvent::record(
+        &ctx.db,
+        project_id,
+        conversation_id,
+        RequestEventKind::UserResponded,
+        json!({ "response": { "accepted": { "pin_valid": true } } }),
+    )
+    .await?;
+
+    let user = Account::create_account(&ctx.db, AccountType::User, 10_000).await?;
+    let business = Account::create_account(&ctx.db, AccountType::User, 0).await?;
+    let (txn, _) = Ledger::transfer_in_conversation(
+        &ctx.db,
+        Some(conversation_id),
+        Some(user.id),
+        business.id,
+        100,
+        &TransactionType::SendMoney,
+        None,
+    )
+    .await?;
+    assert_eq!(txn.conversation_id.as_deref(), Some(conversation_id));
+
+    let callback = CallbackLog::create(
+        &ctx.db,
+        CreateCallbackParams {
+            project_id,
+            callback_type: CallbackType::StkPush,
+            url: "https://example.com/stk".to_string(),
+            conversation_id: conversation_id.to_string(),
+            originator_id: checkout_id.to_string(),
+            payload: json!({ "Body": { "stkCallback": { "ResultCode": 0 } } }),
+            transaction_id: Some(txn.id.clone()),
+        },
+    )
+    .await?;
+    callback
+        .update_dispatch_status(
+            &ctx.db,
+            DispatchOutcome::Delivered {
+                status_code: 200,
+                headers: Default::default(),
+                body: "ok".to_string(),
+            },
+        )
+        .await?;
+
+    let timeline = get_request_timeline(&ctx, checkout_id.to_string())
+        .await?
+        .expect("the checkout id resolves");
+    assert_eq!(timeline.conversation_id, conversation_id);
+    assert_eq!(timeline.project_id, Some(project_id));
+
+    let kinds: Vec<String> = timeline
+        .entries
+        .iter()
+        .map(|entry| {
+            serde_json::to_value(entry).unwrap()["kind"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    let mut expected = vec![
+        "request_received",
+        "prompt_shown",
+        "user_responded",
+        "transaction_posted",
+    ];
+    expected.extend(std::iter::repeat_n("ledger_entry", kinds.len() - 6));
+    expected.extend(["callback_sent", "callback_response"]);
+    assert_eq!(kinds, expected);
+
+    // every id of the request leads to the same timeline
+    for id in [log.id.clone(), txn.id.clone(), conversation_id.to_string()] {
+        let other = get_request_timeline(&ctx, id).await?.expect("id resolves");
+        assert_eq!(other.entries.len(), timeline.entries.len());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_ids_have_no_timeline() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    common::create_project(&ctx, "600401").await?;
+
+    assert!(
+        get_request_timeline(&ctx, "AG_missing".to_string())
+            .await?
+            .is_none()
+    );
+    Ok(())
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    Router,
+    extract::State,
+    http::{HeaderMap, HeaderValue},
+    routing::post,
+};
+#[cfg(feature = "otel")]
+use opentelemetry::trace::{SpanId, TraceContextExt, TraceId, TracerProvider as _};
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use pesa_core::{
+    callbacks::{CallbackType, CreateCallbackParams, orchestrator::CallbackOrchestrator},
+    server::trace_context::{TRACEPARENT, TRACESTATE, TraceContext},
+};
+use serde_json::json;
+#[cfg(feature = "otel")]
+use tracing::Instrument;
+#[cfg(feature = "otel")]
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+#[cfg(feature = "otel")]
+use tracing_subscriber::layer::SubscriberExt;
+
+const INBOUND: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+type Received = Arc<Mutex<Vec<HeaderMap>>>;
+
+/// A callback receiver recording the headers it gets.
+async fn receiver() -> anyhow::Result<(String, Received)> {
+    let received = Received::default();
+    let app = Router::new()
+        .route(
+            "/callback",
+            post(
+                |State(received): State<Received>, headers: HeaderMap| async move {
+                    received.lock().unwrap().push(headers);
+                    "ok"
+                },
+            ),
+        )
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}/callback", listener.local_addr()?);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok((url, received))
+}
+
+async fn send_callback(trace: Option<TraceContext>) -> anyhow::Result<HeaderMap> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600600").await?;
+    let (url, received) = receiver().await?;
+
+    CallbackOrchestrator::handle_callback(
+        &ctx,
+        CreateCallbackParams {
+            project_id,
+            callback_type: CallbackType::StkPush,
+            url,
+            conversation_id: "AG_20261019_0000000000".to_string(),
+            originator_id: "ws_CO_1".to_string(),
+            payload: json!({ "ResultCode": 0 }),
+            transaction_id: None,
+        },
+        trace,
+    )
+    .await;
+
+    let headers = received.lock().unwrap().pop().expect("callback delivered");
+    Ok(headers)
+}
+
+#[test]
+fn traceparent_is_parsed_strictly() {
+    let trace = TraceContext::parse(INBOUND).expect("valid traceparent");
+    assert_eq!(
+        format!("{:032x}", trace.trace_id),
+        "4bf92f3577b34da6a3ce929d0e0e4736"
+    );
+    assert!(trace.sampled);
+    assert_eq!(trace.traceparent(), INBOUND);
+
+    for invalid in [
+        "",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+    ] {
+        assert!(TraceContext::parse(invalid).is_none(), "{invalid:?}");
+    }
+    // later versions may carry more fields
+    assert!(
+        TraceContext::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-x").is_some()
+    );
+}
+
+#[tokio::test]
+async fn callbacks_carry_the_callers_trace() -> anyhow::Result<()> {
+    let mut inbound = HeaderMap::new();
+    inbound.insert(TRACEPARENT, HeaderValue::from_static(INBOUND));
+    inbound.insert(TRACESTATE, HeaderValue::from_static("vendor=value"));
+    let trace = TraceContext::from_headers(&inbound);
+
+    let headers = send_callback(trace).await?;
+    let outgoing = TraceContext::from_headers(&headers).expect("traceparent sent");
+    let inbound = TraceContext::parse(INBOUND).unwrap();
+    assert_eq!(outgoing.trace_id, inbound.trace_id);
+    assert_ne!(outgoing.span_id, inbound.span_id);
+    assert_eq!(outgoing.state.as_deref(), Some("vendor=value"));
+
+    // no trace came in, none goes out
+    let headers = send_callback(None).await?;
+    assert!(!headers.contains_key(TRACEPARENT));
+    Ok(())
+}
+
+#[cfg(feature = "otel")]
+#[tokio::test(flavor = "current_thread")]
+async fn exported_spans_join_the_callers_trace() -> anyhow::Result<()> {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let inbound = TraceContext {
+        state: Some("vendor=value".to_string()),
+        ..TraceContext::parse(INBOUND).unwrap()
+    };
+    let span = tracing::info_span!("mpesa.request");
+    inbound.attach(&span);
+    let request_context = span.context().span().span_context().clone();
+    assert_eq!(request_context.trace_id(), TraceId::from(inbound.trace_id));
+    assert_eq!(request_context.trace_state().header(), "vendor=value");
+
+    let headers = send_callback(Some(inbound.clone()))
+        .instrument(span)
+        .await?;
+    provider.force_flush()?;
+
+    let spans = exporter.get_finished_spans()?;
+    let dispatch = spans
+        .iter()
+        .find(|span| span.name == "callback.dispatch")
+        .expect("dispatch span exported");
+    assert_eq!(
+        dispatch.span_context.trace_id(),
+        TraceId::from(inbound.trace_id)
+    );
+    assert_eq!(dispatch.parent_span_id, request_context.span_id());
+    assert_eq!(
+        dispatch.span_context.trace_state().get("vendor"),
+        Some("value")
+    );
+
+    // the receiver's spans hang off the dispatch span
+    let outgoing = TraceContext::from_headers(&headers).expect("traceparent sent");
+    assert_eq!(outgoing.trace_id, inbound.trace_id);
+    assert_eq!(
+        SpanId::from(outgoing.span_id),
+        dispatch.span_context.span_id()
+    );
+    Ok(())
+}
@@ -0,0 +1,235 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use base64::{Engine, engine::general_purpose};
+use chrono::{TimeZone, Utc};
+use http_body_util::BodyExt;
+use pesa_core::{
+    projects::{self, UpdateProject, ValidationMode},
+    server::{create_router, validation},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+const STK_ROUTE: &str = "/mpesa/stkpush/v1/processrequest";
+
+struct Sandbox {
+    router: axum::Router,
+    token: String,
+    _dir: tempfile::TempDir,
+}
+
+/// A sandbox of a project in `mode`, with an access token.
+async fn sandbox(mode: ValidationMode) -> anyhow::Result<Sandbox> {
+    let dir = tempfile::tempdir()?;
+    let ctx = common::app_context(common::setup_db().await?, &dir).await?;
+    let project_id = common::create_project(&ctx, "600800").await?;
+    projects::ui::update_project(
+        &ctx,
+        project_id,
+        UpdateProject {
+            validation_mode: Some(mode),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let project = projects::ui::get_project(&ctx, project_id).await?;
+    let router = create_router(ctx, project_id, false);
+
+    let credentials = general_purpose::STANDARD.encode(format!(
+        "{}:{}",
+        project.consumer_key, project.consumer_secret
+    ));
+    let response = router
+        .clone()
+        .oneshot(
+            Request::get("/oauth/v1/generate?grant_type=client_credentials")
+                .header(header::AUTHORIZATION, format!("Basic {credentials}"))
+                .body(Body::empty())?,
+        )
+        .await?;
+    let bytes = response.into_body().collect().await?.to_bytes();
+    let token = serde_json::from_slice::<Value>(&bytes)?["access_token"]
+        .as_str()
+        .expect("access token issued")
+        .to_string();
+
+    Ok(Sandbox {
+        router,
+        token,
+        _dir: dir,
+    })
+}
+
+/// The status, error code and internal description of the answer to `body`.
+async fn post(
+    sandbox: &Sandbox,
+    route: &str,
+    body: String,
+) -> anyhow::Result<(StatusCode, Value, String)> {
+    let request = Request::post(route)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", sandbox.token))
+        .body(Body::from(body))?;
+    let response = sandbox.router.clone().oneshot(request).await?;
+    let status = response.status();
+    let description = response
+        .headers()
+        .get("X-Internal-Desc")
+        .map(|value| value.to_str().unwrap_or_default().to_string())
+        .unwrap_or_default();
+    let bytes = response.into_body().collect().await?.to_bytes();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    Ok((status, body["errorCode"].clone(), description))
+}
+
+/// An STK push that follows Daraja's rules, for a paybill the sandbox doesn't have.
+fn stk_push() -> Value {
+    let timestamp = Utc::now()
+        .with_timezone(&chrono::FixedOffset::east_opt(3 * 3600).unwrap())
+        .format(validation::TIMESTAMP_FORMAT)
+        .to_string();
+    json!({
+        "BusinessShortCode": "174379",
+        "Password": "c2VjcmV0",
+        "Timestamp": timestamp,
+        "TransactionType": "CustomerPayBillOnline",
+        "Amount": "10",
+        "PartyA": "254708374149",
+        "PartyB": "174379",
+        "PhoneNumber": "254708374149",
+        "CallBackURL": "https://example.com/callback",
+        "AccountReference": "INV-001",
+        "TransactionDesc": "Payment"
+    })
+}
+
+fn with(mut body: Value, field: &str, value: &str) -> String {
+    body[field] = json!(value);
+    body.to_string()
+}
+
+#[tokio::test]
+async fn malformed_bodies_get_daraja_errors() -> anyhow::Result<()> {
+    let sandbox = sandbox(ValidationMode::Lenient).await?;
+
+    let (status, code, _) = post(&sandbox, STK_ROUTE, "{".to_string()).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(code, "400.001.01");
+
+    let (status, code, description) = post(&sandbox, STK_ROUTE, "{}".to_string()).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(code, "400.002.14");
+    assert!(description.contains("missing field"), "{description}");
+
+    let (status, code, _) = post(
+        &sandbox,
+        "/mpesa/c2b/v2/registerurl",
+        json!({ "ShortCode": "not a number" }).to_string(),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(code, "400.001.01");
+    Ok(())
+}
+
+#[tokio::test]
+async fn lenient_projects_only_need_values_they_can_use() -> anyhow::Result<()> {
+    let sandbox = sandbox(ValidationMode::Lenient).await?;
+
+    // a shortcode that isn't a number no longer looks up paybill 0
+    let (_, code, description) =
+        post(&sandbox, STK_ROUTE, with(stk_push(), "PartyB", "abc")).await?;
+    assert_eq!(code, "400.002.07");
+    assert!(description.contains("PartyB"), "{description}");
+
+    // local numbers and fractional amounts get as far as the paybill lookup
+    let mut body = stk_push();
+    body["PhoneNumber"] = json!("0708374149");
+    body["Amount"] = json!("10.50");
+    let (_, code, description) = post(&sandbox, STK_ROUTE, body.to_string()).await?;
+    assert_eq!(code, "400.002.07");
+    assert_eq!(description, "Invalid Paybill Number");
+
+    // an amount that isn't a number is Daraja's error, not an internal one
+    let b2c = json!({
+        "OriginatorConversationID": "b2c-1",
+        "InitiatorName": "testapi",
+        "SecurityCredential": "secret",
+        "CommandID": "BusinessPayment",
+        "Amount": "ten",
+        "PartyA": "600800",
+        "PartyB": "254708374149",
+        "Remarks": "Salary",
+        "QueueTimeOutURL": "https://example.com/timeout",
+        "ResultURL": "https://example.com/result",
+        "Occassion": ""
+    });
+    let (_, code, _) = post(&sandbox, "/mpesa/b2c/v3/paymentrequest", b2c.to_string()).await?;
+    assert_eq!(code, "400.002.05");
+    Ok(())
+}
+
+#[tokio::test]
+async fn strict_projects_apply_darajas_rules() -> anyhow::Result<()> {
+    let sandbox = sandbox(ValidationMode::Strict).await?;
+
+    let (_, code, description) = post(&sandbox, STK_ROUTE, stk_push().to_string()).await?;
+    assert_eq!(code, "400.002.07");
+    assert_eq!(description, "Invalid Paybill Number");
+
+    for (field, value, expected) in [
+        ("PhoneNumber", "0708374149", "400.002.02"),
+        ("PartyA", "25470837414", "400.002.02"),
+        ("Amount", "10.50", "400.002.05"),
+        ("Amount", "0", "400.002.05"),
+        ("Timestamp", "20160216165627", "400.002.03"),
+        ("Timestamp", "2026-10-19 12:00", "400.002.03"),
+        ("BusinessShortCode", "12", "400.002.07"),
+        ("CallBackURL", "example.com/callback", "400.002.12"),
+        ("AccountReference", "INVOICE-000001", "400.002.15"),
+        ("TransactionDesc", "", "400.002.14"),
+    ] {
+        let (status, code, description) =
+            post(&sandbox, STK_ROUTE, with(stk_push(), field, value)).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{field}: {value}");
+        assert_eq!(code, expected, "{field}: {value} ({description})");
+    }
+
+    let (_, code, description) = post(
+        &sandbox,
+        "/mpesa/c2b/v2/registerurl",
+        json!({
+            "ShortCode": 600800,
+            "ResponseType": "Completed",
+            "ConfirmationURL": "https://example.com/mpesa/confirmation",
+            "ValidationURL": "https://example.com/validation",
+        })
+        .to_string(),
+    )
+    .await?;
+    assert_eq!(code, "400.002.12");
+    assert!(description.contains("mpesa"), "{description}");
+    Ok(())
+}
+
+#[test]
+fn timestamps_are_nairobi_time_within_a_day() {
+    // 12:00 in Nairobi
+    let now = Utc.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap();
+
+    for accepted in ["20261019120000", "20261019090000", "20261019120400"] {
+        assert!(validation::timestamp(accepted, now).is_ok(), "{accepted}");
+    }
+    for rejected in [
+        "20261018115959",
+        "20261019121000",
+        "20261319120000",
+        "1760860800",
+    ] {
+        assert!(validation::timestamp(rejected, now).is_err(), "{rejected}");
+    }
+}
@@ -0,0 +1,42 @@
+//! `pesa.json` and `pesa.base64`.
+use base64::{Engine, engine::general_purpose};
+use mlua::{Lua, LuaSerdeExt, Result as LuaResult, Table, Value};
+
+/// Registers `pesa.json.encode(value, pretty)`, `pesa.json.decode(text)`,
+/// `pesa.base64.encode(data)` and `pesa.base64.decode(text)`.
+pub(crate) fn register(lua: &Lua, pesa: &Table) -> LuaResult<()> {
+    let json = lua.create_table()?;
+    let encode_fn = lua.create_function(|lua, (value, pretty): (Value, Option<bool>)| {
+        let value: serde_json::Value = lua.from_value(value)?;
+        let text = if pretty.unwrap_or(false) {
+            serde_json::to_string_pretty(&value)
+        } else {
+            serde_json::to_string(&value)
+        };
+        text.map_err(mlua::Error::external)
+    })?;
+    json.set("encode", encode_fn)?;
+    let decode_fn = lua.create_function(|lua, text: mlua::String| {
+        let value: serde_json::Value = serde_json::from_slice(&text.as_bytes())
+            .map_err(|e| mlua::Error::runtime(format!("pesa.json.decode: {}", e)))?;
+        lua.to_value(&value)
+    })?;
+    json.set("decode", decode_fn)?;
+    pesa.set("json", json)?;
+
+    let base64 = lua.create_table()?;
+    let encode_fn = lua.create_function(|_, data: mlua::String| {
+        Ok(general_purpose::STANDARD.encode(data.as_bytes()))
+    })?;
+    base64.set("encode", encode_fn)?;
+    let decode_fn = lua.create_function(|lua, text: mlua::String| {
+        let data = general_purpose::STANDARD
+            .decode(text.as_bytes())
+            .map_err(|e| mlua::Error::runtime(format!("pesa.base64.decode: {}", e)))?;
+        lua.create_string(data)
+    })?;
+    base64.set("decode", decode_fn)?;
+    pesa.set("base64", base64)?;
+
+    Ok(())
+}
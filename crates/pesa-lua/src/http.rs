@@ -0,0 +1,172 @@
+//! `pesa.http`, an HTTP client limited to the hosts allowed in the settings.
+use mlua::{Lua, LuaSerdeExt, Result as LuaResult, Table, Value};
+use pesa_core::AppContext;
+use reqwest::{Method, Url, redirect};
+use serde::Deserialize;
+use std::{collections::HashMap, time::Duration};
+
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// The options table of `pesa.http.request`.
+#[derive(Debug, Default, Deserialize)]
+struct RequestOptions {
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    query: HashMap<String, String>,
+    /// Sent as is.
+    #[serde(default)]
+    body: Option<String>,
+    /// Sent as JSON, with a JSON content type.
+    #[serde(default)]
+    json: Option<serde_json::Value>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+/// Registers `pesa.http.request(opts)`, `pesa.http.get(url, opts)` and
+/// `pesa.http.post(url, body, opts)`.
+///
+/// Responses are tables with `status`, `ok`, `headers`, `body` and, when the body is JSON,
+/// the decoded `json`. Only failing to get a response raises an error.
+pub(crate) fn register(lua: &Lua, pesa: &Table, app_context: AppContext) -> LuaResult<()> {
+    let http = lua.create_table()?;
+
+    let request_context = app_context.clone();
+    let request_fn = lua.create_async_function(move |lua, opts: Value| {
+        let app_context = request_context.clone();
+        async move {
+            let opts: RequestOptions = lua.from_value(opts)?;
+            send(&lua, &app_context, opts).await
+        }
+    })?;
+    http.set("request", request_fn)?;
+
+    let get_context = app_context.clone();
+    let get_fn = lua.create_async_function(move |lua, (url, opts): (String, Value)| {
+        let app_context = get_context.clone();
+        async move {
+            let opts = RequestOptions {
+                method: Some("GET".to_string()),
+                url,
+                ..options(&lua, opts)?
+            };
+            send(&lua, &app_context, opts).await
+        }
+    })?;
+    http.set("get", get_fn)?;
+
+    // a table body is sent as JSON, a string as is
+    let post_fn =
+        lua.create_async_function(move |lua, (url, body, opts): (String, Value, Value)| {
+            let app_context = app_context.clone();
+            async move {
+                let mut opts = RequestOptions {
+                    method: Some("POST".to_string()),
+                    url,
+                    ..options(&lua, opts)?
+                };
+                match body {
+                    Value::Nil => {}
+                    Value::String(body) => opts.body = Some(body.to_str()?.to_string()),
+                    body => opts.json = Some(lua.from_value(body)?),
+                }
+                send(&lua, &app_context, opts).await
+            }
+        })?;
+    http.set("post", post_fn)?;
+
+    pesa.set("http", http)?;
+    Ok(())
+}
+
+fn options(lua: &Lua, opts: Value) -> LuaResult<RequestOptions> {
+    match opts {
+        Value::Nil => Ok(RequestOptions::default()),
+        opts => lua.from_value(opts),
+    }
+}
+
+async fn send(lua: &Lua, app_context: &AppContext, opts: RequestOptions) -> LuaResult<Table> {
+    let settings = app_context.settings.get().await;
+    let url = Url::parse(&opts.url).map_err(|e| {
+        mlua::Error::runtime(format!("pesa.http: invalid URL '{}': {}", opts.url, e))
+    })?;
+    if !url
+        .host_str()
+        .is_some_and(|h| settings.allows_script_host(h))
+    {
+        return Err(mlua::Error::runtime(format!(
+            "pesa.http: host '{}' is not in the script HTTP allowlist",
+            url.host_str().unwrap_or_default()
+        )));
+    }
+
+    // redirects must not lead out of the allowlist either
+    let allowlist = settings.clone();
+    let client = reqwest::Client::builder()
+        .redirect(redirect::Policy::custom(move |attempt| {
+            let host_allowed = attempt
+                .url()
+                .host_str()
+                .is_some_and(|h| allowlist.allows_script_host(h));
+            if !host_allowed {
+                attempt.error("redirect to a host outside the script HTTP allowlist")
+            } else if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .map_err(mlua::Error::external)?;
+
+    let method = opts.method.as_deref().unwrap_or("GET").to_ascii_uppercase();
+    let method = Method::from_bytes(method.as_bytes())
+        .map_err(|_| mlua::Error::runtime(format!("pesa.http: invalid method '{}'", method)))?;
+    let mut request = client
+        .request(method, url)
+        .timeout(Duration::from_millis(
+            opts.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+        ))
+        .query(&opts.query);
+    if let Some(json) = &opts.json {
+        request = request.json(json);
+    } else if let Some(body) = opts.body {
+        request = request.body(body);
+    }
+    for (name, value) in &opts.headers {
+        request = request.header(name, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| mlua::Error::runtime(format!("pesa.http: {}", e)))?;
+
+    let result = lua.create_table()?;
+    let status = response.status();
+    result.set("status", status.as_u16())?;
+    result.set("ok", status.is_success())?;
+    let headers = lua.create_table()?;
+    for (name, value) in response.headers() {
+        if let Ok(value) = value.to_str() {
+            headers.set(name.as_str(), value)?;
+        }
+    }
+    result.set("headers", headers)?;
+
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| mlua::Error::runtime(format!("pesa.http: {}", e)))?;
+    if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&body) {
+        result.set("json", lua.to_value(&json)?)?;
+    }
+    result.set("body", lua.create_string(&body)?)?;
+    Ok(result)
+}
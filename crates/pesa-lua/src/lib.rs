@@ -1,3 +1,8 @@
+pub mod encoding;
+pub mod http;
 pub mod manager;
+pub mod testing;
+pub mod timers;
 pub mod types;
-pub use manager::ScriptManager;
+pub mod watch;
+pub use manager::{EventDispatcher, ScriptManager};
@@ -1,95 +1,375 @@
 #![cfg(feature = "cli")]
 
-use clap::Parser;
-use pesa_core::{AppContext, AppEventManager, db};
-use pesa_lua::ScriptManager;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use mlua::Value;
+use pesa_core::{
+    AppContext, AppEventManager, db,
+    self_test::{emitter::TestStatus, report::TestReport},
+};
+use pesa_lua::{EventDispatcher, ScriptManager, testing::TestOptions, watch};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
-use tokio::sync::Mutex;
-use tracing::{error, info};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{error, info, warn};
 
 const TAURI_APP_ID: &str = "net.omenta.pesaplayground";
 
-/// A dummy event manager for the CLI that does nothing.
-struct CliEventManager;
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Broadcasts core events so that script listeners receive them, like the desktop and web hosts.
+struct CliEventManager {
+    sender: broadcast::Sender<(String, serde_json::Value)>,
+}
+
 impl AppEventManager for CliEventManager {
-    fn emit_all(&self, _event: &str, _payload: serde_json::Value) -> anyhow::Result<()> {
-        // In the CLI, we don't need to broadcast events.
+    fn emit_all(&self, event: &str, payload: serde_json::Value) -> anyhow::Result<()> {
+        // Sending only fails when nobody is subscribed, which is not an error for the host.
+        let _ = self.sender.send((event.to_string(), payload));
         Ok(())
     }
 }
 
 /// A lightweight M-Pesa ecosystem simulator for developers.
+///
+/// Runs a Lua script against the simulator without the desktop app, exiting with the script's
+/// status: an error or `false` exits with 1, an integer exits with that code and anything else
+/// with 0. Scripts with event listeners keep running until they call `pesa.exit(code)`, and
+/// scripts with timers until the last one is done.
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to the Lua script to execute
     #[arg(short, long)]
-    script: PathBuf,
+    script: Option<PathBuf>,
+
+    /// Directory holding the database, settings and scripts
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+
+    /// Path to the SQLite database, defaults to `database.sqlite` in the data directory
+    #[arg(long, global = true)]
+    db: Option<PathBuf>,
+
+    /// Start the sandbox of a project, by id or name, before running the script
+    #[arg(long = "start-sandbox", value_name = "PROJECT", global = true)]
+    start_sandbox: Vec<String>,
+
+    /// Run the scripts in `scripts/autorun` and reload saved scripts when they change
+    #[arg(long)]
+    autorun: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the `pesa.test` scenarios of a Lua file, or of every Lua file in a directory.
+    ///
+    /// Without `--data-dir` the tests run against a fresh database that is deleted afterwards.
+    /// Exits with 1 when a test fails.
+    Test {
+        /// Lua file or directory of Lua files to run
+        path: PathBuf,
+
+        /// Write a JUnit XML report to this file
+        #[arg(long, value_name = "FILE")]
+        junit: Option<PathBuf>,
+
+        /// Seconds a test may run before it fails as timed out
+        #[arg(long, default_value_t = 60)]
+        test_timeout: u64,
+
+        /// Seconds `t.expect_callback` waits for a callback
+        #[arg(long, default_value_t = 30)]
+        callback_timeout: u64,
+    },
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt::init();
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
 
     let args = Args::parse();
 
+    match run(args).await {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            error!("{:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> anyhow::Result<u8> {
     info!("Setting up data directory...");
-    let data_dir = if let Some(mut dir) = dirs::data_dir() {
-        dir.push(TAURI_APP_ID);
-        dir
-    } else {
-        // Fallback to current directory
-        PathBuf::from(".")
+    let mut _temp_dir = None;
+    let data_dir = match args.data_dir {
+        Some(dir) => dir,
+        // tests must not touch the data of the desktop app
+        None if args.command.is_some() => {
+            let temp_dir = tempfile::tempdir()?;
+            let dir = temp_dir.path().to_path_buf();
+            _temp_dir = Some(temp_dir);
+            dir
+        }
+        None => match dirs::data_dir() {
+            Some(mut dir) => {
+                dir.push(TAURI_APP_ID);
+                dir
+            }
+            // Fallback to current directory
+            None => PathBuf::from("."),
+        },
     };
 
     if !data_dir.exists() {
-        if let Err(e) = std::fs::create_dir_all(&data_dir) {
-            error!("Failed to create data directory: {}", e);
-            // Exit if we can't create the data dir
-            return Err(anyhow::anyhow!("Failed to create data directory: {}", e));
-        }
+        std::fs::create_dir_all(&data_dir)
+            .map_err(|e| anyhow::anyhow!("Failed to create data directory: {}", e))?;
     }
 
-    let db_path = data_dir.join("database.sqlite");
+    let db_path = args.db.unwrap_or_else(|| data_dir.join("database.sqlite"));
     let settings_path = data_dir.join("settings.json");
 
     info!("Setting up database at {:?}...", db_path);
-    let db = Arc::new(db::Database::new(&db_path).await?);
+    let db = db::Database::new(&db_path).await?;
     db.init().await?;
 
     let settings_manager = pesa_core::settings::SettingsManager::new(settings_path).await?;
 
+    let (event_sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
     let app_context = AppContext {
         db: db.conn.clone(),
         settings: settings_manager,
-        event_manager: Arc::new(CliEventManager),
-        running: Arc::new(Mutex::new(HashMap::new())),
+        event_manager: Arc::new(CliEventManager {
+            sender: event_sender.clone(),
+        }),
+        running: Arc::new(pesa_core::dashmap::DashMap::new()),
+        app_root: data_dir.clone(),
+        interceptors: Default::default(),
+        callback_hooks: Default::default(),
+        recordings: Default::default(),
+        metrics: Default::default(),
     };
 
     info!("Initializing script manager...");
-    let manager = ScriptManager::new(app_context, &data_dir)?;
+    let manager = ScriptManager::new(app_context.clone(), &data_dir)?;
+    let (exit_sender, exit_receiver) = mpsc::unbounded_channel();
+    let dispatcher = {
+        let manager = manager.lock().await;
+        manager.set_exit_handler(exit_sender)?;
+        manager.dispatcher()
+    };
+    tokio::spawn(forward_events(event_sender.subscribe(), dispatcher));
+
+    for project in &args.start_sandbox {
+        let project_id = resolve_project(&app_context, project).await?;
+        let url = pesa_core::sandboxes::ui::start_sandbox(&app_context, project_id, None).await?;
+        info!("Sandbox for project {} listening on {}", project_id, url);
+    }
 
-    info!("Reading script from: {:?}", args.script);
-    let script_code = fs::read_to_string(args.script).await?;
+    let code = match args.command {
+        Some(Command::Test {
+            path,
+            junit,
+            test_timeout,
+            callback_timeout,
+        }) => {
+            let options = TestOptions {
+                test_timeout: Duration::from_secs(test_timeout),
+                callback_timeout: Duration::from_secs(callback_timeout),
+            };
+            let report = manager
+                .lock()
+                .await
+                .run_tests(&test_files(&path)?, &options)
+                .await?;
+            print_report(&report);
+            if let Some(junit) = junit {
+                fs::write(&junit, report.to_junit_xml()).await?;
+            }
+            if report.passed() { 0 } else { 1 }
+        }
+        None => {
+            if args.autorun {
+                manager.lock().await.autorun().await?;
+                tokio::spawn(watch::watch(manager.clone(), watch::WATCH_INTERVAL));
+            }
+            run_script(
+                &manager,
+                args.script,
+                !args.start_sandbox.is_empty(),
+                exit_receiver,
+            )
+            .await?
+        }
+    };
+    manager.lock().await.timers().cancel_all();
 
-    info!("Executing script...");
-    let result = manager.lock().await.execute_script(&script_code).await?;
+    let running: Vec<u32> = app_context.running.iter().map(|s| *s.key()).collect();
+    for project_id in running {
+        if let Err(e) = pesa_core::sandboxes::ui::stop_sandbox(&app_context, project_id).await {
+            warn!("Failed to stop sandbox for project {}: {}", project_id, e);
+        }
+    }
+
+    Ok(code)
+}
 
-    info!("Script finished.");
-    println!("{}", result);
+/// Runs `script`, then keeps the host alive while the script has listeners, timers or
+/// sandboxes, returning the exit status.
+async fn run_script(
+    manager: &Arc<tokio::sync::Mutex<ScriptManager>>,
+    script: Option<PathBuf>,
+    sandboxes: bool,
+    mut exit_receiver: mpsc::UnboundedReceiver<i64>,
+) -> anyhow::Result<u8> {
+    let mut status = 0;
+    if let Some(script) = script {
+        info!("Reading script from: {:?}", script);
+        let script_code = fs::read_to_string(script).await?;
 
-    if manager.lock().await.has_listeners() {
-        info!("Event listeners registered. Script host will remain active.");
-        // Keep the process alive to listen for events
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+        info!("Executing script...");
+        let result = manager.lock().await.evaluate_script(&script_code).await;
+        status = match result {
+            Ok(value) => script_status(value)?,
+            Err(e) => {
+                error!("{}", e);
+                1
+            }
+        };
+        info!("Script finished.");
+    }
+
+    // `pesa.exit` called while the script ran takes precedence over its result.
+    let exit = exit_receiver.try_recv().ok();
+
+    let timers = manager.lock().await.timers();
+    let code = match exit {
+        Some(code) => clamp_status(code),
+        None if status == 0 => {
+            let mut announced = false;
+            loop {
+                // one-shot timers finishing may leave nothing to wait for
+                let keep_alive =
+                    manager.lock().await.has_listeners() || timers.active() > 0 || sandboxes;
+                if !keep_alive {
+                    break status;
+                }
+                if !announced {
+                    info!("Listeners, timers or sandboxes active. Script host will remain active.");
+                    announced = true;
+                }
+
+                tokio::select! {
+                    code = exit_receiver.recv() => break code.map(clamp_status).unwrap_or(0),
+                    _ = tokio::signal::ctrl_c() => break 0,
+                    _ = timers.changed() => {}
+                }
+            }
+        }
+        None => status,
+    };
+    Ok(code)
+}
+
+/// The Lua files to test: `path` itself, or the files under it in name order.
+fn test_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(test_files(&path)?);
+        } else if path.extension().and_then(|s| s.to_str()) == Some("lua") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn print_report(report: &TestReport) {
+    for suite in &report.suites {
+        println!("{}", suite.name);
+        for case in &suite.cases {
+            let label = match case.status {
+                TestStatus::Passed => "PASS",
+                TestStatus::TimedOut => "TIME",
+                _ => "FAIL",
+            };
+            println!("  {} {} ({}ms)", label, case.name, case.duration_ms);
+            for line in case.message.lines() {
+                println!("       {}", line);
+            }
         }
-    } else {
-        info!("No event listeners registered. Exiting.");
     }
 
-    Ok(())
+    let total = report.cases().count();
+    let failed = report.failures();
+    println!(
+        "\n{} passed, {} failed, {} total",
+        total - failed,
+        failed,
+        total
+    );
+}
+
+/// Delivers core events to the script listeners until the host shuts down.
+async fn forward_events(
+    mut receiver: broadcast::Receiver<(String, serde_json::Value)>,
+    dispatcher: EventDispatcher,
+) {
+    loop {
+        match receiver.recv().await {
+            Ok((event, payload)) => dispatcher.emit(&event, payload),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!("Script listeners missed {} events", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn resolve_project(ctx: &AppContext, project: &str) -> anyhow::Result<u32> {
+    if let Ok(id) = project.parse() {
+        return Ok(id);
+    }
+
+    pesa_core::projects::ui::get_projects(ctx)
+        .await?
+        .into_iter()
+        .find(|p| p.name == project)
+        .map(|p| p.id)
+        .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project))
+}
+
+/// Maps the value returned by a script to the process exit status, printing other results.
+fn script_status(value: Value) -> anyhow::Result<u8> {
+    Ok(match value {
+        Value::Nil | Value::Boolean(true) => 0,
+        Value::Boolean(false) => 1,
+        Value::Integer(code) => clamp_status(code),
+        Value::String(s) => {
+            println!("{}", s.to_str()?);
+            0
+        }
+        other => {
+            println!("{}", serde_json::to_string_pretty(&other)?);
+            0
+        }
+    })
+}
+
+/// Exit statuses are a byte, any code outside of one is a failure.
+fn clamp_status(code: i64) -> u8 {
+    u8::try_from(code).unwrap_or(1)
 }
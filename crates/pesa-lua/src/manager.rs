@@ -1,19 +1,31 @@
+use crate::testing::{self, TestOptions};
+use crate::timers::{self, Timers};
 use crate::types::*;
+use crate::{encoding, http};
 use anyhow::Result;
+use futures::future::BoxFuture;
 use mlua::{
-    AnyUserData, Function, Lua, LuaSerdeExt, RegistryKey, Result as LuaResult, UserData,
-    UserDataMethods,
+    AnyUserData, Function, Lua, LuaSerdeExt, RegistryKey, Result as LuaResult, Table, UserData,
+    UserDataMethods, Value,
+};
+use pesa_core::{
+    AppContext,
+    callbacks::{
+        CreateCallbackParams,
+        hooks::{CallbackHook, HookOutcome},
+    },
+    self_test::{callback::CallbackManager, report::TestReport},
+    server::intercept::{InterceptedRequest, Interception, RequestInterceptor},
 };
-use pesa_core::AppContext;
 use pesa_macros::generate_lua_bindings;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fs,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
-use tokio::sync::Mutex as TokioMutex;
-use tracing::error;
+use tokio::sync::{Mutex as TokioMutex, mpsc::UnboundedSender};
+use tracing::{error, info};
 
 // A wrapper for AppContext to be stored in Lua registry
 #[derive(Clone)]
@@ -51,6 +63,10 @@ generate_lua_bindings! {
     update_business(id: u32, #[wrap] input: UpdateBusiness) => pesa_core::business::ui::update_business,
     delete_business(id: u32) => pesa_core::business::ui::delete_business,
 
+    create_operator(#[wrap] input: CreateOperatorPayload) => pesa_core::business_operators::ui::create_operator,
+    get_operators_by_business(business_id: u32) => pesa_core::business_operators::ui::get_operators_by_business,
+    delete_operator(operator_id: u32) => pesa_core::business_operators::ui::delete_operator,
+
     get_users() => pesa_core::accounts::user_profiles::ui::get_users,
     create_user(name: String, phone: String, balance: f64, pin: String) => pesa_core::accounts::user_profiles::ui::create_user,
     remove_user(user_id: u32) => pesa_core::accounts::user_profiles::ui::remove_user,
@@ -84,8 +100,11 @@ generate_lua_bindings! {
     get_user_transactions(user_id: u32, limit: Option<u32>, offset: Option<u32>) => pesa_core::transactions::ui::get_user_transactions,
     get_recent_transactions(limit: Option<u32>) => pesa_core::transactions::ui::get_recent_transactions,
     get_transaction_stats() => pesa_core::transactions::ui::get_transaction_stats,
-    // transfer(source: Option<u32>, destination: u32, amount: i64, #[wrap] txn_type: TransactionType, #[wrap] notes: Option<TransactionNote>) => pesa_core::transactions::ui::transfer,
-    reverse(id: String) => pesa_core::transactions::ui::reverse,
+    get_transaction_history(#[wrap] filter: HistoryFilter) => pesa_core::transactions_log::ui::get_transaction_history,
+    transfer(source: Option<u32>, destination: u32, amount: i64, #[wrap] txn_type: TransactionType, #[wrap] notes: Option<TransactionNote>) => pesa_core::transactions::ui::transfer,
+    reverse(id: String, #[wrap] options: Option<ReversalOptions>) => pesa_core::transactions::ui::reverse,
+    complete_reversal(id: String) => pesa_core::transactions::ui::complete_reversal,
+    cancel_reversal(id: String) => pesa_core::transactions::ui::cancel_reversal,
     lipa(#[wrap] args: LipaArgs) => pesa_core::transactions::ui::lipa,
 
     get_transaction_log(transaction_id: u32) => pesa_core::transactions_log::ui::get_transaction_log,
@@ -94,6 +113,9 @@ generate_lua_bindings! {
     list_accounts_full_transaction_logs(accounts: Vec<u32>, limit: Option<u64>, offset: Option<u64>) => pesa_core::transactions_log::ui::list_accounts_full_transaction_logs,
     count_transaction_logs(accounts: Vec<u32>) => pesa_core::transactions_log::ui::count_transaction_logs,
 
+    audit_ledger() => pesa_core::journal::ui::audit_ledger,
+    get_journal_entries(transaction_id: String) => pesa_core::journal::ui::get_journal_entries,
+
     get_api_log(log_id: String) => pesa_core::api_logs::ui::get_api_log,
     update_api_log(log_id: String, #[wrap] request: UpdateApiLogRequest) => pesa_core::api_logs::ui::update_api_log,
     delete_api_log(log_id: String) => pesa_core::api_logs::ui::delete_api_log,
@@ -101,41 +123,308 @@ generate_lua_bindings! {
     count_api_logs(project_id: Option<i64>, method: Option<String>, path: Option<String>, status_code: Option<i32>) => pesa_core::api_logs::ui::count_api_logs,
     get_project_api_logs(project_id: u32, #[wrap] filter: ApiLogFilter) => pesa_core::api_logs::ui::get_project_api_logs,
     get_api_logs_by_method(project_id: u32, method: String, limit: Option<u64>) => pesa_core::api_logs::ui::get_api_logs_by_method,
+    export_api_logs_har(#[wrap] filter: ApiLogFilter) => pesa_core::api_logs::ui::export_api_logs_har,
+    export_api_log_har(log_id: String) => pesa_core::api_logs::ui::export_api_log_har,
+    export_api_log_curl(log_id: String) => pesa_core::api_logs::ui::export_api_log_curl,
+
+    get_retention_policy(project_id: u32) => pesa_core::logs::ui::get_retention_policy,
+    set_retention_policy(#[wrap] policy: RetentionPolicy) => pesa_core::logs::ui::set_retention_policy,
+    clear_retention_policy(project_id: u32) => pesa_core::logs::ui::clear_retention_policy,
+    prune_logs(project_id: Option<u32>) => pesa_core::logs::ui::prune_logs,
+    search_logs(#[wrap] search: LogSearch) => pesa_core::logs::ui::search_logs,
+    get_request_timeline(id: String) => pesa_core::timeline::ui::get_request_timeline,
+
+    list_inboxes(project_id: u32) => pesa_core::inbox::ui::list_inboxes,
+    list_inbox_requests(#[wrap] filter: InboxFilter) => pesa_core::inbox::ui::list_inbox_requests,
+    clear_inbox(project_id: u32, inbox: Option<String>) => pesa_core::inbox::ui::clear_inbox,
+    get_inbox_response(project_id: u32, inbox: String) => pesa_core::inbox::ui::get_inbox_response,
+    set_inbox_response(project_id: u32, inbox: String, #[wrap] response: CannedResponse) => pesa_core::inbox::ui::set_inbox_response,
+    get_inbox_url(project_id: u32, inbox: String) => pesa_core::inbox::ui::get_inbox_url,
+
+    start_recording(project_id: u32, name: Option<String>) => pesa_core::recording::ui::start_recording,
+    stop_recording(project_id: u32) => pesa_core::recording::ui::stop_recording,
+    recording_status(project_id: u32) => pesa_core::recording::ui::recording_status,
+    list_sessions() => pesa_core::recording::ui::list_sessions,
+    get_session(name: String) => pesa_core::recording::ui::get_session,
+    delete_session(name: String) => pesa_core::recording::ui::delete_session,
+    replay_session(#[wrap] options: ReplayOptions) => pesa_core::recording::ui::replay_session,
 
     create_transaction_cost(#[wrap] data: TransactionCostData) => pesa_core::transaction_costs::ui::create_transaction_cost,
     list_transaction_costs() => pesa_core::transaction_costs::ui::list_transaction_costs,
     update_transaction_cost(id: i32, #[wrap] data: TransactionCostData) => pesa_core::transaction_costs::ui::update_transaction_cost,
     delete_transaction_cost(id: i32) => pesa_core::transaction_costs::ui::delete_transaction_cost,
-    calculate_transaction_fee(#[wrap] txn_type: TransactionType, amount: i64) => pesa_core::transaction_costs::ui::calculate_transaction_fee,
+    calculate_transaction_fee(#[wrap] txn_type: TransactionType, amount: i64, business_id: Option<u32>) => pesa_core::transaction_costs::ui::calculate_transaction_fee,
+    list_tariff_versions() => pesa_core::transaction_costs::ui::list_tariff_versions,
+    import_tariff_schedule(#[wrap] input: TariffImport) => pesa_core::transaction_costs::ui::import_tariff_schedule,
+    export_tariff_schedule(#[wrap] input: TariffExport) => pesa_core::transaction_costs::ui::export_tariff_schedule,
+    preview_tariff_schedule(#[wrap] input: TariffImport) => pesa_core::transaction_costs::ui::preview_tariff_schedule,
 
     resolve_stk_prompt(checkout_id: String, #[wrap] result: UserResponse) => pesa_core::server::api::stkpush::ui::resolve_stk_prompt,
     #[no_context]
     get_app_info() => pesa_core::info::get_app_info,
 
+    // Settings
+    get_settings() => pesa_core::settings::ui::get_settings,
+    set_settings(#[wrap] settings: AppSettings) => pesa_core::settings::ui::set_settings,
+    generate_security_credential(password: String) => pesa_core::settings::ui::generate_security_credential,
+
     get_account(id: u32) => pesa_core::accounts::ui::get_account,
     create_account(#[wrap] account_type: AccountType, initial_balance: i64) => pesa_core::accounts::ui::create_account,
+    clear_all_data() => pesa_core::system::ui::clear_all_data,
 
     get_utility_account(id: u32) => pesa_core::accounts::utility_accounts::ui::get_utility_account,
+    get_utility_account_by_business_id(business_id: u32) => pesa_core::accounts::utility_accounts::ui::get_utility_account_by_business_id,
     get_mmf_account(id: u32) => pesa_core::accounts::mmf_accounts::ui::get_mmf_account,
+    get_mmf_account_by_business_id(business_id: u32) => pesa_core::accounts::mmf_accounts::ui::get_mmf_account_by_business_id,
 
     revenue_settlement(business_id: u32) => pesa_core::business::ui::revenue_settlement,
-    run_self_tests(#[wrap] mode: TestMode) => pesa_core::self_test::ui::run_self_tests
+    run_self_tests(#[wrap] mode: TestMode) => pesa_core::self_test::ui::run_self_tests,
+    run_selected_self_tests(#[wrap] options: SelfTestOptions) => pesa_core::self_test::ui::run_selected_self_tests,
+    list_self_tests() => pesa_core::self_test::ui::list_self_tests,
+    run_contract_tests(#[wrap] options: ContractTestOptions) => pesa_core::self_test::ui::run_contract_tests,
+    list_contract_tests() => pesa_core::self_test::ui::list_contract_tests
+}
+
+/// A Lua function registered by a script, removed when that script is unloaded.
+struct Handler {
+    script: String,
+    key: RegistryKey,
+}
+
+type HandlerMap = Arc<Mutex<HashMap<String, Vec<Handler>>>>;
+
+/// Route that matches every sandbox request in `pesa.intercept`.
+const ANY_ROUTE: &str = "*";
+
+/// Owner of what is registered by code run with [`ScriptManager::execute_script`] or
+/// [`ScriptManager::evaluate_script`], which lives as long as the Lua state.
+const ANONYMOUS_SCRIPT: &str = "";
+
+/// Directory inside `scripts/` whose scripts run when the host starts.
+pub const AUTORUN_DIR: &str = "autorun";
+
+/// Everything scripts register with `pesa`, tagged with the script that registered it.
+#[derive(Clone, Default)]
+struct Registrations {
+    listeners: HandlerMap,
+    routes: HandlerMap,
+    callback_hooks: Arc<Mutex<Vec<Handler>>>,
+    timers: Arc<Timers>,
+}
+
+impl Registrations {
+    /// Adds `pesa.event`, `pesa.intercept`, `pesa.callbacks` and `pesa.timer` to `pesa`, so
+    /// whatever they register belongs to `script`.
+    fn register(&self, lua: &Lua, pesa: &Table, script: &str) -> LuaResult<()> {
+        let event_table = lua.create_table()?;
+        let listen_listeners = self.listeners.clone();
+        let listen_script = script.to_string();
+        let listen_fn =
+            lua.create_function(move |lua, (event_name, handler): (String, Function)| {
+                let handler = Handler {
+                    script: listen_script.clone(),
+                    key: lua.create_registry_value(handler)?,
+                };
+                let mut listeners_lock = listen_listeners.lock().unwrap();
+                listeners_lock.entry(event_name).or_default().push(handler);
+                Ok(())
+            })?;
+        event_table.set("listen", listen_fn)?;
+        pesa.set("event", event_table)?;
+
+        // pesa.intercept(route, fn) runs fn before the handler of `route` ("*" for all),
+        // pesa.intercept(route, nil) removes the script's interceptors of `route`
+        let intercept_routes = self.routes.clone();
+        let intercept_script = script.to_string();
+        let intercept_fn =
+            lua.create_function(move |lua, (route, handler): (String, Option<Function>)| {
+                let mut routes = intercept_routes.lock().unwrap();
+                match handler {
+                    Some(handler) => routes.entry(route).or_default().push(Handler {
+                        script: intercept_script.clone(),
+                        key: lua.create_registry_value(handler)?,
+                    }),
+                    None => {
+                        if let Some(handlers) = routes.get_mut(&route) {
+                            handlers.retain(|h| h.script != intercept_script);
+                            if handlers.is_empty() {
+                                routes.remove(&route);
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            })?;
+        pesa.set("intercept", intercept_fn)?;
+
+        // pesa.callbacks.before_dispatch(fn) runs fn on every outgoing callback,
+        // pesa.callbacks.before_dispatch(nil) removes the script's hooks
+        let callbacks_table = lua.create_table()?;
+        let before_dispatch_hooks = self.callback_hooks.clone();
+        let before_dispatch_script = script.to_string();
+        let before_dispatch_fn = lua.create_function(move |lua, handler: Option<Function>| {
+            let mut hooks = before_dispatch_hooks.lock().unwrap();
+            match handler {
+                Some(handler) => hooks.push(Handler {
+                    script: before_dispatch_script.clone(),
+                    key: lua.create_registry_value(handler)?,
+                }),
+                None => hooks.retain(|h| h.script != before_dispatch_script),
+            }
+            Ok(())
+        })?;
+        callbacks_table.set("before_dispatch", before_dispatch_fn)?;
+        pesa.set("callbacks", callbacks_table)?;
+
+        timers::register(lua, pesa, self.timers.clone(), script)
+    }
+
+    /// Tears down the listeners, interceptors, hooks and timers of `script`.
+    fn remove(&self, script: &str) {
+        for map in [&self.listeners, &self.routes] {
+            let mut map = map.lock().unwrap();
+            for handlers in map.values_mut() {
+                handlers.retain(|h| h.script != script);
+            }
+            // an empty route would still make the sandbox buffer its requests
+            map.retain(|_, handlers| !handlers.is_empty());
+        }
+        self.callback_hooks
+            .lock()
+            .unwrap()
+            .retain(|h| h.script != script);
+        self.timers.cancel_script(script);
+    }
 }
 
-type ListenerMap = Arc<Mutex<HashMap<String, Vec<RegistryKey>>>>;
+/// Runs the `pesa.intercept` handlers of the scripts for sandbox requests.
+struct LuaInterceptor {
+    lua: Arc<Lua>,
+    routes: HandlerMap,
+}
 
+impl LuaInterceptor {
+    fn handlers(&self, path: &str) -> LuaResult<Vec<Function>> {
+        let routes = self.routes.lock().unwrap();
+        [path, ANY_ROUTE]
+            .iter()
+            .filter_map(|route| routes.get(*route))
+            .flatten()
+            .map(|handler| self.lua.registry_value::<Function>(&handler.key))
+            .collect()
+    }
+}
+
+/// Runs the `pesa.callbacks.before_dispatch` hooks, in the order they were registered.
+struct LuaCallbackHook {
+    lua: Arc<Lua>,
+    hooks: Arc<Mutex<Vec<Handler>>>,
+}
+
+impl CallbackHook for LuaCallbackHook {
+    fn before_dispatch(
+        &self,
+        callback: CreateCallbackParams,
+    ) -> BoxFuture<'static, Result<HookOutcome>> {
+        let lua = self.lua.clone();
+        let handlers: LuaResult<Vec<Function>> = self
+            .hooks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|handler| self.lua.registry_value::<Function>(&handler.key))
+            .collect();
+
+        Box::pin(async move {
+            let mut outcome = HookOutcome::from(callback);
+            for handler in handlers? {
+                // hooks may edit the callback in place, return a new one or false to drop it
+                let callback: Table =
+                    lua.to_value(&outcome)?.as_table().cloned().ok_or_else(|| {
+                        anyhow::anyhow!("Failed to convert callback for Lua hook")
+                    })?;
+                outcome = match handler.call_async::<Value>(callback.clone()).await? {
+                    Value::Nil => lua.from_value(Value::Table(callback))?,
+                    Value::Table(rewritten) => lua.from_value(Value::Table(rewritten))?,
+                    Value::Boolean(false) => HookOutcome {
+                        drop: true,
+                        ..outcome
+                    },
+                    other => {
+                        return Err(anyhow::anyhow!(
+                            "Callback hook must return nil, false or a callback table, got {}",
+                            other.type_name()
+                        ));
+                    }
+                };
+                if outcome.drop {
+                    break;
+                }
+            }
+            Ok(outcome)
+        })
+    }
+}
+
+impl RequestInterceptor for LuaInterceptor {
+    fn handles(&self, _project_id: u32, path: &str) -> bool {
+        let routes = self.routes.lock().unwrap();
+        routes.contains_key(path) || routes.contains_key(ANY_ROUTE)
+    }
+
+    fn intercept(&self, request: InterceptedRequest) -> BoxFuture<'static, Result<Interception>> {
+        let lua = self.lua.clone();
+        let handlers = self.handlers(&request.path);
+
+        Box::pin(async move {
+            let mut request = request;
+            for handler in handlers? {
+                // handlers may edit the request table in place and return nothing to forward it
+                let req: Table = lua.to_value(&request)?.as_table().cloned().ok_or_else(|| {
+                    anyhow::anyhow!("Failed to convert request for Lua interceptor")
+                })?;
+                match handler.call_async::<Value>(req.clone()).await? {
+                    Value::Nil => request = lua.from_value(Value::Table(req))?,
+                    Value::Table(response) => {
+                        return Ok(Interception::Respond(
+                            lua.from_value(Value::Table(response))?,
+                        ));
+                    }
+                    other => {
+                        return Err(anyhow::anyhow!(
+                            "Interceptor for '{}' must return nil or a response table, got {}",
+                            request.path,
+                            other.type_name()
+                        ));
+                    }
+                }
+            }
+            Ok(Interception::Forward(request))
+        })
+    }
+}
+
+/// Clones share the Lua state and what the scripts registered, so a clone can run a script
+/// without holding the lock of the manager.
+#[derive(Clone)]
 pub struct ScriptManager {
     lua: Arc<Lua>,
     scripts_dir: PathBuf,
-    listeners: ListenerMap,
-    has_listeners: Arc<Mutex<bool>>,
+    registrations: Registrations,
+    /// Scripts started with [`ScriptManager::run_script`] and not unloaded since, including
+    /// ones whose last run failed so that fixing them reloads them.
+    loaded: Arc<Mutex<BTreeSet<String>>>,
 }
 
 // Helper function to normalize script names
 fn normalize_name(name: &str) -> String {
-    // Simple normalization: lowercase, replace spaces with hyphens, and add .lua extension
-    // A more robust solution might use a slugify library.
-    format!("{}.lua", name.to_lowercase().replace(' ', "-"))
+    format!("{}.lua", script_id(name))
+}
+
+// Simple normalization: lowercase and replace spaces with hyphens.
+// A more robust solution might use a slugify library.
+fn script_id(name: &str) -> String {
+    name.to_lowercase().replace(' ', "-")
 }
 
 impl ScriptManager {
@@ -143,10 +432,9 @@ impl ScriptManager {
         let lua = Arc::new(Lua::new());
         let app_context_clone = app_context.clone();
         let scripts_dir = data_dir.join("scripts");
-        fs::create_dir_all(&scripts_dir)?;
+        fs::create_dir_all(scripts_dir.join(AUTORUN_DIR))?;
 
-        let listeners: ListenerMap = Arc::new(Mutex::new(HashMap::new()));
-        let has_listeners = Arc::new(Mutex::new(false));
+        let registrations = Registrations::default();
 
         let res: Result<(), mlua::Error> = (|| {
             // Store AppContext in the registry
@@ -157,24 +445,11 @@ impl ScriptManager {
             let pesa_table = lua.create_table()?;
             lua.globals().set("pesa", pesa_table.clone())?;
 
-            // Create 'pesa.event' table
-            let event_table = lua.create_table()?;
-            let listen_listeners = listeners.clone();
-            let listen_has_listeners = has_listeners.clone();
-
-            let listen_fn =
-                lua.create_function(move |lua, (event_name, handler): (String, Function)| {
-                    let registry_key = lua.create_registry_value(handler)?;
-                    let mut listeners_lock = listen_listeners.lock().unwrap();
-                    listeners_lock
-                        .entry(event_name)
-                        .or_default()
-                        .push(registry_key);
-                    *listen_has_listeners.lock().unwrap() = true;
-                    Ok(())
-                })?;
-            event_table.set("listen", listen_fn)?;
-            pesa_table.set("event", event_table)?;
+            // Saved scripts get their own copy of these, see `ScriptManager::script_env`
+            registrations.register(&lua, &pesa_table, ANONYMOUS_SCRIPT)?;
+            timers::register_sleep(&lua, &pesa_table)?;
+            http::register(&lua, &pesa_table, app_context.clone())?;
+            encoding::register(&lua, &pesa_table)?;
 
             // Call the function generated by the macro to populate the 'pesa' table
             register_lua_bindings(lua.as_ref())?;
@@ -188,18 +463,37 @@ impl ScriptManager {
             return Err(anyhow::anyhow!("Failed to initialize Lua context: {}", e));
         }
 
+        app_context.interceptors.set(Arc::new(LuaInterceptor {
+            lua: lua.clone(),
+            routes: registrations.routes.clone(),
+        }));
+        app_context.callback_hooks.set(Arc::new(LuaCallbackHook {
+            lua: lua.clone(),
+            hooks: registrations.callback_hooks.clone(),
+        }));
+
         let manager = Self {
             lua,
             scripts_dir,
-            listeners,
-            has_listeners,
+            registrations,
+            loaded: Default::default(),
         };
 
         Ok(Arc::new(TokioMutex::new(manager)))
     }
 
     pub fn has_listeners(&self) -> bool {
-        *self.has_listeners.lock().unwrap()
+        self.registrations
+            .listeners
+            .lock()
+            .unwrap()
+            .values()
+            .any(|handlers| !handlers.is_empty())
+    }
+
+    /// The timers started with `pesa.timer`, which keep a headless host running.
+    pub fn timers(&self) -> Arc<Timers> {
+        self.registrations.timers.clone()
     }
 
     pub async fn execute_script(&self, script_code: &str) -> Result<String> {
@@ -214,6 +508,15 @@ impl ScriptManager {
         }
     }
 
+    /// Runs `script_code` and returns its raw result, for hosts that map it to an exit status.
+    pub async fn evaluate_script(&self, script_code: &str) -> Result<Value> {
+        self.lua
+            .load(script_code)
+            .eval_async()
+            .await
+            .map_err(|e| anyhow::anyhow!("Lua script error: {}", e))
+    }
+
     pub fn list_scripts(&self) -> Result<Vec<String>> {
         let mut scripts = Vec::new();
         for entry in fs::read_dir(&self.scripts_dir)? {
@@ -230,25 +533,207 @@ impl ScriptManager {
     }
 
     pub fn read_script(&self, name: &str) -> Result<String> {
-        let path = self.scripts_dir.join(normalize_name(name));
+        let path = self.script_path(name);
         Ok(fs::read_to_string(path)?)
     }
 
     pub fn save_script(&self, name: &str, content: &str) -> Result<()> {
-        let path = self.scripts_dir.join(normalize_name(name));
+        let path = self.script_path(name);
         Ok(fs::write(path, content)?)
     }
 
     pub fn delete_script(&self, name: &str) -> Result<()> {
+        let path = self.script_path(name);
+        fs::remove_file(path)?;
+        self.unload_script(name);
+        Ok(())
+    }
+
+    /// Runs the saved script `name` in its own environment, replacing what an earlier run of it
+    /// registered. Its listeners, interceptors, hooks and timers stay until it is unloaded.
+    pub async fn run_script(&self, name: &str) -> Result<()> {
+        let id = script_id(name);
+        let path = self.script_path(name);
+        let script_code = fs::read_to_string(&path)?;
+
+        self.unload_script(&id);
+        self.loaded.lock().unwrap().insert(id.clone());
+
+        let result = async {
+            let env = self.script_env(&id)?;
+            self.lua
+                .load(script_code)
+                .set_name(format!("@{}", path.display()))
+                .set_environment(env)
+                .exec_async()
+                .await
+        }
+        .await;
+
+        result.map_err(|e| {
+            // don't leave the half of the script that ran before the error behind
+            self.registrations.remove(&id);
+            anyhow::anyhow!("Lua script '{}' error: {}", id, e)
+        })
+    }
+
+    /// Tears down everything the script `name` registered, without touching other scripts.
+    /// Returns false when the script was not loaded.
+    pub fn unload_script(&self, name: &str) -> bool {
+        let id = script_id(name);
+        self.registrations.remove(&id);
+        self.lua.expire_registry_values();
+        self.loaded.lock().unwrap().remove(&id)
+    }
+
+    pub fn loaded_scripts(&self) -> Vec<String> {
+        self.loaded.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// The file of the script `name`. Saved scripts have normalized file names, scripts put in
+    /// the folder by hand are found by the name they normalize to.
+    fn script_path(&self, name: &str) -> PathBuf {
         let path = self.scripts_dir.join(normalize_name(name));
-        Ok(fs::remove_file(path)?)
+        if path.exists() {
+            return path;
+        }
+        let id = script_id(name);
+        let stem = id.rsplit('/').next().unwrap_or(&id);
+        let Some(dir) = path.parent() else {
+            return path;
+        };
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .find(|candidate| {
+                candidate.extension().and_then(|s| s.to_str()) == Some("lua")
+                    && candidate
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .is_some_and(|candidate| script_id(candidate) == stem)
+            })
+            .unwrap_or(path)
+    }
+
+    /// The scripts in `scripts/autorun`, named `autorun/<name>` with the name normalized like
+    /// [`ScriptManager::run_script`] does, in the order they run.
+    pub fn autorun_scripts(&self) -> Result<Vec<String>> {
+        let mut scripts = Vec::new();
+        for entry in fs::read_dir(self.scripts_dir.join(AUTORUN_DIR))? {
+            let path = entry?.path();
+            if path.is_file()
+                && path.extension().and_then(|s| s.to_str()) == Some("lua")
+                && let Some(filename) = path.file_stem().and_then(|s| s.to_str())
+            {
+                scripts.push(script_id(&format!("{}/{}", AUTORUN_DIR, filename)));
+            }
+        }
+        scripts.sort();
+        Ok(scripts)
+    }
+
+    /// Runs the autorun scripts, logging the ones that fail so the others still start.
+    pub async fn autorun(&self) -> Result<()> {
+        for name in self.autorun_scripts()? {
+            match self.run_script(&name).await {
+                Ok(()) => info!("Started script {}", name),
+                Err(e) => error!("{}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// The file of every script to watch for changes: the loaded ones and the autorun ones.
+    pub fn watched_scripts(&self) -> Result<Vec<(String, PathBuf)>> {
+        let mut names: BTreeSet<String> = self.autorun_scripts()?.into_iter().collect();
+        names.extend(self.loaded_scripts());
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let path = self.script_path(&name);
+                (name, path)
+            })
+            .collect())
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.loaded.lock().unwrap().contains(&script_id(name))
+    }
+
+    /// Runs the `pesa.test` cases declared by `files`, each file in its own environment.
+    /// Callbacks the tests expect are captured by a [`CallbackManager`] shared by the run.
+    pub async fn run_tests(&self, files: &[PathBuf], options: &TestOptions) -> Result<TestReport> {
+        let callbacks = Arc::new(CallbackManager::new(options.callback_timeout).await?);
+        let mut report = TestReport::new("ppg-script");
+        for file in files {
+            let id = format!("test/{}", file.display());
+            let env = self.script_env(&id)?;
+            let suite = testing::run_file(&self.lua, env, file, &callbacks, options).await;
+            // listeners and timers started by one file must not leak into the next
+            self.registrations.remove(&id);
+            report.suites.push(suite);
+        }
+        Ok(report)
+    }
+
+    /// The globals of a saved script: its own `pesa.event`, `pesa.intercept`, `pesa.callbacks`
+    /// and `pesa.timer` so its registrations can be torn down, and everything else shared.
+    fn script_env(&self, script: &str) -> LuaResult<Table> {
+        let globals = self.lua.globals();
+        let shared_pesa: Table = globals.get("pesa")?;
+
+        let pesa = self.lua.create_table()?;
+        self.registrations.register(&self.lua, &pesa, script)?;
+        pesa.set_metatable(Some(
+            self.lua.create_table_from([("__index", shared_pesa)])?,
+        ))?;
+
+        let env = self.lua.create_table()?;
+        env.set("pesa", pesa)?;
+        env.set_metatable(Some(self.lua.create_table_from([("__index", globals)])?))?;
+        Ok(env)
+    }
+
+    /// A handle delivering host events to the `pesa.event.listen` handlers.
+    ///
+    /// Unlike [`ScriptManager::emit_event`] it does not need the manager lock, so events reach
+    /// listeners while a script is still running.
+    pub fn dispatcher(&self) -> EventDispatcher {
+        EventDispatcher {
+            lua: self.lua.clone(),
+            listeners: self.registrations.listeners.clone(),
+        }
+    }
+
+    /// Registers `pesa.exit(code)`, letting a script end a headless host with `code`.
+    pub fn set_exit_handler(&self, sender: UnboundedSender<i64>) -> Result<()> {
+        let exit_fn = self.lua.create_function(move |_, code: Option<i64>| {
+            let _ = sender.send(code.unwrap_or(0));
+            Ok(())
+        })?;
+        let pesa: Table = self.lua.globals().get("pesa")?;
+        pesa.set("exit", exit_fn)?;
+        Ok(())
     }
 
     pub async fn emit_event(&self, event_name: &str, payload: serde_json::Value) {
+        self.dispatcher().emit(event_name, payload);
+    }
+}
+
+#[derive(Clone)]
+pub struct EventDispatcher {
+    lua: Arc<Lua>,
+    listeners: HandlerMap,
+}
+
+impl EventDispatcher {
+    pub fn emit(&self, event_name: &str, payload: serde_json::Value) {
         let listeners_lock = self.listeners.lock().unwrap();
         if let Some(handlers_ref) = listeners_lock.get(event_name) {
-            for handler_key in handlers_ref {
-                match self.lua.registry_value::<Function>(handler_key) {
+            for handler in handlers_ref {
+                match self.lua.registry_value::<Function>(&handler.key) {
                     Ok(func) => match self.lua.to_value(&payload) {
                         Ok(lua_payload) => {
                             let event_name_clone = event_name.to_string();
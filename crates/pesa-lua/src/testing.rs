@@ -0,0 +1,235 @@
+//! `pesa.test` scenarios, run by `ppg-script test`.
+//!
+//! ```lua
+//! pesa.test("stk happy path", function(t)
+//!     local url = t.callback_url("/stk")
+//!     -- ... trigger an STK push with `url` as its CallBackURL
+//!     local callback = t.expect_callback("/stk")
+//!     t.eq(callback.Body.stkCallback.ResultCode, 0)
+//! end)
+//! ```
+use mlua::{Function, Lua, LuaSerdeExt, RegistryKey, Result as LuaResult, Table, Value};
+use pesa_core::self_test::{
+    callback::{CallbackEntry, CallbackManager},
+    emitter::TestStatus,
+    report::{TestCaseReport, TestSuiteReport},
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+/// Limits for a test run.
+#[derive(Debug, Clone)]
+pub struct TestOptions {
+    /// How long a single test may run before it is failed as timed out.
+    pub test_timeout: Duration,
+    /// How long `t.expect_callback` waits for a callback.
+    pub callback_timeout: Duration,
+}
+
+impl Default for TestOptions {
+    fn default() -> Self {
+        Self {
+            test_timeout: Duration::from_secs(60),
+            callback_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+type DeclaredTests = Arc<Mutex<Vec<(String, RegistryKey)>>>;
+
+/// Loads `file` in `env` and runs the tests it declares with `pesa.test`, in order.
+pub(crate) async fn run_file(
+    lua: &Lua,
+    env: Table,
+    file: &Path,
+    callbacks: &Arc<CallbackManager>,
+    options: &TestOptions,
+) -> TestSuiteReport {
+    let mut suite = TestSuiteReport::new(file.display().to_string());
+    let declared: DeclaredTests = Default::default();
+
+    let started = Instant::now();
+    let loaded = async {
+        let pesa: Table = env.get("pesa")?;
+        pesa.set("test", declare_fn(lua, declared.clone())?)?;
+        let code = std::fs::read_to_string(file)?;
+        lua.load(code)
+            .set_name(format!("@{}", file.display()))
+            .set_environment(env)
+            .exec_async()
+            .await
+    }
+    .await;
+    if let Err(e) = loaded {
+        // a file that doesn't load fails as a whole, its tests are unknown
+        suite.cases.push(TestCaseReport {
+            name: "(load)".to_string(),
+            status: TestStatus::Failed,
+            message: failure_message(&e),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+        return suite;
+    }
+
+    let tests = std::mem::take(&mut *declared.lock().unwrap());
+    for (name, key) in tests {
+        let started = Instant::now();
+        let run = async {
+            let test: Function = lua.registry_value(&key)?;
+            let t = test_handle(lua, callbacks.clone())?;
+            test.call_async::<()>(t).await
+        };
+        let (status, message) = match tokio::time::timeout(options.test_timeout, run).await {
+            Ok(Ok(())) => (TestStatus::Passed, String::new()),
+            Ok(Err(e)) => (TestStatus::Failed, failure_message(&e)),
+            Err(_) => (
+                TestStatus::TimedOut,
+                format!("Timed out after {:?}", options.test_timeout),
+            ),
+        };
+        info!("{:?}: {} ({})", status, name, file.display());
+        suite.cases.push(TestCaseReport {
+            name,
+            status,
+            message,
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+    suite
+}
+
+/// `pesa.test(name, fn)`, collecting the tests of a file until it has loaded.
+fn declare_fn(lua: &Lua, declared: DeclaredTests) -> LuaResult<Function> {
+    lua.create_function(move |lua, (name, test): (String, Function)| {
+        let key = lua.create_registry_value(test)?;
+        declared.lock().unwrap().push((name, key));
+        Ok(())
+    })
+}
+
+/// The `t` handed to a test: callback capture and assertions.
+fn test_handle(lua: &Lua, callbacks: Arc<CallbackManager>) -> LuaResult<Table> {
+    let t = lua.create_table()?;
+    let pending: Arc<Mutex<HashMap<String, CallbackEntry<serde_json::Value>>>> = Default::default();
+
+    // t.callback_url(path) returns a URL whose next request `t.expect_callback(path)` receives
+    let register_pending = pending.clone();
+    let callback_url_fn = lua.create_function(move |_, path: String| {
+        let entry = callbacks
+            .register_callback::<serde_json::Value>(path.clone())
+            .map_err(mlua::Error::external)?;
+        let url = entry.url().to_string();
+        register_pending.lock().unwrap().insert(path, entry);
+        Ok(url)
+    })?;
+    t.set("callback_url", callback_url_fn)?;
+
+    // t.expect_callback(path, timeout_ms) waits for the callback and returns its JSON body
+    let expect_callback_fn =
+        lua.create_async_function(move |lua, (path, timeout_ms): (String, Option<u64>)| {
+            let entry = pending.lock().unwrap().remove(&path);
+            async move {
+                let entry = entry.ok_or_else(|| {
+                    mlua::Error::runtime(format!(
+                        "No callback registered for '{}', call t.callback_url first",
+                        path
+                    ))
+                })?;
+                let call = match timeout_ms {
+                    Some(ms) => tokio::time::timeout(Duration::from_millis(ms), entry)
+                        .await
+                        .map_err(|_| {
+                            mlua::Error::runtime(format!(
+                                "Timed out after {}ms waiting for callback at '{}'",
+                                ms, path
+                            ))
+                        })?,
+                    None => entry.await,
+                }
+                .map_err(|e| mlua::Error::runtime(format!("{:#}", e)))?;
+
+                let body = lua.to_value(&call.body)?;
+                call.respond(
+                    StatusCode::OK,
+                    &json!({"ResultCode": 0, "ResultDesc": "Accepted"}),
+                    None,
+                )
+                .await
+                .map_err(mlua::Error::external)?;
+                Ok(body)
+            }
+        })?;
+    t.set("expect_callback", expect_callback_fn)?;
+
+    // t.expect(value, message) fails the test when value is nil or false
+    let expect_fn = lua.create_function(|lua, (value, message): (Value, Option<String>)| {
+        if matches!(value, Value::Nil | Value::Boolean(false)) {
+            return Err(assertion_failed(
+                lua,
+                message.unwrap_or_else(|| "expected a truthy value".to_string()),
+            ));
+        }
+        Ok(())
+    })?;
+    t.set("expect", expect_fn)?;
+
+    // t.eq(actual, expected, message) compares tables by their contents
+    let eq_fn = lua.create_function(
+        |lua, (actual, expected, message): (Value, Value, Option<String>)| {
+            let actual: serde_json::Value = lua.from_value(actual)?;
+            let expected: serde_json::Value = lua.from_value(expected)?;
+            if actual != expected {
+                let detail = format!("expected {}, got {}", expected, actual);
+                return Err(assertion_failed(
+                    lua,
+                    match message {
+                        Some(message) => format!("{}: {}", message, detail),
+                        None => detail,
+                    },
+                ));
+            }
+            Ok(())
+        },
+    )?;
+    t.set("eq", eq_fn)?;
+
+    let fail_fn = lua.create_function(|lua, message: Option<String>| -> LuaResult<()> {
+        Err(assertion_failed(
+            lua,
+            message.unwrap_or_else(|| "failed".to_string()),
+        ))
+    })?;
+    t.set("fail", fail_fn)?;
+
+    Ok(t)
+}
+
+/// An assertion error pointing at the line of the test that made it.
+fn assertion_failed(lua: &Lua, message: String) -> mlua::Error {
+    let location = lua
+        .inspect_stack(1, |debug| {
+            let source = debug.source().short_src?.into_owned();
+            Some(format!("{}:{}", source, debug.current_line()?))
+        })
+        .flatten();
+    mlua::Error::runtime(match location {
+        Some(location) => format!("{}: {}", location, message),
+        None => message,
+    })
+}
+
+/// The message of the error that failed a test, without the Rust callback wrapping.
+fn failure_message(error: &mlua::Error) -> String {
+    match error {
+        mlua::Error::CallbackError { cause, .. } => failure_message(cause),
+        mlua::Error::RuntimeError(message) => message.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,163 @@
+//! `pesa.timer` and `pesa.sleep`, driven by the tokio runtime.
+use mlua::{Function, Lua, Result as LuaResult, Table, UserData, UserDataMethods};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{Notify, futures::Notified},
+    time::{Instant, MissedTickBehavior},
+};
+use tracing::error;
+
+/// The timers started by scripts, so hosts can cancel them and know when they are all done.
+#[derive(Default)]
+pub struct Timers {
+    next_id: AtomicU64,
+    /// The script that started each timer, and the notify waking it to stop. A running
+    /// callback always completes.
+    active: Mutex<HashMap<u64, (String, Arc<Notify>)>>,
+    changed: Notify,
+}
+
+impl Timers {
+    /// Number of timers still scheduled.
+    pub fn active(&self) -> usize {
+        self.active.lock().unwrap().len()
+    }
+
+    /// Resolves once a timer finishes or is cancelled.
+    pub fn changed(&self) -> Notified<'_> {
+        self.changed.notified()
+    }
+
+    pub fn cancel_all(&self) {
+        for (_, (_, cancel)) in self.active.lock().unwrap().drain() {
+            cancel.notify_one();
+        }
+        self.changed.notify_one();
+    }
+
+    /// Cancels the timers started by `script`.
+    pub fn cancel_script(&self, script: &str) {
+        self.active.lock().unwrap().retain(|_, (owner, cancel)| {
+            if owner != script {
+                return true;
+            }
+            cancel.notify_one();
+            false
+        });
+        self.changed.notify_one();
+    }
+
+    fn spawn(
+        self: &Arc<Self>,
+        script: &str,
+        delay: Duration,
+        repeat: bool,
+        func: Function,
+    ) -> TimerHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancel = Arc::new(Notify::new());
+        self.active
+            .lock()
+            .unwrap()
+            .insert(id, (script.to_string(), cancel.clone()));
+
+        let timers = self.clone();
+        tokio::spawn(async move {
+            // `after(0, fn)` is allowed, intervals can't be zero
+            let period = delay.max(Duration::from_millis(1));
+            let mut interval = tokio::time::interval_at(Instant::now() + delay, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = cancel.notified() => break,
+                }
+                if let Err(e) = func.call_async::<()>(()).await {
+                    error!("Error executing Lua timer {}: {:?}", id, e);
+                }
+                if !repeat {
+                    break;
+                }
+            }
+            timers.cancel(id);
+        });
+
+        TimerHandle {
+            id,
+            timers: self.clone(),
+        }
+    }
+
+    /// Stops the timer, returning false when it already fired or was cancelled.
+    fn cancel(&self, id: u64) -> bool {
+        let Some((_, cancel)) = self.active.lock().unwrap().remove(&id) else {
+            return false;
+        };
+        cancel.notify_one();
+        self.changed.notify_one();
+        true
+    }
+}
+
+/// Returned by `pesa.timer.after` and `pesa.timer.every`.
+struct TimerHandle {
+    id: u64,
+    timers: Arc<Timers>,
+}
+
+impl UserData for TimerHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        // returns false when the timer already fired or was cancelled
+        methods.add_method("cancel", |_, this, ()| Ok(this.timers.cancel(this.id)));
+        methods.add_method("active", |_, this, ()| {
+            Ok(this.timers.active.lock().unwrap().contains_key(&this.id))
+        });
+    }
+}
+
+/// Registers `pesa.sleep(ms)`.
+pub(crate) fn register_sleep(lua: &Lua, pesa: &Table) -> LuaResult<()> {
+    let sleep_fn = lua.create_async_function(|_, ms: u64| async move {
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        Ok(())
+    })?;
+    pesa.set("sleep", sleep_fn)
+}
+
+/// Registers `pesa.timer.after(ms, fn)` and `pesa.timer.every(ms, fn)`, owned by `script`.
+pub(crate) fn register(
+    lua: &Lua,
+    pesa: &Table,
+    timers: Arc<Timers>,
+    script: &str,
+) -> LuaResult<()> {
+    let timer_table = lua.create_table()?;
+
+    let after_timers = timers.clone();
+    let after_script = script.to_string();
+    let after_fn = lua.create_function(move |_, (ms, func): (u64, Function)| {
+        Ok(after_timers.spawn(&after_script, Duration::from_millis(ms), false, func))
+    })?;
+    timer_table.set("after", after_fn)?;
+
+    let script = script.to_string();
+    let every_fn = lua.create_function(move |_, (ms, func): (u64, Function)| {
+        if ms == 0 {
+            return Err(mlua::Error::runtime(
+                "pesa.timer.every needs an interval above 0ms",
+            ));
+        }
+        Ok(timers.spawn(&script, Duration::from_millis(ms), true, func))
+    })?;
+    timer_table.set("every", every_fn)?;
+
+    pesa.set("timer", timer_table)?;
+    Ok(())
+}
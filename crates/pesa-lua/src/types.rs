@@ -5,18 +5,31 @@ wrap_core_types! {
     UpdateProject from pesa_core::projects,
     CreateBusiness from pesa_core::business,
     UpdateBusiness from pesa_core::business,
+    CreateOperatorPayload from pesa_core::business_operators::ui,
     CreatePaybillAccount from pesa_core::accounts::paybill_accounts,
     UpdatePaybillAccount from pesa_core::accounts::paybill_accounts,
     CreateTillAccount from pesa_core::accounts::till_accounts,
     UpdateTillAccount from pesa_core::accounts::till_accounts,
     TransactionFilter from pesa_core::transactions::ui,
+    HistoryFilter from pesa_core::transactions_log::ui,
     LipaArgs from pesa_core::transactions::ui,
     TransactionType from pesa_core::transactions,
     UpdateApiLogRequest from pesa_core::api_logs,
     ApiLogFilter from pesa_core::api_logs::ui,
+    InboxFilter from pesa_core::inbox::ui,
+    CannedResponse from pesa_core::inbox,
+    RetentionPolicy from pesa_core::logs,
+    LogSearch from pesa_core::logs::ui,
+    ReplayOptions from pesa_core::recording::replay,
     TransactionCostData from pesa_core::transaction_costs::ui,
+    TariffImport from pesa_core::transaction_costs::ui,
+    TariffExport from pesa_core::transaction_costs::ui,
     UserResponse from pesa_core::server::api::stkpush::ui,
     AccountType from pesa_core::accounts,
     TransactionNote from pesa_core::transactions,
+    ReversalOptions from pesa_core::transactions,
     TestMode from pesa_core::self_test::context,
+    SelfTestOptions from pesa_core::self_test::ui,
+    ContractTestOptions from pesa_core::self_test::ui,
+    AppSettings from pesa_core::settings::models,
 }
@@ -0,0 +1,59 @@
+//! Hot reload of saved scripts, by polling the modification time of their files.
+use crate::ScriptManager;
+use std::{collections::HashMap, fs, sync::Arc, time::Duration, time::SystemTime};
+use tokio::sync::Mutex as TokioMutex;
+use tracing::{error, info};
+
+/// How often hosts check the scripts for changes.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reloads loaded scripts when their file changes and unloads them when it is deleted. Scripts
+/// added to `scripts/autorun` while the host runs are started.
+pub async fn watch(manager: Arc<TokioMutex<ScriptManager>>, period: Duration) {
+    let mut modified: HashMap<String, SystemTime> = HashMap::new();
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+        let (reloader, changed) = {
+            let manager = manager.lock().await;
+            let scripts = match manager.watched_scripts() {
+                Ok(scripts) => scripts,
+                Err(e) => {
+                    error!("Failed to list scripts to watch: {}", e);
+                    continue;
+                }
+            };
+
+            let mut changed = Vec::new();
+            for (name, path) in scripts {
+                let Ok(current) = fs::metadata(&path).and_then(|m| m.modified()) else {
+                    modified.remove(&name);
+                    if manager.unload_script(&name) {
+                        info!("Script {} was deleted, unloaded it", name);
+                    }
+                    continue;
+                };
+
+                let previous = modified.insert(name.clone(), current);
+                let is_changed = match previous {
+                    Some(previous) => previous != current,
+                    // seen for the first time: loaded scripts just ran, new autorun ones haven't
+                    None => !manager.is_loaded(&name),
+                };
+                if is_changed {
+                    changed.push(name);
+                }
+            }
+            (manager.clone(), changed)
+        };
+
+        // scripts may run for a while, hosts keep using the manager meanwhile
+        for name in changed {
+            match reloader.run_script(&name).await {
+                Ok(()) => info!("Reloaded script {}", name),
+                Err(e) => error!("{}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#![allow(dead_code)]
+
+use pesa_core::{AppContext, migrations::Migrator};
+use pesa_lua::ScriptManager;
+use sea_orm::Database;
+use sea_orm_migration::MigratorTrait;
+use std::sync::Arc;
+use tokio::sync::Mutex as TokioMutex;
+
+/// Discards the events emitted by the core.
+pub struct NoopEvents;
+
+impl pesa_core::AppEventManager for NoopEvents {
+    fn emit_all(&self, _event: &str, _payload: serde_json::Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// A script host over a fresh database, with its data kept in `dir`.
+pub struct Host {
+    pub manager: Arc<TokioMutex<ScriptManager>>,
+    pub context: AppContext,
+    pub dir: tempfile::TempDir,
+}
+
+pub async fn host() -> anyhow::Result<Host> {
+    let dir = tempfile::tempdir()?;
+    let db = Database::connect("sqlite::memory:").await?;
+    Migrator::up(&db, None).await?;
+    pesa_core::transaction_costs::init_default_costs(&db).await?;
+    // default settings up front, generating encryption keys takes seconds in debug builds
+    let settings_path = dir.path().join("settings.json");
+    std::fs::write(
+        &settings_path,
+        serde_json::to_string(&pesa_core::settings::models::AppSettings::default())?,
+    )?;
+    let settings = pesa_core::settings::SettingsManager::new(settings_path).await?;
+
+    let context = AppContext {
+        db,
+        settings,
+        event_manager: Arc::new(NoopEvents),
+        running: Default::default(),
+        app_root: dir.path().to_path_buf(),
+        interceptors: Default::default(),
+        callback_hooks: Default::default(),
+        recordings: Default::default(),
+        metrics: Default::default(),
+    };
+    let manager = ScriptManager::new(context.clone(), dir.path())?;
+
+    Ok(Host {
+        manager,
+        context,
+        dir,
+    })
+}
+
+impl Host {
+    /// Runs `code` in the global environment and returns what it returns, as JSON.
+    pub async fn eval(&self, code: &str) -> anyhow::Result<serde_json::Value> {
+        let manager = self.manager.lock().await;
+        let value = manager.evaluate_script(code).await?;
+        Ok(serde_json::to_value(&value)?)
+    }
+}
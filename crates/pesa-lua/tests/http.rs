@@ -0,0 +1,73 @@
+mod common;
+
+use axum::{Json, Router, response::Redirect, routing::get};
+use serde_json::json;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+/// A server on 127.0.0.1 answering `/hello`, counting its hits, and redirecting `/away` to
+/// `/hello` on `localhost`.
+async fn server() -> anyhow::Result<(u16, Arc<AtomicUsize>)> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    let counter = hits.clone();
+    let app =
+        Router::new()
+            .route(
+                "/hello",
+                get(move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    async { Json(json!({ "hello": "world" })) }
+                }),
+            )
+            .route(
+                "/away",
+                get(move || async move {
+                    Redirect::temporary(&format!("http://localhost:{port}/hello"))
+                }),
+            );
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    Ok((port, hits))
+}
+
+#[tokio::test]
+async fn requests_stay_within_the_allowlist() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    let mut settings = host.context.settings.get().await;
+    settings.script_http_allowlist = vec!["127.0.0.1".to_string()];
+    host.context.settings.set(settings).await?;
+    let (port, hits) = server().await?;
+
+    let response = host
+        .eval(&format!(
+            r#"local r = pesa.http.get("http://127.0.0.1:{port}/hello")
+            return {{ status = r.status, ok = r.ok, hello = r.json.hello }}"#
+        ))
+        .await?;
+    assert_eq!(
+        response,
+        json!({ "status": 200, "ok": true, "hello": "world" })
+    );
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let error = host
+        .eval(&format!(
+            r#"pesa.http.get("http://localhost:{port}/hello")"#
+        ))
+        .await
+        .expect_err("localhost is not allowed");
+    assert!(error.to_string().contains("allowlist"), "{error}");
+
+    // the redirect is refused before it reaches the server
+    let error = host
+        .eval(&format!(r#"pesa.http.get("http://127.0.0.1:{port}/away")"#))
+        .await
+        .expect_err("the redirect leaves the allowlist");
+    assert!(error.to_string().contains("redirect"), "{error}");
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+    Ok(())
+}
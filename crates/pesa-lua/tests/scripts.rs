@@ -0,0 +1,292 @@
+mod common;
+
+use pesa_core::{
+    callbacks::{CallbackType, CreateCallbackParams},
+    self_test::emitter::TestStatus,
+    server::intercept::{InterceptedRequest, Interception},
+};
+use pesa_lua::testing::TestOptions;
+use serde_json::json;
+use std::{fs, time::Duration};
+
+fn request(path: &str, body: serde_json::Value) -> InterceptedRequest {
+    InterceptedRequest {
+        project_id: 1,
+        method: "POST".to_string(),
+        path: path.to_string(),
+        headers: Default::default(),
+        body,
+    }
+}
+
+fn callback() -> CreateCallbackParams {
+    CreateCallbackParams {
+        project_id: 1,
+        callback_type: CallbackType::StkPush,
+        url: "http://127.0.0.1:9/callback".to_string(),
+        conversation_id: "conversation".to_string(),
+        originator_id: "originator".to_string(),
+        payload: json!({ "ResultCode": 0 }),
+        transaction_id: None,
+    }
+}
+
+/// Polls `condition` until it holds, for up to two seconds.
+async fn eventually(mut condition: impl AsyncFnMut() -> bool) -> bool {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+    while tokio::time::Instant::now() < deadline {
+        if condition().await {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn json_and_base64_round_trip() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    let result = host
+        .eval(
+            r#"local decoded = pesa.json.decode('{"amount": 10, "tags": ["a", "b"]}')
+            return {
+                amount = decoded.amount,
+                second = decoded.tags[2],
+                encoded = pesa.json.encode({ ok = true }),
+                base64 = pesa.base64.encode("pesa"),
+                decoded = pesa.base64.decode("cGVzYQ=="),
+            }"#,
+        )
+        .await?;
+    assert_eq!(
+        result,
+        json!({
+            "amount": 10,
+            "second": "b",
+            "encoded": r#"{"ok":true}"#,
+            "base64": "cGVzYQ==",
+            "decoded": "pesa",
+        })
+    );
+    assert!(host.eval(r#"pesa.json.decode("{")"#).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn interceptors_rewrite_or_answer_requests() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    host.eval(
+        r#"pesa.intercept("/stk", function(req)
+            if req.body.answer then
+                return { status = 201, body = { intercepted = true } }
+            end
+            req.body.seen = true
+        end)"#,
+    )
+    .await?;
+    let interceptor = host.context.interceptors.get().expect("installed");
+    assert!(interceptor.handles(1, "/stk"));
+    assert!(!interceptor.handles(1, "/b2c"));
+
+    match interceptor.intercept(request("/stk", json!({}))).await? {
+        Interception::Forward(request) => assert_eq!(request.body, json!({ "seen": true })),
+        other => panic!("expected the request to be forwarded, got {other:?}"),
+    }
+    match interceptor
+        .intercept(request("/stk", json!({ "answer": true })))
+        .await?
+    {
+        Interception::Respond(response) => {
+            assert_eq!(response.status, 201);
+            assert_eq!(response.body, json!({ "intercepted": true }));
+        }
+        other => panic!("expected a response, got {other:?}"),
+    }
+
+    host.eval(r#"pesa.intercept("/stk", nil)"#).await?;
+    assert!(!interceptor.handles(1, "/stk"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn callback_hooks_rewrite_delay_and_drop() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    host.eval(
+        r#"pesa.callbacks.before_dispatch(function(cb)
+            cb.payload.ResultCode = 1032
+            cb.delay_ms = 50
+        end)"#,
+    )
+    .await?;
+    let hook = host.context.callback_hooks.get().expect("installed");
+
+    let outcome = hook.before_dispatch(callback()).await?;
+    assert_eq!(outcome.callback.payload, json!({ "ResultCode": 1032 }));
+    assert_eq!(outcome.delay_ms, Some(50));
+    assert!(!outcome.drop);
+
+    host.eval("pesa.callbacks.before_dispatch(function() return false end)")
+        .await?;
+    assert!(hook.before_dispatch(callback()).await?.drop);
+    Ok(())
+}
+
+#[tokio::test]
+async fn timers_fire_and_cancel() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    host.eval(
+        r#"fired, ticks = false, 0
+        pesa.timer.after(10, function() fired = true end)
+        ticker = pesa.timer.every(5, function() ticks = ticks + 1 end)"#,
+    )
+    .await?;
+    let timers = host.manager.lock().await.timers();
+    assert_eq!(timers.active(), 2);
+
+    assert!(
+        eventually(async || host.eval("return fired and ticks >= 2").await.unwrap() == json!(true))
+            .await
+    );
+    assert_eq!(host.eval("return ticker:cancel()").await?, json!(true));
+    assert_eq!(timers.active(), 0);
+    let ticks = host.eval("return ticks").await?;
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(host.eval("return ticks").await?, ticks);
+    Ok(())
+}
+
+#[tokio::test]
+async fn saved_scripts_reload_when_they_change() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    let path = host.dir.path().join("scripts/routes.lua");
+    fs::write(&path, r#"pesa.intercept("/v1", function() end)"#)?;
+    host.manager.lock().await.run_script("routes").await?;
+    let interceptor = host.context.interceptors.get().expect("installed");
+    assert!(interceptor.handles(1, "/v1"));
+
+    tokio::spawn(pesa_lua::watch::watch(
+        host.manager.clone(),
+        Duration::from_millis(10),
+    ));
+    // let the watcher see the script before it changes
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    fs::write(&path, r#"pesa.intercept("/v2", function() end)"#)?;
+    let later = fs::metadata(&path)?.modified()? + Duration::from_secs(1);
+    fs::File::options()
+        .write(true)
+        .open(&path)?
+        .set_modified(later)?;
+    assert!(eventually(async || interceptor.handles(1, "/v2")).await);
+    assert!(!interceptor.handles(1, "/v1"));
+
+    fs::remove_file(&path)?;
+    assert!(eventually(async || !interceptor.handles(1, "/v2")).await);
+    assert!(!host.manager.lock().await.is_loaded("routes"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn autorun_scripts_keep_their_file_names() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    let path = host.dir.path().join("scripts/autorun/Seed Data.lua");
+    fs::write(&path, r#"pesa.intercept("/seed", function() end)"#)?;
+
+    let manager = host.manager.lock().await.clone();
+    assert_eq!(manager.autorun_scripts()?, ["autorun/seed-data"]);
+    manager.autorun().await?;
+    assert!(manager.is_loaded("autorun/Seed Data"));
+    let interceptor = host.context.interceptors.get().expect("installed");
+    assert!(interceptor.handles(1, "/seed"));
+
+    // the watcher finds the file under its real name instead of taking it for deleted
+    tokio::spawn(pesa_lua::watch::watch(
+        host.manager.clone(),
+        Duration::from_millis(10),
+    ));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(manager.is_loaded("autorun/seed-data"));
+
+    fs::remove_file(&path)?;
+    assert!(eventually(async || !manager.is_loaded("autorun/seed-data")).await);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_files_report_each_case() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    let file = host.dir.path().join("cases.lua");
+    fs::write(
+        &file,
+        r#"pesa.test("adds up", function(t)
+            t.eq({ total = 1 + 1 }, { total = 2 })
+            t.expect(true)
+        end)
+        pesa.test("mismatch", function(t)
+            t.eq(1, 2, "amount")
+        end)
+        pesa.test("too slow", function(t)
+            pesa.sleep(1000)
+        end)"#,
+    )?;
+
+    let options = TestOptions {
+        test_timeout: Duration::from_millis(200),
+        ..Default::default()
+    };
+    let report = host
+        .manager
+        .lock()
+        .await
+        .run_tests(std::slice::from_ref(&file), &options)
+        .await?;
+
+    let cases = &report.suites[0].cases;
+    let statuses: Vec<_> = cases.iter().map(|case| case.status.clone()).collect();
+    assert_eq!(
+        statuses,
+        [TestStatus::Passed, TestStatus::Failed, TestStatus::TimedOut]
+    );
+    assert!(
+        cases[1]
+            .message
+            .contains("cases.lua:6: amount: expected 2, got 1"),
+        "{}",
+        cases[1].message
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn reloading_scripts_leave_the_manager_usable() -> anyhow::Result<()> {
+    let host = common::host().await?;
+    let path = host.dir.path().join("scripts/slow.lua");
+    fs::write(&path, "_G.started = false")?;
+    host.manager.lock().await.run_script("slow").await?;
+    tokio::spawn(pesa_lua::watch::watch(
+        host.manager.clone(),
+        Duration::from_millis(10),
+    ));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    fs::write(&path, "_G.started = true\npesa.sleep(10000)")?;
+    let later = fs::metadata(&path)?.modified()? + Duration::from_secs(1);
+    fs::File::options()
+        .write(true)
+        .open(&path)?
+        .set_modified(later)?;
+
+    // the reload is still sleeping, yet the manager can be locked to look at it
+    let started = eventually(async || {
+        match tokio::time::timeout(Duration::from_millis(50), host.manager.lock()).await {
+            Ok(manager) => {
+                let started = manager.evaluate_script("return started").await;
+                matches!(started, Ok(mlua::Value::Boolean(true)))
+            }
+            Err(_) => false,
+        }
+    })
+    .await;
+    assert!(started);
+    Ok(())
+}
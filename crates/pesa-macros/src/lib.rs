@@ -83,6 +83,19 @@ impl Parse for AxumRpcHandler {
     }
 }
 
+/// Whether `ty` is spelled `Option<..>`, so `#[wrap]` arguments can be unwrapped through it.
+fn is_option_type(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(type_path)
+        if type_path.path.segments.last().is_some_and(|segment| segment.ident == "Option"))
+}
+
+fn command_names(mappings: &Punctuated<CommandMapping, Token![,]>) -> Vec<String> {
+    mappings
+        .iter()
+        .map(|mapping| mapping.command.to_string())
+        .collect()
+}
+
 fn to_camel_case(s: &str) -> String {
     s.split('_')
         .filter(|seg| !seg.is_empty())
@@ -146,8 +159,12 @@ pub fn generate_tauri_wrappers(input: TokenStream) -> TokenStream {
         }
     });
 
+    let command_names = command_names(&mappings);
     let expanded = quote! {
         #(#generated_functions)*
+
+        /// The commands wrapped for Tauri, see `assert_commands_match!`.
+        pub const TAURI_COMMANDS: &[&str] = &[#(#command_names),*];
     };
 
     TokenStream::from(expanded)
@@ -262,7 +279,11 @@ pub fn generate_axum_rpc_handler(input: TokenStream) -> TokenStream {
         }
     });
 
+    let command_names = command_names(&mappings);
     let expanded = quote! {
+        /// The methods served by the RPC handler, see `assert_commands_match!`.
+        pub const RPC_COMMANDS: &[&str] = &[#(#command_names),*];
+
         #[derive(serde::Deserialize)]
         pub struct RpcRequest {
             jsonrpc: String,
@@ -323,6 +344,9 @@ pub fn generate_lua_bindings(input: TokenStream) -> TokenStream {
                 // Check for our `#[wrap]` attribute
                 let has_wrap_attr = pat_type.attrs.iter().any(|attr| attr.path().is_ident("wrap"));
                 if has_wrap_attr {
+                    if is_option_type(&pat_type.ty) {
+                        return quote! { #pat.map(|wrapped| wrapped.0) };
+                    }
                     return quote! { #pat.0 };
                 }
                 return quote! { #pat };
@@ -366,7 +390,11 @@ pub fn generate_lua_bindings(input: TokenStream) -> TokenStream {
         }
     });
 
+    let command_names = command_names(&mappings);
     let expanded = quote! {
+        /// The functions bound on the `pesa` table, see `assert_commands_match!`.
+        pub const LUA_COMMANDS: &[&str] = &[#(#command_names),*];
+
         fn register_lua_bindings(lua: &::mlua::Lua) -> ::mlua::Result<()> {
             let globals = lua.globals();
             let pesa_table: ::mlua::Table = globals.get("pesa")?;
@@ -443,3 +471,73 @@ pub fn wrap_core_types(input: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+struct CommandLists {
+    left: syn::Expr,
+    _comma: Token![,],
+    right: syn::Expr,
+}
+
+impl Parse for CommandLists {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(CommandLists {
+            left: input.parse()?,
+            _comma: input.parse()?,
+            right: input.parse()?,
+        })
+    }
+}
+
+/// Fails the build when two command lists, e.g. `RPC_COMMANDS` and `LUA_COMMANDS`, differ.
+///
+/// The evaluation error names the first command found in only one of the lists.
+#[proc_macro]
+pub fn assert_commands_match(input: TokenStream) -> TokenStream {
+    let CommandLists { left, right, .. } = parse_macro_input!(input as CommandLists);
+    let expanded = quote! {
+        const _: () = {
+            const fn same(a: &str, b: &str) -> bool {
+                let (a, b) = (a.as_bytes(), b.as_bytes());
+                if a.len() != b.len() {
+                    return false;
+                }
+                let mut i = 0;
+                while i < a.len() {
+                    if a[i] != b[i] {
+                        return false;
+                    }
+                    i += 1;
+                }
+                true
+            }
+
+            /// The first command of `list` missing from `other`.
+            const fn missing(list: &[&'static str], other: &[&str]) -> Option<&'static str> {
+                let mut i = 0;
+                while i < list.len() {
+                    let mut found = false;
+                    let mut j = 0;
+                    while j < other.len() {
+                        found = found || same(list[i], other[j]);
+                        j += 1;
+                    }
+                    if !found {
+                        return Some(list[i]);
+                    }
+                    i += 1;
+                }
+                None
+            }
+
+            if let Some(command) = missing(#left, #right) {
+                // const panics can only print the name itself
+                panic!("{}", command);
+            }
+            if let Some(command) = missing(#right, #left) {
+                panic!("{}", command);
+            }
+        };
+    };
+
+    TokenStream::from(expanded)
+}
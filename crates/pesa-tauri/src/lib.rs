@@ -7,20 +7,26 @@ use pesa_core::{
     api_logs::{UpdateApiLogRequest, ui::ApiLogFilter},
     business::{CreateBusiness, UpdateBusiness},
     business_operators::ui::CreateOperatorPayload,
+    inbox::{CannedResponse, ui::InboxFilter},
+    logs::{RetentionPolicy, ui::LogSearch},
     projects::{CreateProject, UpdateProject},
-    self_test::context::TestMode,
+    recording::replay::ReplayOptions,
+    self_test::{
+        context::TestMode,
+        ui::{ContractTestOptions, SelfTestOptions},
+    },
     server::api::stkpush::ui::UserResponse,
     settings::models::AppSettings,
-    transaction_costs::ui::TransactionCostData,
+    transaction_costs::ui::{TariffExport, TariffImport, TransactionCostData},
     transactions::{
-        TransactionNote, TransactionType,
+        ReversalOptions, TransactionNote, TransactionType,
         ui::{LipaArgs, TransactionFilter},
     },
     transactions_log::ui::HistoryFilter,
 };
 
 use pesa_lua::ScriptManager;
-use pesa_macros::generate_tauri_wrappers;
+use pesa_macros::{assert_commands_match, generate_tauri_wrappers};
 use std::sync::Arc;
 use tauri::{Emitter, Manager, Runtime, State};
 use tokio::sync::{Mutex, broadcast};
@@ -125,7 +131,9 @@ generate_tauri_wrappers! {
     get_recent_transactions(limit: Option<u32>) => pesa_core::transactions::ui::get_recent_transactions,
     get_transaction_stats() => pesa_core::transactions::ui::get_transaction_stats,
     transfer(source: Option<u32>, destination: u32, amount: i64, txn_type: TransactionType, notes: Option<TransactionNote>) => pesa_core::transactions::ui::transfer,
-    reverse(id: String) => pesa_core::transactions::ui::reverse,
+    reverse(id: String, options: Option<ReversalOptions>) => pesa_core::transactions::ui::reverse,
+    complete_reversal(id: String) => pesa_core::transactions::ui::complete_reversal,
+    cancel_reversal(id: String) => pesa_core::transactions::ui::cancel_reversal,
     lipa(args: LipaArgs) => pesa_core::transactions::ui::lipa,
 
     get_transaction_log(transaction_id: u32) => pesa_core::transactions_log::ui::get_transaction_log,
@@ -135,6 +143,9 @@ generate_tauri_wrappers! {
     list_accounts_full_transaction_logs(accounts: Vec<u32>, limit: Option<u64>, offset: Option<u64>) => pesa_core::transactions_log::ui::list_accounts_full_transaction_logs,
     count_transaction_logs(accounts: Vec<u32>) => pesa_core::transactions_log::ui::count_transaction_logs,
 
+    audit_ledger() => pesa_core::journal::ui::audit_ledger,
+    get_journal_entries(transaction_id: String) => pesa_core::journal::ui::get_journal_entries,
+
     get_api_log(log_id: String) => pesa_core::api_logs::ui::get_api_log,
     update_api_log(log_id: String, request: UpdateApiLogRequest) => pesa_core::api_logs::ui::update_api_log,
     delete_api_log(log_id: String) => pesa_core::api_logs::ui::delete_api_log,
@@ -142,12 +153,41 @@ generate_tauri_wrappers! {
     count_api_logs(project_id: Option<i64>, method: Option<String>, path: Option<String>, status_code: Option<i32>) => pesa_core::api_logs::ui::count_api_logs,
     get_project_api_logs(project_id: u32, filter: ApiLogFilter) => pesa_core::api_logs::ui::get_project_api_logs,
     get_api_logs_by_method(project_id: u32, method: String, limit: Option<u64>) => pesa_core::api_logs::ui::get_api_logs_by_method,
+    export_api_logs_har(filter: ApiLogFilter) => pesa_core::api_logs::ui::export_api_logs_har,
+    export_api_log_har(log_id: String) => pesa_core::api_logs::ui::export_api_log_har,
+    export_api_log_curl(log_id: String) => pesa_core::api_logs::ui::export_api_log_curl,
+
+    get_retention_policy(project_id: u32) => pesa_core::logs::ui::get_retention_policy,
+    set_retention_policy(policy: RetentionPolicy) => pesa_core::logs::ui::set_retention_policy,
+    clear_retention_policy(project_id: u32) => pesa_core::logs::ui::clear_retention_policy,
+    prune_logs(project_id: Option<u32>) => pesa_core::logs::ui::prune_logs,
+    search_logs(search: LogSearch) => pesa_core::logs::ui::search_logs,
+    get_request_timeline(id: String) => pesa_core::timeline::ui::get_request_timeline,
+
+    list_inboxes(project_id: u32) => pesa_core::inbox::ui::list_inboxes,
+    list_inbox_requests(filter: InboxFilter) => pesa_core::inbox::ui::list_inbox_requests,
+    clear_inbox(project_id: u32, inbox: Option<String>) => pesa_core::inbox::ui::clear_inbox,
+    get_inbox_response(project_id: u32, inbox: String) => pesa_core::inbox::ui::get_inbox_response,
+    set_inbox_response(project_id: u32, inbox: String, response: CannedResponse) => pesa_core::inbox::ui::set_inbox_response,
+    get_inbox_url(project_id: u32, inbox: String) => pesa_core::inbox::ui::get_inbox_url,
+
+    start_recording(project_id: u32, name: Option<String>) => pesa_core::recording::ui::start_recording,
+    stop_recording(project_id: u32) => pesa_core::recording::ui::stop_recording,
+    recording_status(project_id: u32) => pesa_core::recording::ui::recording_status,
+    list_sessions() => pesa_core::recording::ui::list_sessions,
+    get_session(name: String) => pesa_core::recording::ui::get_session,
+    delete_session(name: String) => pesa_core::recording::ui::delete_session,
+    replay_session(options: ReplayOptions) => pesa_core::recording::ui::replay_session,
 
     create_transaction_cost(data: TransactionCostData) => pesa_core::transaction_costs::ui::create_transaction_cost,
     list_transaction_costs() => pesa_core::transaction_costs::ui::list_transaction_costs,
     update_transaction_cost(id: i32, data: TransactionCostData) => pesa_core::transaction_costs::ui::update_transaction_cost,
     delete_transaction_cost(id: i32) => pesa_core::transaction_costs::ui::delete_transaction_cost,
-    calculate_transaction_fee(txn_type: TransactionType, amount: i64) => pesa_core::transaction_costs::ui::calculate_transaction_fee,
+    calculate_transaction_fee(txn_type: TransactionType, amount: i64, business_id: Option<u32>) => pesa_core::transaction_costs::ui::calculate_transaction_fee,
+    list_tariff_versions() => pesa_core::transaction_costs::ui::list_tariff_versions,
+    import_tariff_schedule(input: TariffImport) => pesa_core::transaction_costs::ui::import_tariff_schedule,
+    export_tariff_schedule(input: TariffExport) => pesa_core::transaction_costs::ui::export_tariff_schedule,
+    preview_tariff_schedule(input: TariffImport) => pesa_core::transaction_costs::ui::preview_tariff_schedule,
 
     resolve_stk_prompt(checkout_id: String, result: UserResponse) => pesa_core::server::api::stkpush::ui::resolve_stk_prompt,
     #[no_context]
@@ -164,9 +204,15 @@ generate_tauri_wrappers! {
     get_mmf_account_by_business_id(business_id: u32) => pesa_core::accounts::mmf_accounts::ui::get_mmf_account_by_business_id,
     revenue_settlement(business_id: u32) => pesa_core::business::ui::revenue_settlement,
 
-    run_self_tests(mode: TestMode) => pesa_core::self_test::ui::run_self_tests
+    run_self_tests(mode: TestMode) => pesa_core::self_test::ui::run_self_tests,
+    run_selected_self_tests(options: SelfTestOptions) => pesa_core::self_test::ui::run_selected_self_tests,
+    list_self_tests() => pesa_core::self_test::ui::list_self_tests,
+    run_contract_tests(options: ContractTestOptions) => pesa_core::self_test::ui::run_contract_tests,
+    list_contract_tests() => pesa_core::self_test::ui::list_contract_tests
 }
 
+assert_commands_match!(TAURI_COMMANDS, pesa_lua::manager::LUA_COMMANDS);
+
 #[tauri::command]
 async fn scripts_list(state: State<'_, TauriAppState>) -> Result<Vec<String>, String> {
     let manager = state.script_manager.lock().await;
@@ -209,6 +255,25 @@ async fn scripts_execute(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+async fn scripts_run(name: String, state: State<'_, TauriAppState>) -> Result<(), String> {
+    // scripts may run for a while, don't hold the lock meanwhile
+    let manager = state.script_manager.lock().await.clone();
+    manager.run_script(&name).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn scripts_unload(name: String, state: State<'_, TauriAppState>) -> Result<bool, String> {
+    let manager = state.script_manager.lock().await;
+    Ok(manager.unload_script(&name))
+}
+
+#[tauri::command]
+async fn scripts_loaded(state: State<'_, TauriAppState>) -> Result<Vec<String>, String> {
+    let manager = state.script_manager.lock().await;
+    Ok(manager.loaded_scripts())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let app = tauri::Builder::default()
@@ -244,12 +309,24 @@ pub fn run() {
                     event_manager: event_manager.clone(),
                     running: Arc::new(pesa_core::dashmap::DashMap::new()),
                     app_root: app_dir.clone(),
+                    interceptors: Default::default(),
+                    callback_hooks: Default::default(),
+                    recordings: Default::default(),
+                    metrics: Default::default(),
                 };
 
                 // Initialize ScriptManager
                 let script_manager = ScriptManager::new(context.clone(), &app_dir)
                     .expect("Failed to initialize script manager");
 
+                if let Err(e) = script_manager.lock().await.autorun().await {
+                    eprintln!("Failed to run autorun scripts: {:?}", e);
+                }
+                tauri::async_runtime::spawn(pesa_lua::watch::watch(
+                    script_manager.clone(),
+                    pesa_lua::watch::WATCH_INTERVAL,
+                ));
+
                 let script_manager_clone = script_manager.clone();
                 let mut script_event_receiver = event_sender.subscribe();
 
@@ -261,7 +338,7 @@ pub fn run() {
                                     event_payload["event"].as_str(),
                                     event_payload["payload"].as_object(),
                                 ) {
-                                    let sm = script_manager_clone.lock().await;
+                                    let sm = script_manager_clone.lock().await.clone();
                                     sm.emit_event(
                                         event_name,
                                         serde_json::Value::Object(payload.clone()),
@@ -298,6 +375,9 @@ pub fn run() {
             scripts_save,
             scripts_delete,
             scripts_execute,
+            scripts_run,
+            scripts_unload,
+            scripts_loaded,
             // Core Commands
             start_sandbox,
             stop_sandbox,
@@ -346,6 +426,8 @@ pub fn run() {
             get_transaction_stats,
             transfer,
             reverse,
+            complete_reversal,
+            cancel_reversal,
             lipa,
             get_transaction_log,
             get_full_transaction_log,
@@ -353,6 +435,8 @@ pub fn run() {
             get_transaction_history,
             list_accounts_full_transaction_logs,
             count_transaction_logs,
+            audit_ledger,
+            get_journal_entries,
             get_api_log,
             update_api_log,
             delete_api_log,
@@ -360,11 +444,37 @@ pub fn run() {
             count_api_logs,
             get_project_api_logs,
             get_api_logs_by_method,
+            export_api_logs_har,
+            export_api_log_har,
+            export_api_log_curl,
+            get_retention_policy,
+            set_retention_policy,
+            clear_retention_policy,
+            prune_logs,
+            search_logs,
+            get_request_timeline,
+            list_inboxes,
+            list_inbox_requests,
+            clear_inbox,
+            get_inbox_response,
+            set_inbox_response,
+            get_inbox_url,
+            start_recording,
+            stop_recording,
+            recording_status,
+            list_sessions,
+            get_session,
+            delete_session,
+            replay_session,
             create_transaction_cost,
             list_transaction_costs,
             update_transaction_cost,
             delete_transaction_cost,
             calculate_transaction_fee,
+            list_tariff_versions,
+            import_tariff_schedule,
+            export_tariff_schedule,
+            preview_tariff_schedule,
             resolve_stk_prompt,
             get_app_info,
             get_account,
@@ -375,7 +485,11 @@ pub fn run() {
             get_mmf_account_by_business_id,
             get_utility_account_by_business_id,
             revenue_settlement,
-            run_self_tests
+            run_self_tests,
+            run_selected_self_tests,
+            list_self_tests,
+            run_contract_tests,
+            list_contract_tests
         ]);
 
     app.run(tauri::generate_context!())
@@ -367,6 +367,12 @@ export enum SimulationMode {
 	Random = 'Random'
 }
 
+/** How closely a sandbox holds requests to Daraja's rules. */
+export enum ValidationMode {
+	Lenient = 'Lenient',
+	Strict = 'Strict'
+}
+
 /**
  * Represents the data structure for creating a new project.
  * Corresponds to `ProjectData` in Rust.
@@ -376,6 +382,7 @@ export interface ProjectData {
 	name: string;
 	callback_url?: string;
 	simulation_mode: SimulationMode;
+	validation_mode?: ValidationMode;
 	stk_delay: number;
 	prefix?: string;
 }
@@ -390,6 +397,7 @@ export interface UpdateProjectData {
 	name?: string;
 	callback_url?: string;
 	simulation_mode?: SimulationMode;
+	validation_mode?: ValidationMode;
 	stk_delay?: number;
 	prefix?: string;
 }
@@ -400,6 +408,7 @@ export interface Project {
 	name: string;
 	callback_url?: string;
 	simulation_mode: SimulationMode;
+	validation_mode: ValidationMode;
 	stk_delay: number;
 	prefix?: string;
 	created_at: string;
@@ -415,6 +424,7 @@ export interface ProjectDetails {
 	name: string;
 	callback_url?: string;
 	simulation_mode: SimulationMode;
+	validation_mode: ValidationMode;
 	stk_delay: number;
 	prefix?: string;
 	created_at: string;
@@ -609,6 +619,7 @@ export interface Transaction {
 	created_at: string;
 	updated_at?: string;
 	notes?: TransactionNote;
+	conversation_id?: string;
 }
 
 // Filter interface for frontend use
@@ -652,6 +663,14 @@ export async function reverse(id: String): Promise<Transaction> {
 	return await invoke('reverse', { id });
 }
 
+export async function completeReversal(id: string): Promise<Transaction> {
+	return await invoke('complete_reversal', { id });
+}
+
+export async function cancelReversal(id: string): Promise<Transaction> {
+	return await invoke('cancel_reversal', { id });
+}
+
 export async function getTransaction(transaction_id: string): Promise<Transaction | null> {
 	return await invoke('get_transaction', { transactionId: transaction_id });
 }
@@ -692,6 +711,8 @@ export async function getTransactionStats(projectId?: number): Promise<Transacti
 	return await invoke('get_transaction_stats', { projectId });
 }
 
+export type FeePayer = 'customer' | 'business';
+
 export interface TransactionCost {
 	id: number;
 	transaction_type: string;
@@ -699,6 +720,8 @@ export interface TransactionCost {
 	max_amount: number;
 	fee_fixed?: number;
 	fee_percentage?: number;
+	business_id?: number;
+	paid_by: FeePayer;
 }
 
 export interface TransactionCostData {
@@ -707,6 +730,13 @@ export interface TransactionCostData {
 	max_amount: number;
 	fee_fixed?: number;
 	fee_percentage?: number;
+	business_id?: number;
+	paid_by?: FeePayer;
+}
+
+export interface AppliedFee {
+	fee: number;
+	paid_by: FeePayer;
 }
 
 export async function createTransactionCost(data: TransactionCostData): Promise<TransactionCost> {
@@ -728,13 +758,60 @@ export async function deleteTransactionCost(id: number): Promise<void> {
 	return await invoke('delete_transaction_cost', { id });
 }
 
-export async function calculateTransactionFee(txnType: TransactionType, amount: number) {
+export async function calculateTransactionFee(
+	txnType: TransactionType,
+	amount: number,
+	businessId?: number
+): Promise<AppliedFee> {
 	return await invoke('calculate_transaction_fee', {
 		txnType,
-		amount
+		amount,
+		businessId
 	});
 }
 
+export type TariffFormat = 'csv' | 'json';
+
+export interface TariffSchedule {
+	effective_from?: string;
+	bands: TransactionCostData[];
+}
+
+export interface TariffVersion {
+	effective_from?: string;
+	bands: number;
+}
+
+export interface TariffPreview {
+	transaction_type: string;
+	business_id?: number;
+	amount: number;
+	current: AppliedFee;
+	candidate: AppliedFee;
+}
+
+export interface TariffImport {
+	format: TariffFormat;
+	content: string;
+	effective_from?: string;
+}
+
+export async function importTariffSchedule(input: TariffImport): Promise<TariffSchedule> {
+	return await invoke('import_tariff_schedule', { input });
+}
+
+export async function exportTariffSchedule(format: TariffFormat, at?: string): Promise<string> {
+	return await invoke('export_tariff_schedule', { input: { format, at } });
+}
+
+export async function previewTariffSchedule(input: TariffImport): Promise<TariffPreview[]> {
+	return await invoke('preview_tariff_schedule', { input });
+}
+
+export async function listTariffVersions(): Promise<TariffVersion[]> {
+	return await invoke('list_tariff_versions');
+}
+
 export interface FullTransactionLog {
 	transaction_id: string;
 	transaction_date: string;
@@ -833,11 +910,13 @@ export interface ApiLog {
 	project_id: number;
 	method: string;
 	path: string;
+	query?: string;
 	status_code: number;
 	request_body?: string;
 	response_body?: string;
 	error_desc?: string;
 	created_at: string;
+	conversation_id?: string;
 }
 
 export enum LogLevel {
@@ -863,6 +942,8 @@ export interface AppSettings {
 	server_log_level: LogLevel;
 	encryption_keys?: EncryptionKeys;
 	custom_keymaps?: Record<string, string> | null;
+	/** Hosts Lua scripts may reach with `pesa.http`, `*.example.com` allows subdomains. */
+	script_http_allowlist: string[];
 }
 
 export async function getSettings(): Promise<AppSettings> {
@@ -883,6 +964,8 @@ export interface ApiLogFilter {
 	method?: string;
 	path?: string;
 	status_code?: number;
+	/** Full-text search over the bodies and error, at least 3 characters per term */
+	search?: string;
 	limit?: number;
 	offset?: number;
 }
@@ -938,6 +1021,286 @@ export async function listApiLogs(filter: ApiLogFilter = {}): Promise<ApiLog[]>
 	return await invoke('list_api_logs', { filter });
 }
 
+/** A HAR 1.2 archive, see http://www.softwareishard.com/blog/har-12-spec/ */
+export interface Har {
+	log: {
+		version: string;
+		creator: { name: string; version: string };
+		entries: any[];
+	};
+}
+
+export async function exportApiLogsHar(filter: ApiLogFilter = {}): Promise<Har> {
+	return await invoke('export_api_logs_har', { filter });
+}
+
+export async function exportApiLogHar(log_id: string): Promise<Har | null> {
+	return await invoke('export_api_log_har', { logId: log_id });
+}
+
+export async function exportApiLogCurl(log_id: string): Promise<string | null> {
+	return await invoke('export_api_log_curl', { logId: log_id });
+}
+
+export interface RetentionPolicy {
+	project_id: number;
+	max_age_days?: number;
+	max_count?: number;
+}
+
+export interface PruneReport {
+	api_logs: number;
+	callback_logs: number;
+}
+
+export interface CallbackLog {
+	id: number;
+	project_id: number;
+	conversation_id: string;
+	originator_id: string;
+	transaction_id?: string;
+	callback_url: string;
+	callback_type: string;
+	payload: any;
+	response_status?: number;
+	response_body?: string;
+	response_headers?: any;
+	status: string;
+	error?: string;
+	hook_decision?: string;
+	original_payload?: any;
+	created_at: string;
+	updated_at?: string;
+}
+
+export interface LogSearch {
+	project_id?: number;
+	query: string;
+	limit?: number;
+	offset?: number;
+}
+
+export interface LogSearchResults {
+	api_logs: ApiLog[];
+	callback_logs: CallbackLog[];
+}
+
+export async function getRetentionPolicy(projectId: number): Promise<RetentionPolicy | null> {
+	return await invoke('get_retention_policy', { projectId });
+}
+
+export async function setRetentionPolicy(policy: RetentionPolicy): Promise<PruneReport> {
+	return await invoke('set_retention_policy', { policy });
+}
+
+export async function clearRetentionPolicy(projectId: number): Promise<boolean> {
+	return await invoke('clear_retention_policy', { projectId });
+}
+
+export async function pruneLogs(projectId?: number): Promise<PruneReport> {
+	return await invoke('prune_logs', { projectId });
+}
+
+export async function searchLogs(search: LogSearch): Promise<LogSearchResults> {
+	return await invoke('search_logs', { search });
+}
+
+export type TimelineEvent =
+	| {
+			kind: 'request_received';
+			log_id: string;
+			method: string;
+			path: string;
+			status_code: number;
+			error?: string;
+	  }
+	| { kind: 'prompt_shown'; detail: any }
+	| { kind: 'user_responded'; detail: any }
+	| {
+			kind: 'transaction_posted';
+			transaction_id: string;
+			transaction_type: string;
+			amount: number;
+			fee: number;
+			status: string;
+	  }
+	| {
+			kind: 'ledger_entry';
+			transaction_id: string;
+			account_id: number;
+			side: 'Debit' | 'Credit';
+			amount: number;
+	  }
+	| { kind: 'callback_sent'; callback_id: number; callback_type: string; url: string; payload: any }
+	| {
+			kind: 'callback_response';
+			callback_id: number;
+			status: string;
+			response_status?: number;
+			response_body?: string;
+			error?: string;
+	  };
+
+export interface RequestTimeline {
+	conversation_id: string;
+	project_id?: number;
+	entries: ({ at: string } & TimelineEvent)[];
+}
+
+/** Takes a conversation id, API log id, transaction receipt, CheckoutRequestID or OriginatorConversationID */
+export async function getRequestTimeline(id: string): Promise<RequestTimeline | null> {
+	return await invoke('get_request_timeline', { id });
+}
+
+export interface InboxRequest {
+	id: number;
+	project_id: number;
+	inbox: string;
+	method: string;
+	query?: string;
+	headers: Record<string, string>;
+	body: any;
+	response_status: number;
+	created_at: string;
+}
+
+export type CannedResponse =
+	| { kind: 'accept' }
+	| { kind: 'reject'; result_code?: string; result_desc?: string }
+	| { kind: 'custom'; status: number; headers?: Record<string, string>; body?: any };
+
+export interface InboxSummary {
+	name: string;
+	requests: number;
+	response: CannedResponse;
+}
+
+export interface InboxFilter {
+	project_id: number;
+	inbox?: string;
+	limit?: number;
+	offset?: number;
+}
+
+export async function listInboxes(projectId: number): Promise<InboxSummary[]> {
+	return await invoke('list_inboxes', { projectId });
+}
+
+export async function listInboxRequests(filter: InboxFilter): Promise<InboxRequest[]> {
+	return await invoke('list_inbox_requests', { filter });
+}
+
+export async function clearInbox(projectId: number, inbox?: string): Promise<number> {
+	return await invoke('clear_inbox', { projectId, inbox });
+}
+
+export async function getInboxResponse(projectId: number, inbox: string): Promise<CannedResponse> {
+	return await invoke('get_inbox_response', { projectId, inbox });
+}
+
+export async function setInboxResponse(
+	projectId: number,
+	inbox: string,
+	response: CannedResponse
+): Promise<void> {
+	return await invoke('set_inbox_response', { projectId, inbox, response });
+}
+
+export async function getInboxUrl(projectId: number, inbox: string): Promise<string | null> {
+	return await invoke('get_inbox_url', { projectId, inbox });
+}
+
+export interface SessionSummary {
+	name: string;
+	project_id: number;
+	started_at: string;
+	stopped_at?: string;
+	requests: number;
+	callbacks: number;
+	ledger_entries: number;
+}
+
+export type SessionEvent =
+	| {
+			kind: 'request';
+			method: string;
+			uri: string;
+			headers: Record<string, string>;
+			body?: string;
+			status: number;
+			response?: string;
+	  }
+	| { kind: 'ledger'; transaction: any }
+	| { kind: 'prompt'; decision: any }
+	| {
+			kind: 'callback';
+			callback_type: string;
+			url: string;
+			payload: any;
+			status: string;
+			response_status?: number;
+			error?: string;
+	  };
+
+export interface Session {
+	name: string;
+	project_id: number;
+	started_at: string;
+	stopped_at?: string;
+	snapshot: string;
+	entries: ({ offset_ms: number } & SessionEvent)[];
+}
+
+export interface ReplayOptions {
+	session: string;
+	ignore_timing?: boolean;
+	settle_ms?: number;
+}
+
+export interface Divergence {
+	kind: 'response' | 'callback' | 'missing_callback' | 'unexpected_callback' | 'ledger';
+	index: number;
+	target: string;
+	expected: any;
+	actual: any;
+}
+
+export interface ReplayReport {
+	session: string;
+	requests: number;
+	callbacks: number;
+	unanswered_prompts: number;
+	divergences: Divergence[];
+}
+
+export async function startRecording(projectId: number, name?: string): Promise<SessionSummary> {
+	return await invoke('start_recording', { projectId, name });
+}
+
+export async function stopRecording(projectId: number): Promise<SessionSummary> {
+	return await invoke('stop_recording', { projectId });
+}
+
+export async function recordingStatus(projectId: number): Promise<SessionSummary | null> {
+	return await invoke('recording_status', { projectId });
+}
+
+export async function listSessions(): Promise<SessionSummary[]> {
+	return await invoke('list_sessions');
+}
+
+export async function getSession(name: string): Promise<Session> {
+	return await invoke('get_session', { name });
+}
+
+export async function deleteSession(name: string): Promise<boolean> {
+	return await invoke('delete_session', { name });
+}
+
+export async function replaySession(options: ReplayOptions): Promise<ReplayReport> {
+	return await invoke('replay_session', { options });
+}
+
 export async function listRunningSandboxes(): Promise<any[]> {
 	return await invoke('list_running_sandboxes');
 }
@@ -1124,10 +1487,53 @@ export interface TestStepInfo extends TestStep {
 	logs: string[];
 }
 
+export interface SelfTestInfo extends TestStep {
+	tags: string[];
+	depends_on: string[];
+}
+
+export interface SelfTestOptions {
+	mode: TestMode;
+	names?: string[];
+	tags?: string[];
+	continue_on_failure?: boolean;
+}
+
 export async function startSelfTest(mode: TestMode) {
 	await invoke('run_self_tests', { mode });
 }
 
+export async function startSelectedSelfTests(options: SelfTestOptions) {
+	await invoke('run_selected_self_tests', { options });
+}
+
+export async function listSelfTests(): Promise<SelfTestInfo[]> {
+	return await invoke('list_self_tests');
+}
+
+export interface ContractTarget {
+	base_url: string;
+	stk_callback_path?: string;
+	b2c_result_path?: string;
+	c2b_validation_path?: string;
+	c2b_confirmation_path?: string;
+}
+
+export interface ContractTestOptions {
+	target: ContractTarget;
+	names?: string[];
+	tags?: string[];
+	continue_on_failure?: boolean;
+}
+
+export async function startContractTests(options: ContractTestOptions) {
+	await invoke('run_contract_tests', { options });
+}
+
+export async function listContractTests(): Promise<SelfTestInfo[]> {
+	return await invoke('list_contract_tests');
+}
+
 export enum TestEvents {
 	Start = 'self_test_start',
 	Plan = 'self_test_plan',
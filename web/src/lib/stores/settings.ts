@@ -6,7 +6,8 @@ import { toast } from 'svelte-sonner';
 export const defaultAppSettings: AppSettings = {
 	theme: Theme.Dark,
 	server_log_level: LogLevel.Info,
-	custom_keymaps: null
+	custom_keymaps: null,
+	script_http_allowlist: ['localhost', '127.0.0.1', '::1']
 };
 
 type SettingsStore = Writable<AppSettings> & {